@@ -0,0 +1,7 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+// Applies the operations encoded by the input to `BitSet` and `BTreeSet`,
+// panicking at the first difference
+fuzz_target!(|data: &[u8]| bit_set::oracle::check_ops(data));
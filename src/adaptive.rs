@@ -0,0 +1,485 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A set whose storage adapts to the density of each range of values, in
+//! the style of Roaring bitmaps.
+
+use alloc::boxed::Box;
+use alloc::vec::Vec;
+use core::fmt;
+use core::hash;
+use core::iter::{Cloned, FromIterator};
+use core::ops::Range;
+use core::slice;
+
+use bit_vec::BitBlock;
+
+use {BitSet, BlockIter};
+
+/// Containers with at most this many values are stored as sorted arrays.
+const ARRAY_MAX_LEN: usize = 4096;
+const BITMAP_WORDS: usize = 1024;
+
+type Bitmap = Box<[u64; BITMAP_WORDS]>;
+
+/// The values of one range of 2^16 values, as their low 16 bits.
+#[derive(Clone)]
+enum Container {
+    /// Sorted values.
+    Array(Vec<u16>),
+    /// One bit per value, with the number of values.
+    Bitmap(Bitmap, usize),
+    /// Sorted, disjoint runs of `len - 1` values after `start`, as
+    /// `(start, len - 1)`.
+    Run(Vec<(u16, u16)>),
+}
+
+fn empty_bitmap() -> Bitmap {
+    Box::new([0; BITMAP_WORDS])
+}
+
+impl Container {
+    /// Builds the smaller of an array or bitmap container from a bitmap.
+    fn from_bitmap(words: Bitmap) -> Option<Container> {
+        let len: usize = words.iter().map(|w| w.count_ones() as usize).sum();
+        if len == 0 {
+            None
+        } else if len <= ARRAY_MAX_LEN {
+            let values = BlockIter::from_blocks(words.iter().cloned()).map(|v| v as u16);
+            Some(Container::Array(values.collect()))
+        } else {
+            Some(Container::Bitmap(words, len))
+        }
+    }
+
+    fn to_bitmap(&self) -> Bitmap {
+        let mut words = empty_bitmap();
+        match *self {
+            Container::Bitmap(ref bitmap, _) => words.copy_from_slice(&bitmap[..]),
+            _ => for value in self.iter() {
+                words[value / 64] |= 1 << (value % 64);
+            },
+        }
+        words
+    }
+
+    fn len(&self) -> usize {
+        match *self {
+            Container::Array(ref values) => values.len(),
+            Container::Bitmap(_, len) => len,
+            Container::Run(ref runs) => runs.iter().map(|&(_, len)| len as usize + 1).sum(),
+        }
+    }
+
+    fn contains(&self, low: u16) -> bool {
+        match *self {
+            Container::Array(ref values) => values.binary_search(&low).is_ok(),
+            Container::Bitmap(ref words, _) => words[low as usize / 64] & (1 << (low % 64)) != 0,
+            Container::Run(ref runs) => {
+                let i = runs.partition_point(|&(start, _)| start <= low);
+                i > 0 && low - runs[i - 1].0 <= runs[i - 1].1
+            }
+        }
+    }
+
+    /// Inserts or removes `low`, returning `true` if the container changed.
+    /// Run containers are expanded first.
+    fn set(&mut self, low: u16, value: bool) -> bool {
+        if let Container::Run(_) = *self {
+            let expanded = Container::from_bitmap(self.to_bitmap());
+            *self = expanded.expect("run containers are never empty");
+        }
+        let changed = match *self {
+            Container::Array(ref mut values) => match (values.binary_search(&low), value) {
+                (Err(i), true) => { values.insert(i, low); true }
+                (Ok(i), false) => { values.remove(i); true }
+                _ => false,
+            },
+            Container::Bitmap(ref mut words, ref mut len) => {
+                let (word, mask) = (&mut words[low as usize / 64], 1 << (low % 64));
+                let changed = (*word & mask != 0) != value;
+                if changed {
+                    *word ^= mask;
+                    if value { *len += 1 } else { *len -= 1 }
+                }
+                changed
+            }
+            Container::Run(_) => unreachable!(),
+        };
+        // Switch representation when crossing the array size limit
+        let len = self.len();
+        if (len > ARRAY_MAX_LEN) == matches!(*self, Container::Array(_)) {
+            if let Some(container) = Container::from_bitmap(self.to_bitmap()) {
+                *self = container;
+            }
+        }
+        changed
+    }
+
+    /// Returns the runs of the container, as `(start, len - 1)`.
+    fn runs(&self) -> Vec<(u16, u16)> {
+        let mut runs: Vec<(u16, u16)> = Vec::new();
+        for value in self.iter() {
+            match runs.last_mut() {
+                Some(&mut (start, ref mut len)) if start as usize + *len as usize + 1 == value => {
+                    *len += 1
+                }
+                _ => runs.push((value as u16, 0)),
+            }
+        }
+        runs
+    }
+
+    /// Switches to the representation taking the fewest bytes.
+    fn optimize(&mut self) {
+        let runs = self.runs();
+        let len = self.len();
+        let run_size = 2 + 4 * runs.len();
+        let other_size = if len <= ARRAY_MAX_LEN { 2 * len } else { 8 * BITMAP_WORDS };
+        if run_size < other_size {
+            *self = Container::Run(runs);
+        } else if let Container::Run(_) = *self {
+            *self = Container::from_bitmap(self.to_bitmap()).expect("containers are never empty");
+        }
+    }
+
+    fn iter(&self) -> ContainerIter<'_> {
+        match *self {
+            Container::Array(ref values) => ContainerIter::Array(values.iter()),
+            Container::Bitmap(ref words, _) =>
+                ContainerIter::Bitmap(BlockIter::from_blocks(words.iter().cloned())),
+            Container::Run(ref runs) => ContainerIter::Run(runs.iter(), 0..0),
+        }
+    }
+}
+
+/// A set of `usize`s that picks, for each range of 2^16 values holding at
+/// least one element, the most compact of three representations: a sorted
+/// array of up to 4096 values, a 8 KiB bitmap, or a list of runs.
+///
+/// Arrays and bitmaps are switched automatically as values are inserted and
+/// removed. Runs are only chosen by [optimize](#method.optimize), since they
+/// are expensive to update, and are expanded again when modified.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::AdaptiveBitSet;
+///
+/// let mut s: AdaptiveBitSet = (0..100_000).chain(Some(1 << 40)).collect();
+/// s.optimize();
+///
+/// assert_eq!(s.len(), 100_001);
+/// assert!(s.contains(99_999));
+/// assert!(s.contains(1 << 40));
+/// assert!(!s.contains(100_000));
+/// ```
+#[derive(Clone, Default)]
+pub struct AdaptiveBitSet {
+    /// Sorted high parts of the values, `value >> 16`.
+    keys: Vec<usize>,
+    containers: Vec<Container>,
+}
+
+impl AdaptiveBitSet {
+    /// Creates a new empty `AdaptiveBitSet`.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns `true` if this set contains the specified integer.
+    pub fn contains(&self, value: usize) -> bool {
+        match self.keys.binary_search(&(value >> 16)) {
+            Ok(i) => self.containers[i].contains(value as u16),
+            Err(_) => false,
+        }
+    }
+
+    /// Adds a value to the set. Returns `true` if the value was not already
+    /// present in the set.
+    pub fn insert(&mut self, value: usize) -> bool {
+        match self.keys.binary_search(&(value >> 16)) {
+            Ok(i) => self.containers[i].set(value as u16, true),
+            Err(i) => {
+                self.keys.insert(i, value >> 16);
+                self.containers.insert(i, Container::Array(alloc::vec![value as u16]));
+                true
+            }
+        }
+    }
+
+    /// Removes a value from the set. Returns `true` if the value was
+    /// present in the set.
+    pub fn remove(&mut self, value: usize) -> bool {
+        let i = match self.keys.binary_search(&(value >> 16)) {
+            Ok(i) => i,
+            Err(_) => return false,
+        };
+        let changed = self.containers[i].set(value as u16, false);
+        if self.containers[i].len() == 0 {
+            self.keys.remove(i);
+            self.containers.remove(i);
+        }
+        changed
+    }
+
+    /// Returns the number of values in the set.
+    pub fn len(&self) -> usize {
+        self.containers.iter().map(Container::len).sum()
+    }
+
+    /// Returns `true` if the set holds no value.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// Converts each container to the representation taking the fewest
+    /// bytes, including runs.
+    pub fn optimize(&mut self) {
+        for container in &mut self.containers {
+            container.optimize();
+        }
+    }
+
+    /// Combines the containers of both sets word by word with `merge`.
+    fn merge(&mut self, other: &Self, merge: fn(u64, u64) -> u64) {
+        let mut keys = Vec::new();
+        let mut containers = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.keys.len() || j < other.keys.len() {
+            let a_key = self.keys.get(i).cloned().unwrap_or(usize::MAX);
+            let b_key = other.keys.get(j).cloned().unwrap_or(usize::MAX);
+            let key = a_key.min(b_key);
+            let a = if a_key == key { i += 1; self.containers[i - 1].to_bitmap() } else { empty_bitmap() };
+            let b = if b_key == key { j += 1; other.containers[j - 1].to_bitmap() } else { empty_bitmap() };
+            let mut words = a;
+            for (a, &b) in words.iter_mut().zip(b.iter()) {
+                *a = merge(*a, b);
+            }
+            if let Some(container) = Container::from_bitmap(words) {
+                keys.push(key);
+                containers.push(container);
+            }
+        }
+        self.keys = keys;
+        self.containers = containers;
+    }
+
+    /// Unions in-place with the specified other set.
+    pub fn union_with(&mut self, other: &Self) {
+        self.merge(other, |a, b| a | b);
+    }
+
+    /// Intersects in-place with the specified other set.
+    pub fn intersect_with(&mut self, other: &Self) {
+        self.merge(other, |a, b| a & b);
+    }
+
+    /// Makes this set the difference with the specified other set in-place.
+    pub fn difference_with(&mut self, other: &Self) {
+        self.merge(other, |a, b| a & !b);
+    }
+
+    /// Makes this set the symmetric difference with the specified other set
+    /// in-place.
+    pub fn symmetric_difference_with(&mut self, other: &Self) {
+        self.merge(other, |a, b| a ^ b);
+    }
+
+    /// Iterator over each value in the set, in ascending order.
+    pub fn iter(&self) -> AdaptiveIter<'_> {
+        AdaptiveIter {
+            keys: self.keys.iter(),
+            containers: self.containers.iter(),
+            base: 0,
+            values: ContainerIter::Array([].iter()),
+        }
+    }
+
+    /// Returns a `BitSet` holding the values of this set.
+    pub fn to_bit_set<B: BitBlock>(&self) -> BitSet<B> {
+        self.iter().collect()
+    }
+}
+
+impl<'a, B: BitBlock> From<&'a BitSet<B>> for AdaptiveBitSet {
+    fn from(set: &'a BitSet<B>) -> Self {
+        set.iter().collect()
+    }
+}
+
+impl FromIterator<usize> for AdaptiveBitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = AdaptiveBitSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for AdaptiveBitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a AdaptiveBitSet {
+    type Item = usize;
+    type IntoIter = AdaptiveIter<'a>;
+
+    fn into_iter(self) -> AdaptiveIter<'a> {
+        self.iter()
+    }
+}
+
+impl PartialEq for AdaptiveBitSet {
+    fn eq(&self, other: &Self) -> bool {
+        // Equal containers may use different representations
+        self.keys == other.keys &&
+            self.containers.iter().zip(&other.containers).all(|(a, b)| a.iter().eq(b.iter()))
+    }
+}
+
+impl Eq for AdaptiveBitSet {}
+
+impl hash::Hash for AdaptiveBitSet {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        for value in self {
+            value.hash(state);
+        }
+    }
+}
+
+impl fmt::Debug for AdaptiveBitSet {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_set().entries(self).finish()
+    }
+}
+
+/// The low 16 bits of the values of a container.
+#[derive(Clone)]
+enum ContainerIter<'a> {
+    Array(slice::Iter<'a, u16>),
+    Bitmap(BlockIter<Cloned<slice::Iter<'a, u64>>, u64>),
+    Run(slice::Iter<'a, (u16, u16)>, Range<usize>),
+}
+
+impl<'a> Iterator for ContainerIter<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        match *self {
+            ContainerIter::Array(ref mut values) => values.next().map(|&v| v as usize),
+            ContainerIter::Bitmap(ref mut values) => values.next(),
+            ContainerIter::Run(ref mut runs, ref mut current) => {
+                if current.start == current.end {
+                    let &(start, len) = runs.next()?;
+                    *current = start as usize..start as usize + len as usize + 1;
+                }
+                current.next()
+            }
+        }
+    }
+}
+
+/// An iterator for `AdaptiveBitSet`.
+#[derive(Clone)]
+pub struct AdaptiveIter<'a> {
+    keys: slice::Iter<'a, usize>,
+    containers: slice::Iter<'a, Container>,
+    /// The first value of the current container.
+    base: usize,
+    values: ContainerIter<'a>,
+}
+
+impl<'a> Iterator for AdaptiveIter<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        loop {
+            if let Some(low) = self.values.next() {
+                return Some(self.base | low);
+            }
+            self.base = self.keys.next()? << 16;
+            self.values = self.containers.next()?.iter();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::{AdaptiveBitSet, Container};
+    use BitSet;
+
+    fn kinds(set: &AdaptiveBitSet) -> Vec<&'static str> {
+        set.containers.iter().map(|c| match *c {
+            Container::Array(_) => "array",
+            Container::Bitmap(..) => "bitmap",
+            Container::Run(_) => "run",
+        }).collect()
+    }
+
+    #[test]
+    fn test_adaptive_bit_set_switches_containers() {
+        let mut s: AdaptiveBitSet = (0..5000).map(|x| x * 2).collect();
+        s.insert(1 << 20);
+        assert_eq!(kinds(&s), ["bitmap", "array"]);
+
+        for x in 0..1000 {
+            assert!(s.remove(x * 2));
+        }
+        assert_eq!(kinds(&s), ["array", "array"]);
+        assert!(!s.remove(0));
+        assert!(s.remove(1 << 20));
+        assert_eq!(kinds(&s), ["array"]);
+
+        let mut runs: AdaptiveBitSet = (0..60_000).collect();
+        assert_eq!(kinds(&runs), ["bitmap"]);
+        runs.optimize();
+        assert_eq!(kinds(&runs), ["run"]);
+        assert!(runs.contains(59_999) && !runs.contains(60_000));
+        assert_eq!(runs.len(), 60_000);
+        // Equality ignores the representation
+        assert_eq!(runs, (0..60_000).collect());
+        assert!(runs.remove(100));
+        assert_eq!(kinds(&runs), ["bitmap"]);
+        assert_eq!(runs.len(), 59_999);
+    }
+
+    #[test]
+    fn test_adaptive_bit_set_operations() {
+        let a_values: Vec<usize> = (0..200_000).filter(|x| x % 3 == 0).collect();
+        let b_values: Vec<usize> = (100_000..300_000).filter(|x| x % 1000 < 500).collect();
+        let mut a: AdaptiveBitSet = a_values.iter().cloned().collect();
+        let mut b: AdaptiveBitSet = b_values.iter().cloned().collect();
+        b.optimize();
+        a.optimize();
+        let (da, db): (BitSet, BitSet) = (a.to_bit_set(), b.to_bit_set());
+        assert_eq!(a.iter().collect::<Vec<_>>(), a_values);
+        assert_eq!(b.iter().collect::<Vec<_>>(), b_values);
+        assert_eq!(AdaptiveBitSet::from(&da).iter().collect::<Vec<_>>(), a_values);
+
+        type Op<T> = fn(&mut T, &T);
+        let ops: [(Op<AdaptiveBitSet>, Op<BitSet>); 4] = [
+            (AdaptiveBitSet::union_with, BitSet::union_with),
+            (AdaptiveBitSet::intersect_with, BitSet::intersect_with),
+            (AdaptiveBitSet::difference_with, BitSet::difference_with),
+            (AdaptiveBitSet::symmetric_difference_with, BitSet::symmetric_difference_with),
+        ];
+        for &(adaptive_op, dense_op) in ops.iter() {
+            let mut x = a.clone();
+            adaptive_op(&mut x, &b);
+            let mut expected = da.clone();
+            dense_op(&mut expected, &db);
+            assert_eq!(x.to_bit_set::<u32>(), expected);
+        }
+    }
+}
@@ -0,0 +1,271 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! An allocator of small integer ids backed by a `BitSet`.
+
+use core::cmp;
+use core::ops::Range;
+
+use bit_vec::BitBlock;
+
+use {blocks_for_bits, find_bit, BitSet};
+
+/// Hands out the smallest free ids, tracking the ids in use in a `BitSet`.
+///
+/// A second, smaller set records which blocks of the first are full, so the
+/// searches for free ids skip the full blocks a block of them at a time
+/// wherever the ids were freed, instead of scanning every id in use.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BitSetAllocator;
+///
+/// let mut ids = BitSetAllocator::new();
+/// assert_eq!(ids.allocate(), 0);
+/// assert_eq!(ids.allocate(), 1);
+/// assert_eq!(ids.allocate_range(3), 2..5);
+///
+/// assert!(ids.free(1));
+/// assert_eq!(ids.allocate(), 1);
+/// assert_eq!(ids.allocate(), 5);
+/// ```
+#[derive(Clone, Debug)]
+pub struct BitSetAllocator<B: BitBlock = u32> {
+    used: BitSet<B>,
+    /// Holds `i` if the block at index `i` of `used` is full.
+    full: BitSet<B>,
+    len: usize,
+}
+
+impl<B: BitBlock> Default for BitSetAllocator<B> {
+    #[inline]
+    fn default() -> Self {
+        BitSetAllocator { used: BitSet::default(), full: BitSet::default(), len: 0 }
+    }
+}
+
+impl BitSetAllocator<u32> {
+    /// Creates a new allocator with no id in use.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: BitBlock> BitSetAllocator<B> {
+    /// Returns the smallest id at or after `from` not in use.
+    fn next_free(&self, mut from: usize) -> usize {
+        let blocks = self.used.bit_vec.storage();
+        let full = self.full.bit_vec.storage();
+        loop {
+            let i = find_bit(full, from / B::bits(), false)
+                .unwrap_or(cmp::max(from / B::bits(), full.len() * B::bits()));
+            if i >= blocks.len() {
+                return cmp::max(from, blocks.len() * B::bits());
+            }
+            // The free ids of the block may all be before `from`
+            from = cmp::max(from, i * B::bits());
+            match find_bit(&blocks[..i + 1], from, false) {
+                Some(id) => return id,
+                None => from = (i + 1) * B::bits(),
+            }
+        }
+    }
+
+    /// Records which of the blocks of `used` holding `ids` are full.
+    fn update_full(&mut self, ids: Range<usize>) {
+        let first = ids.start / B::bits();
+        let blocks = &self.used.bit_vec.storage()[first..blocks_for_bits::<B>(ids.end)];
+        for (i, &block) in (first..).zip(blocks) {
+            if block == !B::zero() {
+                self.full.insert(i);
+            } else {
+                self.full.remove(i);
+            }
+        }
+    }
+
+    /// Allocates and returns the smallest id not in use.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSetAllocator;
+    ///
+    /// let mut ids = BitSetAllocator::new();
+    /// assert_eq!(ids.allocate(), 0);
+    /// assert_eq!(ids.allocate(), 1);
+    /// ```
+    pub fn allocate(&mut self) -> usize {
+        let id = self.next_free(0);
+        self.used.insert(id);
+        self.update_full(id..id + 1);
+        self.len += 1;
+        id
+    }
+
+    /// Allocates the first range of `len` consecutive ids not in use.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSetAllocator;
+    ///
+    /// let mut ids = BitSetAllocator::new();
+    /// assert_eq!(ids.allocate_range(4), 0..4);
+    /// ids.free(1);
+    /// assert_eq!(ids.allocate_range(2), 4..6);
+    /// assert_eq!(ids.allocate(), 1);
+    /// ```
+    pub fn allocate_range(&mut self, len: usize) -> Range<usize> {
+        if len == 0 {
+            return 0..0;
+        }
+        let mut start = self.next_free(0);
+        loop {
+            // Look for an id in use only up to the end of the candidate range
+            let blocks = self.used.bit_vec.storage();
+            let limit = cmp::min(blocks_for_bits::<B>(start + len), blocks.len());
+            match find_bit(&blocks[..limit], start, true) {
+                Some(id) if id < start + len => start = self.next_free(id + 1),
+                _ => break,
+            }
+        }
+        let range = start..start + len;
+        self.used.fill_range(range.clone());
+        self.update_full(range.clone());
+        self.len += len;
+        range
+    }
+
+    /// Releases `id`, making it available again. Returns `true` if it was in
+    /// use.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSetAllocator;
+    ///
+    /// let mut ids = BitSetAllocator::new();
+    /// let id = ids.allocate();
+    /// assert!(ids.free(id));
+    /// assert!(!ids.free(id));
+    /// ```
+    pub fn free(&mut self, id: usize) -> bool {
+        if !self.used.remove(id) {
+            return false;
+        }
+        self.full.remove(id / B::bits());
+        self.len -= 1;
+        true
+    }
+
+    /// Returns `true` if `id` is in use.
+    #[inline]
+    pub fn is_allocated(&self, id: usize) -> bool {
+        self.used.contains(id)
+    }
+
+    /// Returns the number of ids in use.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if no id is in use.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the set of ids in use.
+    #[inline]
+    pub fn allocated(&self) -> &BitSet<B> {
+        &self.used
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::BitSetAllocator;
+
+    #[test]
+    fn test_allocator_reuses_freed_ids() {
+        let mut ids: BitSetAllocator<u8> = BitSetAllocator::default();
+        let first: Vec<_> = (0..20).map(|_| ids.allocate()).collect();
+        assert_eq!(first, (0..20).collect::<Vec<_>>());
+        assert_eq!(ids.len(), 20);
+
+        assert!(ids.free(12));
+        assert!(ids.free(3));
+        assert!(!ids.free(3));
+        assert!(!ids.free(100));
+        assert_eq!(ids.len(), 18);
+        assert!(!ids.is_allocated(3));
+
+        assert_eq!(ids.allocate(), 3);
+        assert_eq!(ids.allocate(), 12);
+        assert_eq!(ids.allocate(), 20);
+        assert_eq!(ids.len(), 21);
+    }
+
+    #[test]
+    fn test_allocator_ranges() {
+        let mut ids: BitSetAllocator<u8> = BitSetAllocator::default();
+        assert_eq!(ids.allocate_range(0), 0..0);
+        assert_eq!(ids.allocate_range(10), 0..10);
+        ids.free(2);
+        ids.free(3);
+        ids.free(5);
+        assert_eq!(ids.allocate_range(3), 10..13);
+        assert_eq!(ids.allocate_range(2), 2..4);
+        assert_eq!(ids.allocate(), 5);
+        assert_eq!(ids.allocate(), 13);
+        assert_eq!(ids.len(), 14);
+        assert_eq!(ids.allocated().iter().collect::<Vec<_>>(), (0..14).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_allocator_skips_full_blocks() {
+        let mut ids: BitSetAllocator<u8> = BitSetAllocator::default();
+        assert_eq!(ids.allocate_range(1000), 0..1000);
+        assert_eq!(ids.full.len(), 125);
+        for _ in 0..10 {
+            assert!(ids.free(0));
+            assert_eq!(ids.full.iter().next(), Some(1));
+            assert_eq!(ids.allocate(), 0);
+        }
+
+        assert!(ids.free(500) && ids.free(502) && ids.free(503));
+        assert_eq!(ids.full.len(), 124);
+        assert_eq!(ids.allocate_range(2), 502..504);
+        assert_eq!(ids.allocate_range(9), 1000..1009);
+        assert_eq!(ids.allocate(), 500);
+        assert_eq!(ids.allocate(), 1009);
+        assert_eq!(ids.full.len(), 126);
+        assert_eq!(ids.allocated().iter().collect::<Vec<_>>(), (0..1010).collect::<Vec<_>>());
+
+        // Against the first free ids found by scanning every id
+        let mut x = 1u32;
+        for step in 0..3000 {
+            x = x.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let r = (x >> 8) as usize;
+            let len = if step % 3 == 1 { 1 } else { r % 20 };
+            let expected = (0..).find(|&start| (start..start + len).all(|id| !ids.is_allocated(id)))
+                                .unwrap();
+            match step % 3 {
+                0 => assert_eq!(ids.allocate_range(len), expected..expected + len),
+                1 => assert_eq!(ids.allocate(), expected),
+                _ => for id in r % 1200..r % 1200 + len {
+                    ids.free(id);
+                },
+            }
+        }
+    }
+}
@@ -0,0 +1,238 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Zero-copy serialization with `rkyv`, behind the `rkyv` feature.
+
+use core::error::Error;
+use core::fmt;
+use core::iter::Map;
+use core::slice;
+
+use bit_vec::BitBlock;
+use rkyv::bytecheck::{CheckBytes, Verify};
+use rkyv::munge::munge;
+use rkyv::primitive::ArchivedUsize;
+use rkyv::rancor::{fail, Fallible, Source};
+use rkyv::ser::{Allocator, Writer};
+use rkyv::vec::{ArchivedVec, VecResolver};
+use rkyv::{Archive, Archived, Deserialize, Place, Portable, Serialize};
+
+use {blocks_for_bits, BitSet, BlockIter};
+
+/// An archived `BitSet`, queried in place without deserializing it.
+///
+/// It is made of the length in bits and the blocks, so the archive of a
+/// set takes as much space as its storage. Validating an archive checks
+/// that the blocks match the length.
+///
+/// # Examples
+///
+/// ```
+/// # extern crate bit_set;
+/// # extern crate rkyv;
+/// use bit_set::{ArchivedBitSet, BitSet};
+/// use rkyv::rancor::Error;
+///
+/// # fn main() {
+/// let s: BitSet = [1, 40, 100].iter().cloned().collect();
+/// let bytes = rkyv::to_bytes::<Error>(&s).unwrap();
+///
+/// let archived = rkyv::access::<ArchivedBitSet<u32>, Error>(&bytes).unwrap();
+/// assert!(archived.contains(40));
+/// assert_eq!(archived.iter().collect::<Vec<_>>(), [1, 40, 100]);
+///
+/// let back: BitSet = rkyv::deserialize::<_, Error>(archived).unwrap();
+/// assert_eq!(back, s);
+/// # }
+/// ```
+#[derive(Portable, CheckBytes)]
+#[bytecheck(crate = ::rkyv::bytecheck, verify)]
+#[rkyv(crate = ::rkyv)]
+#[repr(C)]
+pub struct ArchivedBitSet<B: Archive> {
+    nbits: ArchivedUsize,
+    blocks: ArchivedVec<Archived<B>>,
+}
+
+impl<B> ArchivedBitSet<B>
+where
+    B: BitBlock + Archive,
+    Archived<B>: Copy + Into<B>,
+{
+    /// Returns the length in bits of the archived set.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.nbits.to_native() as usize
+    }
+
+    /// Returns `true` if the archived set contains the specified value.
+    #[inline]
+    pub fn contains(&self, value: usize) -> bool {
+        value < self.capacity() && {
+            let block: B = self.blocks.as_slice()[value / B::bits()].into();
+            (block >> (value % B::bits())) & B::one() == B::one()
+        }
+    }
+
+    /// Returns the number of values in the archived set.
+    pub fn len(&self) -> usize {
+        self.blocks.iter().map(|&block| block.into().count_ones()).sum()
+    }
+
+    /// Returns `true` if the archived set holds no value.
+    pub fn is_empty(&self) -> bool {
+        self.blocks.iter().all(|&block| block.into() == B::zero())
+    }
+
+    /// Iterator over each value of the archived set, in ascending order.
+    #[inline]
+    pub fn iter(&self) -> ArchivedIter<'_, B> {
+        let blocks: ArchivedBlocks<'_, B> = self.blocks.iter().map(|&block| block.into());
+        ArchivedIter(BlockIter::from_blocks(blocks))
+    }
+}
+
+impl<'a, B> IntoIterator for &'a ArchivedBitSet<B>
+where
+    B: BitBlock + Archive,
+    Archived<B>: Copy + Into<B>,
+{
+    type Item = usize;
+    type IntoIter = ArchivedIter<'a, B>;
+
+    #[inline]
+    fn into_iter(self) -> ArchivedIter<'a, B> {
+        self.iter()
+    }
+}
+
+/// The error of validating an `ArchivedBitSet` whose blocks don't match its
+/// length.
+#[derive(Debug)]
+struct InvalidBlocks;
+
+impl fmt::Display for InvalidBlocks {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("archived bit set blocks don't match its length")
+    }
+}
+
+impl Error for InvalidBlocks {}
+
+unsafe impl<B, C> Verify<C> for ArchivedBitSet<B>
+where
+    B: BitBlock + Archive,
+    Archived<B>: Copy + Into<B>,
+    C: Fallible + ?Sized,
+    C::Error: Source,
+{
+    fn verify(&self, _: &mut C) -> Result<(), C::Error> {
+        let nbits = self.capacity();
+        let blocks = self.blocks.as_slice();
+        if blocks.len() != blocks_for_bits::<B>(nbits) {
+            fail!(InvalidBlocks);
+        }
+        // The unused bits of the last block must be clear
+        if let Some(&last) = blocks.last() {
+            let used = nbits % B::bits();
+            if used != 0 && last.into() >> used != B::zero() {
+                fail!(InvalidBlocks);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<B: BitBlock + Archive> Archive for BitSet<B> {
+    type Archived = ArchivedBitSet<B>;
+    type Resolver = VecResolver;
+
+    fn resolve(&self, resolver: VecResolver, out: Place<ArchivedBitSet<B>>) {
+        munge!(let ArchivedBitSet { nbits, blocks } = out);
+        self.bit_vec.len().resolve((), nbits);
+        ArchivedVec::resolve_from_slice(self.as_block_slice(), resolver, blocks);
+    }
+}
+
+impl<B, S> Serialize<S> for BitSet<B>
+where
+    B: BitBlock + Serialize<S>,
+    S: Fallible + Allocator + Writer + ?Sized,
+{
+    fn serialize(&self, serializer: &mut S) -> Result<VecResolver, S::Error> {
+        ArchivedVec::serialize_from_slice(self.as_block_slice(), serializer)
+    }
+}
+
+impl<B, D> Deserialize<BitSet<B>, D> for ArchivedBitSet<B>
+where
+    B: BitBlock + Archive,
+    Archived<B>: Copy + Into<B>,
+    D: Fallible + ?Sized,
+{
+    fn deserialize(&self, _: &mut D) -> Result<BitSet<B>, D::Error> {
+        let mut set = BitSet::from_blocks(self.blocks.iter().map(|&block| block.into()).collect());
+        set.truncate(self.capacity());
+        Ok(set)
+    }
+}
+
+type ArchivedBlocks<'a, B> = Map<slice::Iter<'a, Archived<B>>, fn(&Archived<B>) -> B>;
+
+/// An iterator over the values of an `ArchivedBitSet`.
+pub struct ArchivedIter<'a, B: Archive + 'a>(BlockIter<ArchivedBlocks<'a, B>, B>);
+
+impl<'a, B> Iterator for ArchivedIter<'a, B>
+where
+    B: BitBlock + Archive,
+    Archived<B>: Copy + Into<B>,
+{
+    type Item = usize;
+
+    #[inline] fn next(&mut self) -> Option<usize> { self.0.next() }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use rkyv::rancor::Error;
+
+    use BitSet;
+    use super::ArchivedBitSet;
+
+    #[test]
+    fn test_archive_roundtrip() {
+        let s: BitSet<u8> = [0, 7, 8, 33, 100].iter().cloned().collect();
+        let bytes = rkyv::to_bytes::<Error>(&s).unwrap();
+        let archived = rkyv::access::<ArchivedBitSet<u8>, Error>(&bytes).unwrap();
+        assert_eq!(archived.capacity(), 101);
+        assert_eq!(archived.len(), 5);
+        assert!(!archived.is_empty());
+        assert!(archived.contains(33) && !archived.contains(34) && !archived.contains(1000));
+        assert_eq!(archived.iter().collect::<Vec<_>>(), [0, 7, 8, 33, 100]);
+        let back: BitSet<u8> = rkyv::deserialize::<_, Error>(archived).unwrap();
+        assert_eq!(back, s);
+        assert_eq!(back.get_ref().len(), 101);
+
+        let s: BitSet<u64> = (0..1000).filter(|x| x % 3 == 0).collect();
+        let bytes = rkyv::to_bytes::<Error>(&s).unwrap();
+        let archived = rkyv::access::<ArchivedBitSet<u64>, Error>(&bytes).unwrap();
+        assert!(archived.iter().eq(s.iter()));
+    }
+
+    #[test]
+    fn test_archive_validation() {
+        let s: BitSet<u32> = [3, 40].iter().cloned().collect();
+        let mut bytes = rkyv::to_bytes::<Error>(&s).unwrap();
+        assert!(rkyv::access::<ArchivedBitSet<u32>, Error>(&bytes).is_ok());
+        // The blocks come first, set a bit past the length in the second
+        assert_eq!(&bytes[..8], [8, 0, 0, 0, 0, 1, 0, 0]);
+        bytes[7] = 0x80;
+        assert!(rkyv::access::<ArchivedBitSet<u32>, Error>(&bytes).is_err());
+    }
+}
@@ -0,0 +1,309 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A fixed-capacity set that can be updated concurrently without locking.
+
+use alloc::vec::Vec;
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use {blocks_for_bits, BitSet};
+
+/// A fixed-capacity set of the values in `0..capacity`, stored as atomic
+/// `u64` words.
+///
+/// Every operation takes `&self`, so the set can be shared between threads
+/// and updated without a lock, e.g. as the visited set of a parallel graph
+/// traversal. Each update is a single atomic read-modify-write on the word
+/// holding the value.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::AtomicBitSet;
+///
+/// let visited = AtomicBitSet::with_capacity(100);
+///
+/// std::thread::scope(|scope| {
+///     for start in 0..4 {
+///         let visited = &visited;
+///         scope.spawn(move || {
+///             for v in (start..100).step_by(4) {
+///                 visited.insert(v);
+///             }
+///         });
+///     }
+/// });
+///
+/// assert_eq!(visited.len(), 100);
+/// ```
+#[derive(Debug, Default)]
+pub struct AtomicBitSet {
+    words: Vec<AtomicU64>,
+}
+
+impl AtomicBitSet {
+    /// Creates a new empty set that can hold the values in `0..nbits`. The
+    /// capacity is rounded up to a multiple of 64.
+    pub fn with_capacity(nbits: usize) -> Self {
+        AtomicBitSet {
+            words: (0..blocks_for_bits::<u64>(nbits)).map(|_| AtomicU64::new(0)).collect(),
+        }
+    }
+
+    /// Returns the number of values this set can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.words.len() * 64
+    }
+
+    /// Adds a value to the set. Returns `true` if the value was not already
+    /// present in the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is not below the capacity of the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::AtomicBitSet;
+    ///
+    /// let s = AtomicBitSet::with_capacity(64);
+    /// assert!(s.insert(3));
+    /// assert!(!s.insert(3));
+    /// ```
+    #[inline]
+    pub fn insert(&self, value: usize) -> bool {
+        let mask = 1 << (value % 64);
+        self.words[value / 64].fetch_or(mask, Ordering::AcqRel) & mask == 0
+    }
+
+    /// Removes a value from the set. Returns `true` if the value was
+    /// present in the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::AtomicBitSet;
+    ///
+    /// let s = AtomicBitSet::with_capacity(64);
+    /// s.insert(3);
+    /// assert!(s.remove(3));
+    /// assert!(!s.remove(3));
+    /// ```
+    #[inline]
+    pub fn remove(&self, value: usize) -> bool {
+        let word = match self.words.get(value / 64) {
+            Some(word) => word,
+            None => return false,
+        };
+        let mask = 1 << (value % 64);
+        word.fetch_and(!mask, Ordering::AcqRel) & mask != 0
+    }
+
+    /// Returns `true` if this set contains the specified integer.
+    #[inline]
+    pub fn contains(&self, value: usize) -> bool {
+        self.words.get(value / 64)
+            .is_some_and(|word| word.load(Ordering::Acquire) & (1 << (value % 64)) != 0)
+    }
+
+    /// Returns the number of values in the set. While other threads update
+    /// the set, this is only a snapshot of each word at a slightly different
+    /// time.
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|word| word.load(Ordering::Acquire).count_ones() as usize).sum()
+    }
+
+    /// Returns `true` if the set holds no value, with the same caveat as
+    /// [len](#method.len).
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| word.load(Ordering::Acquire) == 0)
+    }
+
+    /// Removes all values from the set.
+    pub fn clear(&self) {
+        for word in &self.words {
+            word.store(0, Ordering::Release);
+        }
+    }
+
+    /// Iterates over the values of the set in ascending order, removing
+    /// them. Each word is emptied atomically as the iterator reaches it, so
+    /// a value inserted concurrently is either yielded or left in the set,
+    /// never lost. Values not reached when the iterator is dropped also
+    /// stay in the set: those of the word being iterated are put back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::AtomicBitSet;
+    ///
+    /// let s = AtomicBitSet::with_capacity(128);
+    /// s.insert(100);
+    /// s.insert(7);
+    ///
+    /// assert_eq!(s.drain().collect::<Vec<_>>(), [7, 100]);
+    /// assert!(s.is_empty());
+    /// ```
+    pub fn drain(&self) -> AtomicDrain<'_> {
+        AtomicDrain { words: &self.words, next: 0, head: 0 }
+    }
+
+    /// Returns a `BitSet` holding a snapshot of the values of the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::AtomicBitSet;
+    ///
+    /// let s = AtomicBitSet::with_capacity(64);
+    /// s.insert(5);
+    /// assert_eq!(s.to_bit_set().iter().collect::<Vec<_>>(), [5]);
+    /// ```
+    pub fn to_bit_set(&self) -> BitSet<u64> {
+        BitSet::from_blocks(self.words.iter().map(|word| word.load(Ordering::Acquire)).collect())
+    }
+
+    /// Converts the set into a `BitSet`, keeping its capacity as universe.
+    pub fn into_bit_set(self) -> BitSet<u64> {
+        BitSet::from_blocks(self.words.into_iter().map(AtomicU64::into_inner).collect())
+    }
+}
+
+impl From<BitSet<u64>> for AtomicBitSet {
+    /// Creates an atomic set holding the values of `set`, with the capacity
+    /// of its storage.
+    fn from(set: BitSet<u64>) -> Self {
+        AtomicBitSet { words: set.into_raw_parts().into_iter().map(AtomicU64::new).collect() }
+    }
+}
+
+/// A draining iterator for `AtomicBitSet`.
+pub struct AtomicDrain<'a> {
+    words: &'a [AtomicU64],
+    /// The index of the next word to take.
+    next: usize,
+    /// The values not yielded yet of the last word taken.
+    head: u64,
+}
+
+impl<'a> Iterator for AtomicDrain<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        // Words are only taken once the previous one is exhausted
+        while self.head == 0 {
+            let word = self.words.get(self.next)?;
+            self.head = word.swap(0, Ordering::AcqRel);
+            self.next += 1;
+        }
+        let bit = self.head.trailing_zeros() as usize;
+        self.head &= self.head - 1;
+        Some((self.next - 1) * 64 + bit)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let head = self.head.count_ones() as usize;
+        (head, Some(head + (self.words.len() - self.next) * 64))
+    }
+}
+
+impl<'a> Drop for AtomicDrain<'a> {
+    fn drop(&mut self) {
+        // Put back the values taken but not yielded
+        if self.head != 0 {
+            self.words[self.next - 1].fetch_or(self.head, Ordering::AcqRel);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use std::vec::Vec;
+
+    use super::AtomicBitSet;
+    use BitSet;
+
+    #[test]
+    fn test_atomic_bit_set_basic() {
+        let s = AtomicBitSet::with_capacity(70);
+        assert_eq!(s.capacity(), 128);
+        assert!(s.is_empty());
+        assert!(s.insert(0));
+        assert!(s.insert(127));
+        assert!(!s.insert(127));
+        assert!(s.contains(127));
+        assert!(!s.contains(1000));
+        assert!(!s.remove(1000));
+        assert_eq!(s.len(), 2);
+
+        let copy = s.to_bit_set();
+        assert!(s.remove(0));
+        assert_eq!(copy.iter().collect::<Vec<_>>(), [0, 127]);
+
+        s.clear();
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_atomic_bit_set_insert_out_of_range() {
+        AtomicBitSet::with_capacity(64).insert(64);
+    }
+
+    #[test]
+    fn test_atomic_bit_set_concurrent() {
+        let s = AtomicBitSet::with_capacity(1000);
+        let inserted: usize = thread::scope(|scope| {
+            let handles: Vec<_> = (0..4).map(|_| {
+                let s = &s;
+                scope.spawn(move || (0..1000).filter(|&v| s.insert(v)).count())
+            }).collect();
+            handles.into_iter().map(|h| h.join().unwrap()).sum()
+        });
+        // Each value was newly inserted by exactly one thread
+        assert_eq!(inserted, 1000);
+
+        let drained: Vec<_> = s.drain().collect();
+        assert_eq!(drained, (0..1000).collect::<Vec<_>>());
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn test_atomic_drain_dropped_early() {
+        let s = AtomicBitSet::with_capacity(200);
+        for &v in &[3, 5, 9, 70, 150] {
+            s.insert(v);
+        }
+
+        // Nothing is taken until the drain is iterated
+        drop(s.drain());
+        assert_eq!(s.len(), 5);
+
+        let mut drain = s.drain();
+        assert_eq!(drain.next(), Some(3));
+        drop(drain);
+        assert_eq!(s.to_bit_set().iter().collect::<Vec<_>>(), [5, 9, 70, 150]);
+
+        let mut drain = s.drain();
+        assert_eq!(drain.by_ref().take(3).collect::<Vec<_>>(), [5, 9, 70]);
+        drop(drain);
+        assert_eq!(s.to_bit_set().iter().collect::<Vec<_>>(), [150]);
+    }
+
+    #[test]
+    fn test_atomic_bit_set_conversions() {
+        let set: BitSet<u64> = [3, 64, 65].iter().cloned().collect();
+        let s = AtomicBitSet::from(set.clone());
+        assert_eq!(s.capacity(), 128);
+        s.insert(100);
+        let back = s.into_bit_set();
+        assert_eq!(back.iter().collect::<Vec<_>>(), [3, 64, 65, 100]);
+    }
+}
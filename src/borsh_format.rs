@@ -0,0 +1,102 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Borsh serialization, behind the `borsh` feature.
+//!
+//! A set is written as its length in bits, a `u64`, followed by exactly as
+//! many blocks as that length needs, each in the borsh encoding of the
+//! block type. Equal sets with the same length always give the same bytes.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+
+use bit_vec::BitBlock;
+use borsh::io::{Error, ErrorKind, Read, Result, Write};
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use {blocks_for_bits, BitSet};
+
+fn invalid_data(msg: &'static str) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// # Examples
+///
+/// ```
+/// # extern crate bit_set;
+/// # extern crate borsh;
+/// use bit_set::BitSet;
+///
+/// # fn main() {
+/// let s: BitSet<u8> = [1, 9].iter().cloned().collect();
+/// let bytes = borsh::to_vec(&s).unwrap();
+/// assert_eq!(bytes, [10, 0, 0, 0, 0, 0, 0, 0, 0b10, 0b10]);
+/// assert_eq!(borsh::from_slice::<BitSet<u8>>(&bytes).unwrap(), s);
+/// # }
+/// ```
+impl<B: BitBlock + BorshSerialize> BorshSerialize for BitSet<B> {
+    fn serialize<W: Write>(&self, writer: &mut W) -> Result<()> {
+        (self.bit_vec.len() as u64).serialize(writer)?;
+        for block in self.as_block_slice() {
+            block.serialize(writer)?;
+        }
+        Ok(())
+    }
+}
+
+/// Fails with an error of kind `InvalidData` if the length doesn't fit in
+/// a `usize`, or if bits past the length are set.
+impl<B: BitBlock + BorshDeserialize> BorshDeserialize for BitSet<B> {
+    fn deserialize_reader<R: Read>(reader: &mut R) -> Result<Self> {
+        let nbits = usize::try_from(u64::deserialize_reader(reader)?)
+            .map_err(|_| invalid_data("bit set length overflows usize"))?;
+        // Blocks are pushed as they are read, so a forged length can't
+        // allocate more than the input holds
+        let mut blocks = Vec::new();
+        for _ in 0..blocks_for_bits::<B>(nbits) {
+            blocks.push(B::deserialize_reader(reader)?);
+        }
+        let used = nbits % B::bits();
+        if used != 0 && blocks[blocks.len() - 1] >> used != B::zero() {
+            return Err(invalid_data("bit set has bits set past its length"));
+        }
+        let mut set = BitSet::from_blocks(blocks);
+        set.truncate(nbits);
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use borsh::io::ErrorKind;
+
+    use BitSet;
+
+    #[test]
+    fn test_borsh_roundtrip() {
+        let s: BitSet<u32> = [0, 31, 32, 100].iter().cloned().collect();
+        let bytes = borsh::to_vec(&s).unwrap();
+        assert_eq!(bytes.len(), 8 + 4 * 4);
+        assert_eq!(&bytes[..8], 101u64.to_le_bytes());
+        let back: BitSet<u32> = borsh::from_slice(&bytes).unwrap();
+        assert_eq!(back, s);
+        assert_eq!(back.get_ref().len(), 101);
+
+        let empty: BitSet<u64> = BitSet::default();
+        let bytes = borsh::to_vec(&empty).unwrap();
+        assert_eq!(bytes, [0; 8]);
+        assert_eq!(borsh::from_slice::<BitSet<u64>>(&bytes).unwrap(), empty);
+    }
+
+    #[test]
+    fn test_borsh_errors() {
+        // Bits past the length
+        let err = borsh::from_slice::<BitSet<u8>>(&[4, 0, 0, 0, 0, 0, 0, 0, 0x10]).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        // Missing blocks
+        assert!(borsh::from_slice::<BitSet<u8>>(&[0xff, 0xff, 0, 0, 0, 0, 0, 0, 1]).is_err());
+    }
+}
@@ -0,0 +1,217 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A set refusing elements above a maximum.
+
+use core::fmt;
+use core::ops::Deref;
+
+use bit_vec::BitBlock;
+
+use {find_bit, BitSet};
+
+/// The error returned when a value is not below the limit of a
+/// `BoundedBitSet`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct LimitError {
+    value: usize,
+    limit: usize,
+}
+
+impl LimitError {
+    pub(crate) fn new(value: usize, limit: usize) -> Self {
+        LimitError { value, limit }
+    }
+
+    /// Returns the value that was rejected.
+    #[inline]
+    pub fn value(&self) -> usize {
+        self.value
+    }
+
+    /// Returns the limit of the set.
+    #[inline]
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+}
+
+impl fmt::Display for LimitError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "value {} is not below the limit {}", self.value, self.limit)
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for LimitError {}
+
+/// A `BitSet` holding only values below a limit, whose insertions fail
+/// beyond it instead of growing the storage.
+///
+/// This protects against a corrupted or hostile value such as `u64::MAX`
+/// making a plain `BitSet` allocate gigabytes. All the read-only methods of
+/// `BitSet` are available through `Deref`.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BoundedBitSet;
+///
+/// let mut s = BoundedBitSet::new(1000);
+/// assert_eq!(s.insert(10), Ok(true));
+///
+/// let err = s.insert(1 << 40).unwrap_err();
+/// assert_eq!(err.value(), 1 << 40);
+/// assert_eq!(s.len(), 1);
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub struct BoundedBitSet<B: BitBlock = u32> {
+    set: BitSet<B>,
+    limit: usize,
+}
+
+impl<B: BitBlock> Clone for BoundedBitSet<B> {
+    #[inline]
+    fn clone(&self) -> Self {
+        BoundedBitSet { set: self.set.clone(), limit: self.limit }
+    }
+
+    #[inline]
+    fn clone_from(&mut self, other: &Self) {
+        self.set.clone_from(&other.set);
+        self.limit = other.limit;
+    }
+}
+
+impl BoundedBitSet<u32> {
+    /// Creates a new empty `BoundedBitSet` accepting values below `limit`.
+    #[inline]
+    pub fn new(limit: usize) -> Self {
+        BoundedBitSet { set: BitSet::new(), limit }
+    }
+}
+
+impl<B: BitBlock> BoundedBitSet<B> {
+    /// Wraps `set`, or returns an error holding its first element not below
+    /// `limit`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::{BitSet, BoundedBitSet};
+    ///
+    /// let s: BitSet = [1, 50].iter().cloned().collect();
+    /// assert!(BoundedBitSet::from_bit_set(s.clone(), 51).is_ok());
+    /// assert_eq!(BoundedBitSet::from_bit_set(s, 20).unwrap_err().value(), 50);
+    /// ```
+    pub fn from_bit_set(set: BitSet<B>, limit: usize) -> Result<Self, LimitError> {
+        check_limit(&set, limit)?;
+        Ok(BoundedBitSet { set, limit })
+    }
+
+    /// Returns the limit below which values are accepted.
+    #[inline]
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    /// Changes the limit, failing if the set holds a value not below the new
+    /// one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BoundedBitSet;
+    ///
+    /// let mut s = BoundedBitSet::new(100);
+    /// s.insert(42).unwrap();
+    /// assert!(s.set_limit(10).is_err());
+    /// assert!(s.set_limit(43).is_ok());
+    /// assert!(s.insert(43).is_err());
+    /// ```
+    pub fn set_limit(&mut self, limit: usize) -> Result<(), LimitError> {
+        check_limit(&self.set, limit)?;
+        self.limit = limit;
+        Ok(())
+    }
+
+    /// Adds a value to the set. Returns `true` if the value was not already
+    /// present in the set, or an error if it is not below the limit.
+    #[inline]
+    pub fn insert(&mut self, value: usize) -> Result<bool, LimitError> {
+        if value >= self.limit {
+            return Err(LimitError { value, limit: self.limit });
+        }
+        Ok(self.set.insert(value))
+    }
+
+    /// Removes a value from the set. Returns `true` if the value was
+    /// present in the set.
+    #[inline]
+    pub fn remove(&mut self, value: usize) -> bool {
+        self.set.remove(value)
+    }
+
+    /// Removes all values from the set.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.set.clear();
+    }
+
+    /// Returns the underlying `BitSet`, dropping the limit.
+    #[inline]
+    pub fn into_inner(self) -> BitSet<B> {
+        self.set
+    }
+}
+
+fn check_limit<B: BitBlock>(set: &BitSet<B>, limit: usize) -> Result<(), LimitError> {
+    match find_bit(set.as_block_slice(), limit, true) {
+        Some(value) => Err(LimitError { value, limit }),
+        None => Ok(()),
+    }
+}
+
+impl<B: BitBlock> Deref for BoundedBitSet<B> {
+    type Target = BitSet<B>;
+
+    #[inline]
+    fn deref(&self) -> &BitSet<B> {
+        &self.set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::BoundedBitSet;
+    use BitSet;
+
+    #[test]
+    fn test_bounded_bit_set() {
+        let mut s = BoundedBitSet::new(64);
+        assert_eq!(s.insert(0), Ok(true));
+        assert_eq!(s.insert(63), Ok(true));
+        assert_eq!(s.insert(63), Ok(false));
+        let err = s.insert(64).unwrap_err();
+        assert_eq!((err.value(), err.limit()), (64, 64));
+        assert!(s.insert(usize::MAX).is_err());
+        assert_eq!(s.iter().collect::<Vec<_>>(), [0, 63]);
+        assert_eq!(s.memory_usage().universe_len, 64);
+
+        assert_eq!(s.set_limit(63).unwrap_err().value(), 63);
+        assert!(s.remove(63));
+        assert_eq!(s.set_limit(1), Ok(()));
+        assert_eq!(s.limit(), 1);
+        s.clear();
+        assert_eq!(s.set_limit(0), Ok(()));
+
+        let set: BitSet<u8> = (5..20).collect();
+        assert_eq!(BoundedBitSet::from_bit_set(set.clone(), 7).unwrap_err().value(), 7);
+        assert_eq!(BoundedBitSet::from_bit_set(set.clone(), 20).unwrap().into_inner(), set);
+    }
+}
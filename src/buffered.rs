@@ -0,0 +1,146 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A set deferring insertions to flush them in batches.
+
+use bit_vec::BitBlock;
+
+use BitSet;
+
+/// A set buffering the values inserted into it, up to `N` at a time, and
+/// adding them to the underlying [`BitSet`](struct.BitSet.html) in batches.
+///
+/// A batch is sorted and inserted with
+/// [`insert_sorted`](struct.BitSet.html#method.insert_sorted), growing the
+/// storage at most once and setting the values falling in the same block
+/// together, instead of checking and growing for each value. The pending
+/// values are flushed when the buffer is full, and before the set is read.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BufferedBitSet;
+///
+/// let mut s: BufferedBitSet = BufferedBitSet::new();
+/// for i in 0..1000 {
+///     s.insert(i * 7919 % 10_007);
+/// }
+/// assert_eq!(s.pending(), 1000 % 64);
+/// assert_eq!(s.as_bit_set().len(), 1000);
+/// assert_eq!(s.pending(), 0);
+/// ```
+pub struct BufferedBitSet<B: BitBlock = u32, const N: usize = 64> {
+    set: BitSet<B>,
+    buffer: [usize; N],
+    pending: usize,
+}
+
+impl<const N: usize> BufferedBitSet<u32, N> {
+    /// Creates a new empty `BufferedBitSet`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B: BitBlock, const N: usize> Default for BufferedBitSet<B, N> {
+    #[inline]
+    fn default() -> Self {
+        BufferedBitSet::from_bit_set(BitSet::default())
+    }
+}
+
+impl<B: BitBlock, const N: usize> BufferedBitSet<B, N> {
+    /// Creates a `BufferedBitSet` adding its insertions to `set`.
+    #[inline]
+    pub fn from_bit_set(set: BitSet<B>) -> Self {
+        BufferedBitSet { set, buffer: [0; N], pending: 0 }
+    }
+
+    /// Returns the number of values inserted but not yet added to the set.
+    #[inline]
+    pub fn pending(&self) -> usize {
+        self.pending
+    }
+
+    /// Inserts a value, deferring it until the buffer is flushed.
+    #[inline]
+    pub fn insert(&mut self, value: usize) {
+        if self.pending == N {
+            self.flush();
+        }
+        if N == 0 {
+            self.set.insert(value);
+            return;
+        }
+        self.buffer[self.pending] = value;
+        self.pending += 1;
+    }
+
+    /// Adds the pending values to the set.
+    pub fn flush(&mut self) {
+        let pending = &mut self.buffer[..self.pending];
+        pending.sort_unstable();
+        self.set.insert_sorted(pending);
+        self.pending = 0;
+    }
+
+    /// Returns the set, after flushing the pending values.
+    #[inline]
+    pub fn as_bit_set(&mut self) -> &BitSet<B> {
+        self.flush();
+        &self.set
+    }
+
+    /// Returns the set, mutably, after flushing the pending values.
+    #[inline]
+    pub fn as_bit_set_mut(&mut self) -> &mut BitSet<B> {
+        self.flush();
+        &mut self.set
+    }
+
+    /// Returns the set, after flushing the pending values.
+    #[inline]
+    pub fn into_inner(mut self) -> BitSet<B> {
+        self.flush();
+        self.set
+    }
+}
+
+impl<B: BitBlock, const N: usize> Extend<usize> for BufferedBitSet<B, N> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use BitSet;
+    use super::BufferedBitSet;
+
+    #[test]
+    fn test_buffered_bit_set() {
+        let values: Vec<usize> = (0..500).map(|i| i * 37 % 311).collect();
+        let expected: BitSet<u8> = values.iter().collect();
+
+        let mut s: BufferedBitSet<u8, 16> = BufferedBitSet::from_bit_set([1000].iter().collect());
+        s.extend(values.iter().cloned());
+        assert_eq!(s.pending(), 500 % 16);
+        s.as_bit_set_mut().remove(1000);
+        assert_eq!(s.pending(), 0);
+        assert_eq!(*s.as_bit_set(), expected);
+
+        let mut unbuffered: BufferedBitSet<u8, 0> = BufferedBitSet::default();
+        unbuffered.extend(values.iter().cloned());
+        assert_eq!(unbuffered.pending(), 0);
+        assert_eq!(unbuffered.into_inner(), expected);
+    }
+}
@@ -0,0 +1,114 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! LZ4-compressed persistence, behind the `lz4` feature.
+
+use std::io::{self, Read, Write};
+
+use bit_vec::BitBlock;
+use lz4_flex::frame::{FrameDecoder, FrameEncoder};
+
+use BitSet;
+
+/// Magic bytes starting the format of `write_compressed`.
+const MAGIC: [u8; 4] = *b"BSLZ";
+
+/// Current version of the format of `write_compressed`.
+const VERSION: u8 = 1;
+
+impl<B: BitBlock> BitSet<B> {
+    /// Writes this set to `writer` compressed with LZ4.
+    ///
+    /// The data is made of the magic bytes `b"BSLZ"`, the format version,
+    /// currently `1`, as a single byte, and an LZ4 frame holding the
+    /// portable format of [serialize_into](#method.serialize_into), so it
+    /// can be read back whatever the block type. Large runs of equal blocks,
+    /// as in dense masks, compress well.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = (0..100_000).filter(|x| x % 1000 < 600).collect();
+    ///
+    /// let mut buf = Vec::new();
+    /// s.write_compressed(&mut buf).unwrap();
+    /// assert!(buf.len() < 12_500 / 4);
+    ///
+    /// let t = BitSet::<u64>::read_compressed(&buf[..]).unwrap();
+    /// assert!(t.iter().eq(s.iter()));
+    /// ```
+    pub fn write_compressed<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        let mut encoder = FrameEncoder::new(writer);
+        self.serialize_into(&mut encoder)?;
+        encoder.finish()?;
+        Ok(())
+    }
+
+    /// Reads a set written by [write_compressed](#method.write_compressed)
+    /// from `reader`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `InvalidData` if the data doesn't start with
+    /// the expected magic bytes, has an unsupported version or isn't a
+    /// valid LZ4 frame, and the errors of
+    /// [deserialize_from](#method.deserialize_from) for its content.
+    pub fn read_compressed<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0u8; 5];
+        reader.read_exact(&mut magic)?;
+        if magic[..4] != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "not a compressed bit set"));
+        }
+        if magic[4] != VERSION {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "unsupported format version"));
+        }
+        BitSet::deserialize_from(FrameDecoder::new(reader))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+    use std::vec::Vec;
+
+    use BitSet;
+
+    #[test]
+    fn test_compressed_round_trip() {
+        for set in &[
+            BitSet::<u8>::default(),
+            [0, 9, 64].iter().cloned().collect(),
+            (0..200_000).filter(|x| x % 5 != 0).collect(),
+        ] {
+            let mut buf = Vec::new();
+            set.write_compressed(&mut buf).unwrap();
+            assert_eq!(&buf[..5], b"BSLZ\x01");
+            let back = BitSet::<u8>::read_compressed(&buf[..]).unwrap();
+            assert_eq!(&back, set);
+            assert_eq!(back.get_ref().len(), set.get_ref().len());
+        }
+    }
+
+    #[test]
+    fn test_compressed_errors() {
+        let set: BitSet = (0..1000).collect();
+        let mut buf = Vec::new();
+        set.write_compressed(&mut buf).unwrap();
+
+        let mut bad = buf.clone();
+        bad[0] = b'X';
+        assert_eq!(BitSet::<u32>::read_compressed(&bad[..]).unwrap_err().kind(),
+                   ErrorKind::InvalidData);
+        bad = buf.clone();
+        bad[4] = 2;
+        assert!(BitSet::<u32>::read_compressed(&bad[..]).is_err());
+        assert!(BitSet::<u32>::read_compressed(&buf[..buf.len() / 2]).is_err());
+    }
+}
@@ -0,0 +1,223 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A fixed-size set that can be built in `const` contexts.
+
+use core::iter::Cloned;
+use core::slice;
+
+use bit_vec::BitBlock;
+
+use {blocks_from_le_bytes, BitSet, BlockIter};
+
+/// A fixed-size set of the values in `0..64 * N`, stored inline as `N`
+/// `u64` words.
+///
+/// Unlike `BitSet`, it can be built by `const fn`s, so immutable sets can be
+/// defined as `static`s or `const`s and live in read-only memory instead of
+/// being built at startup. Convert it to a `BitSet` to use the full set API.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::{BitSet, ConstBitSet};
+///
+/// static OPCODES: ConstBitSet<2> = ConstBitSet::from_values(&[1, 5, 70]);
+///
+/// assert!(OPCODES.contains(70));
+/// assert!(!OPCODES.contains(2));
+/// assert_eq!(OPCODES.iter().collect::<Vec<_>>(), [1, 5, 70]);
+///
+/// let s: BitSet = OPCODES.to_bit_set();
+/// assert_eq!(s.len(), 3);
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ConstBitSet<const N: usize> {
+    words: [u64; N],
+}
+
+impl<const N: usize> ConstBitSet<N> {
+    /// The number of values this set can hold, `64 * N`.
+    pub const CAPACITY: usize = 64 * N;
+
+    /// Creates a new empty `ConstBitSet`.
+    #[inline]
+    pub const fn new() -> Self {
+        ConstBitSet { words: [0; N] }
+    }
+
+    /// Creates a new `ConstBitSet` from its words. The value `64 * n + i` is
+    /// in the set if bit `i` of `words[n]` is set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::ConstBitSet;
+    ///
+    /// const SET: ConstBitSet<2> = ConstBitSet::from_u64_words([0b101, 1]);
+    /// assert_eq!(SET.iter().collect::<Vec<_>>(), [0, 2, 64]);
+    /// ```
+    #[inline]
+    pub const fn from_u64_words(words: [u64; N]) -> Self {
+        ConstBitSet { words }
+    }
+
+    /// Creates a new `ConstBitSet` holding each of `values`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a value is not less than `Self::CAPACITY`, which fails the
+    /// compilation in a `const` context.
+    pub const fn from_values(values: &[usize]) -> Self {
+        let mut set = Self::new();
+        let mut i = 0;
+        while i < values.len() {
+            set = set.with(values[i]);
+            i += 1;
+        }
+        set
+    }
+
+    /// Returns this set with `value` added.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` is not less than `Self::CAPACITY`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::ConstBitSet;
+    ///
+    /// const SET: ConstBitSet<1> = ConstBitSet::new().with(3).with(7);
+    /// assert_eq!(SET.len(), 2);
+    /// ```
+    #[inline]
+    pub const fn with(mut self, value: usize) -> Self {
+        assert!(value < Self::CAPACITY, "value out of range");
+        self.words[value / 64] |= 1 << (value % 64);
+        self
+    }
+
+    /// Returns `true` if this set contains the specified integer.
+    #[inline]
+    pub const fn contains(&self, value: usize) -> bool {
+        value < Self::CAPACITY && self.words[value / 64] & (1 << (value % 64)) != 0
+    }
+
+    /// Returns the number of elements in this set.
+    pub const fn len(&self) -> usize {
+        let mut len = 0;
+        let mut i = 0;
+        while i < N {
+            len += self.words[i].count_ones() as usize;
+            i += 1;
+        }
+        len
+    }
+
+    /// Returns whether there are no elements in this set.
+    pub const fn is_empty(&self) -> bool {
+        let mut i = 0;
+        while i < N {
+            if self.words[i] != 0 {
+                return false;
+            }
+            i += 1;
+        }
+        true
+    }
+
+    /// Returns the words of this set.
+    #[inline]
+    pub const fn as_u64_words(&self) -> &[u64; N] {
+        &self.words
+    }
+
+    /// Iterator over each usize stored in this set, in ascending order.
+    #[inline]
+    pub fn iter(&self) -> ConstIter<'_> {
+        ConstIter(BlockIter::from_blocks(self.words.iter().cloned()))
+    }
+
+    /// Copies this set into a `BitSet`.
+    pub fn to_bit_set<B: BitBlock>(&self) -> BitSet<B> {
+        let bytes = self.words.iter().flat_map(|word| word.to_le_bytes());
+        BitSet::from_blocks(blocks_from_le_bytes(bytes))
+    }
+}
+
+impl<const N: usize> Default for ConstBitSet<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a, B: BitBlock, const N: usize> From<&'a ConstBitSet<N>> for BitSet<B> {
+    #[inline]
+    fn from(set: &'a ConstBitSet<N>) -> Self {
+        set.to_bit_set()
+    }
+}
+
+impl<'a, const N: usize> IntoIterator for &'a ConstBitSet<N> {
+    type Item = usize;
+    type IntoIter = ConstIter<'a>;
+
+    fn into_iter(self) -> ConstIter<'a> {
+        self.iter()
+    }
+}
+
+/// An iterator for `ConstBitSet`.
+#[derive(Clone)]
+pub struct ConstIter<'a>(BlockIter<Cloned<slice::Iter<'a, u64>>, u64>);
+
+impl<'a> Iterator for ConstIter<'a> {
+    type Item = usize;
+
+    #[inline] fn next(&mut self) -> Option<usize> { self.0.next() }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+    #[inline] fn fold<Acc, F>(self, init: Acc, f: F) -> Acc where F: FnMut(Acc, usize) -> Acc { self.0.fold(init, f) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::ConstBitSet;
+    use BitSet;
+
+    const EMPTY: ConstBitSet<3> = ConstBitSet::new();
+    static SET: ConstBitSet<3> = ConstBitSet::from_values(&[0, 63, 64, 191, 63]);
+
+    #[test]
+    fn test_const_bit_set() {
+        assert!(EMPTY.is_empty());
+        assert_eq!(EMPTY.iter().count(), 0);
+
+        assert!(!SET.is_empty());
+        assert_eq!(SET.len(), 4);
+        assert!(SET.contains(191));
+        assert!(!SET.contains(192));
+        assert!(!SET.contains(usize::MAX));
+        assert_eq!(SET.iter().collect::<Vec<_>>(), [0, 63, 64, 191]);
+        assert_eq!(SET.as_u64_words(), &[1 | 1 << 63, 1, 1 << 63]);
+        assert_eq!(SET, ConstBitSet::from_u64_words([1 | 1 << 63, 1, 1 << 63]));
+
+        let a: BitSet<u8> = SET.to_bit_set();
+        assert!(a.iter().eq(SET.iter()));
+        let b = BitSet::<u32>::from(&SET);
+        assert!(b.iter().eq(&SET));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_const_bit_set_out_of_range() {
+        ConstBitSet::<1>::new().with(64);
+    }
+}
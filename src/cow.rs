@@ -0,0 +1,192 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A `BitSet` with shared storage that is copied on write.
+
+use alloc::sync::Arc;
+use core::fmt;
+use core::hash;
+use core::iter::FromIterator;
+use core::ops::Deref;
+
+use bit_vec::BitBlock;
+
+use BitSet;
+
+/// A `BitSet` behind an `Arc`, so that clones share the same storage.
+///
+/// Cloning is O(1). The storage is copied the first time a shared set is
+/// modified, after which the copy belongs to the modified set alone. All the
+/// read-only methods of `BitSet` are available through `Deref`.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::{BitSet, CowBitSet};
+///
+/// let base: CowBitSet = BitSet::from_range(0..1000).into();
+///
+/// let mut worker = base.clone();
+/// assert!(worker.shares_storage_with(&base));
+///
+/// // Inserting an existing value doesn't copy the storage
+/// worker.insert(10);
+/// assert!(worker.shares_storage_with(&base));
+///
+/// worker.remove(10);
+/// assert!(!worker.shares_storage_with(&base));
+/// assert!(base.contains(10));
+/// assert!(!worker.contains(10));
+/// ```
+pub struct CowBitSet<B = u32> {
+    inner: Arc<BitSet<B>>,
+}
+
+impl<B: BitBlock> Clone for CowBitSet<B> {
+    #[inline]
+    fn clone(&self) -> Self {
+        CowBitSet { inner: self.inner.clone() }
+    }
+}
+
+impl<B: BitBlock> Default for CowBitSet<B> {
+    #[inline]
+    fn default() -> Self {
+        CowBitSet { inner: Arc::new(BitSet::default()) }
+    }
+}
+
+impl<B: BitBlock> Deref for CowBitSet<B> {
+    type Target = BitSet<B>;
+
+    #[inline]
+    fn deref(&self) -> &BitSet<B> {
+        &self.inner
+    }
+}
+
+impl<B: BitBlock> From<BitSet<B>> for CowBitSet<B> {
+    #[inline]
+    fn from(set: BitSet<B>) -> Self {
+        CowBitSet { inner: Arc::new(set) }
+    }
+}
+
+impl<B: BitBlock> FromIterator<usize> for CowBitSet<B> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        BitSet::from_iter(iter).into()
+    }
+}
+
+impl<B: BitBlock> PartialEq for CowBitSet<B> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.shares_storage_with(other) || *self.inner == *other.inner
+    }
+}
+
+impl<B: BitBlock> Eq for CowBitSet<B> {}
+
+impl<B: BitBlock> hash::Hash for CowBitSet<B> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.inner.hash(state);
+    }
+}
+
+impl<B: BitBlock> fmt::Debug for CowBitSet<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.inner.fmt(fmt)
+    }
+}
+
+impl<B: BitBlock> CowBitSet<B> {
+    /// Returns a mutable reference to the set, copying the storage first if
+    /// it is shared with another `CowBitSet`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::CowBitSet;
+    ///
+    /// let a: CowBitSet = [1, 2].iter().cloned().collect();
+    /// let mut b = a.clone();
+    /// b.to_mut().union_with(&[3].iter().cloned().collect());
+    ///
+    /// assert_eq!(a.len(), 2);
+    /// assert_eq!(b.len(), 3);
+    /// ```
+    #[inline]
+    pub fn to_mut(&mut self) -> &mut BitSet<B> {
+        Arc::make_mut(&mut self.inner)
+    }
+
+    /// Converts into a `BitSet`, copying the storage only if it is shared.
+    #[inline]
+    pub fn into_bit_set(self) -> BitSet<B> {
+        Arc::try_unwrap(self.inner).unwrap_or_else(|inner| (*inner).clone())
+    }
+
+    /// Returns `true` if both sets share the same storage.
+    #[inline]
+    pub fn shares_storage_with(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Adds a value to the set. Returns `true` if the value was not already
+    /// present in the set. The storage is only copied if the set changes.
+    pub fn insert(&mut self, value: usize) -> bool {
+        if self.inner.contains(value) {
+            return false;
+        }
+        self.to_mut().insert(value)
+    }
+
+    /// Removes a value from the set. Returns `true` if the value was
+    /// present in the set. The storage is only copied if the set changes.
+    pub fn remove(&mut self, value: usize) -> bool {
+        if !self.inner.contains(value) {
+            return false;
+        }
+        self.to_mut().remove(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::CowBitSet;
+    use BitSet;
+
+    #[test]
+    fn test_cow_bit_set_copies_on_write() {
+        let base: CowBitSet<u8> = BitSet::from_range(0..20).into();
+        let mut a = base.clone();
+        let b = base.clone();
+        assert!(a.shares_storage_with(&b));
+
+        assert!(!a.insert(5));
+        assert!(!a.remove(50));
+        assert!(a.shares_storage_with(&base));
+
+        assert!(a.insert(30));
+        assert!(!a.shares_storage_with(&base));
+        assert!(!base.contains(30));
+        assert!(b.shares_storage_with(&base));
+        assert_eq!(a.len(), 21);
+
+        // Unshared storage is modified in place
+        let before = a.as_block_slice().as_ptr();
+        a.remove(0);
+        assert_eq!(a.as_block_slice().as_ptr(), before);
+
+        assert_eq!(base, b);
+        assert!(base != a);
+        drop(b);
+        let set = base.into_bit_set();
+        assert_eq!(set.iter().collect::<Vec<_>>(), (0..20).collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,131 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Stable fingerprints of the contents of a set.
+
+use bit_vec::BitBlock;
+
+use {block_byte, BitSet};
+
+const SEED_LOW: u64 = 0x9e37_79b9_7f4a_7c15;
+const SEED_HIGH: u64 = 0xc2b2_ae3d_27d4_eb4f;
+const MULTIPLIER: u64 = 0x1656_67b1_9e37_79f9;
+
+/// The SplitMix64 finalizer.
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// Calls `f` with the index and value of each non-zero 64-bit word of the
+/// bits of `blocks`, in order, whatever the block size.
+fn for_each_word<B: BitBlock, F: FnMut(u64, u64)>(blocks: &[B], mut f: F) {
+    let mut current = (0, 0);
+    for (i, &block) in blocks.iter().enumerate() {
+        if block == B::zero() {
+            continue;
+        }
+        for k in 0..B::bytes() {
+            let byte_index = (i * B::bytes() + k) as u64;
+            let (index, word) = (byte_index / 8, byte_index % 8);
+            if index != current.0 {
+                if current.1 != 0 {
+                    f(current.0, current.1);
+                }
+                current = (index, 0);
+            }
+            current.1 |= (block_byte(block, k) as u64) << (8 * word);
+        }
+    }
+    if current.1 != 0 {
+        f(current.0, current.1);
+    }
+}
+
+/// Hashes the non-zero words of `blocks` from `seed`.
+fn digest_lane<B: BitBlock>(blocks: &[B], seed: u64) -> u64 {
+    let mut hash = seed;
+    let mut count = 0u64;
+    for_each_word(blocks, |index, word| {
+        hash = mix(hash ^ mix(word ^ index.wrapping_mul(MULTIPLIER))).wrapping_mul(MULTIPLIER);
+        count += 1;
+    });
+    mix(hash ^ count)
+}
+
+impl<B: BitBlock> BitSet<B> {
+    /// Returns a 64-bit fingerprint of the values of the set.
+    ///
+    /// Unlike `Hash` with a randomly seeded hasher, the fingerprint only
+    /// depends on the values: it is the same across runs, platforms and
+    /// versions of this crate, for any block type and any capacity. It is
+    /// not cryptographic.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let a: BitSet<u8> = [1, 100, 1000].iter().cloned().collect();
+    /// let mut b: BitSet<u64> = [1, 100, 1000].iter().cloned().collect();
+    /// b.reserve_len(100_000);
+    /// assert_eq!(a.digest(), b.digest());
+    ///
+    /// b.insert(2);
+    /// assert_ne!(a.digest(), b.digest());
+    /// ```
+    pub fn digest(&self) -> u64 {
+        digest_lane(self.as_block_slice(), SEED_LOW)
+    }
+
+    /// Returns a 128-bit fingerprint of the values of the set, with the same
+    /// guarantees as [`digest`](#method.digest) and fewer collisions.
+    pub fn digest128(&self) -> u128 {
+        let blocks = self.as_block_slice();
+        (digest_lane(blocks, SEED_HIGH) as u128) << 64 | digest_lane(blocks, SEED_LOW) as u128
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::vec::Vec;
+
+    use BitSet;
+    use super::for_each_word;
+
+    #[test]
+    fn test_for_each_word() {
+        let blocks: [u8; 10] = [0, 1, 0, 0, 0, 0, 0, 0x80, 0, 3];
+        let mut words = Vec::new();
+        for_each_word(&blocks, |index, word| words.push((index, word)));
+        assert_eq!(words, [(0, 0x8000_0000_0000_0100), (1, 0x0300)]);
+    }
+
+    #[test]
+    fn test_digest() {
+        let values = [0, 7, 8, 63, 64, 65, 1000, 4095];
+        let a: BitSet<u8> = values.iter().cloned().collect();
+        let b: BitSet<u32> = values.iter().cloned().collect();
+        let mut c: BitSet<u64> = values.iter().cloned().collect();
+        c.reserve_len(1 << 16);
+        assert_eq!(a.digest(), b.digest());
+        assert_eq!(a.digest(), c.digest());
+        assert_eq!(a.digest128(), c.digest128());
+        assert_eq!(a.digest128() as u64, a.digest());
+
+        // The values of the fingerprints are part of the API
+        assert_eq!(BitSet::new().digest(), 0xe220_a839_7b1d_cdaf);
+
+        // No collisions among small sets
+        let mut digests = HashSet::new();
+        for mask in 0..1u32 << 12 {
+            let set: BitSet = (0..12).filter(|i| mask >> i & 1 == 1).map(|i| i * 37).collect();
+            assert!(digests.insert(set.digest()));
+        }
+    }
+}
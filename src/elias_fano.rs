@@ -0,0 +1,323 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A compressed immutable set using the Elias–Fano encoding.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FromIterator;
+
+use bit_vec::BitBlock;
+
+use BitSet;
+
+/// Every `SAMPLE_RATE`-th element has its position in the upper bits stored.
+const SAMPLE_RATE: usize = 64;
+
+/// An immutable set stored with the Elias–Fano encoding, taking about
+/// `2 + log2(universe / len)` bits per element instead of one bit per value
+/// of the universe.
+///
+/// Each element is split into its low `l` bits, stored packed, and its high
+/// bits, stored as a unary-coded sequence of gaps. This is compact for sparse
+/// sets spread over a large universe, and supports `contains`, access to the
+/// `n`-th element and galloping intersection without decompressing.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::EliasFanoSet;
+///
+/// let s: EliasFanoSet = [5, 1_000_000, 999_999_999].iter().cloned().collect();
+///
+/// assert!(s.contains(1_000_000));
+/// assert!(!s.contains(6));
+/// assert_eq!(s.select(2), Some(999_999_999));
+/// assert_eq!(s.iter().collect::<Vec<_>>(), [5, 1_000_000, 999_999_999]);
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct EliasFanoSet {
+    len: usize,
+    low_bits: usize,
+    lower: Vec<u64>,
+    /// One set bit per element at `(value >> low_bits) + index`.
+    upper: Vec<u64>,
+    /// The position in `upper` of every `SAMPLE_RATE`-th element.
+    samples: Vec<usize>,
+}
+
+impl EliasFanoSet {
+    /// Encodes a strictly increasing sequence of values.
+    fn from_sorted(values: &[usize]) -> Self {
+        let len = values.len();
+        let universe = values.last().map_or(0, |&max| max + 1);
+        let low_bits = if len == 0 || universe <= len {
+            0
+        } else {
+            (usize::BITS - 1 - (universe / len).leading_zeros()) as usize
+        };
+
+        let upper_len = len + (universe >> low_bits) + 1;
+        let mut set = EliasFanoSet {
+            len,
+            low_bits,
+            lower: alloc::vec![0; (len * low_bits).div_ceil(64)],
+            upper: alloc::vec![0; upper_len.div_ceil(64)],
+            samples: Vec::with_capacity(len.div_ceil(SAMPLE_RATE)),
+        };
+        for (i, &value) in values.iter().enumerate() {
+            let pos = (value >> low_bits) + i;
+            set.upper[pos / 64] |= 1 << (pos % 64);
+            if i % SAMPLE_RATE == 0 {
+                set.samples.push(pos);
+            }
+            if low_bits > 0 {
+                let low = (value & ((1 << low_bits) - 1)) as u64;
+                let bit = i * low_bits;
+                set.lower[bit / 64] |= low << (bit % 64);
+                if bit % 64 + low_bits > 64 {
+                    set.lower[bit / 64 + 1] |= low >> (64 - bit % 64);
+                }
+            }
+        }
+        set
+    }
+
+    fn low(&self, i: usize) -> usize {
+        if self.low_bits == 0 {
+            return 0;
+        }
+        let bit = i * self.low_bits;
+        let mut low = self.lower[bit / 64] >> (bit % 64);
+        if bit % 64 + self.low_bits > 64 {
+            low |= self.lower[bit / 64 + 1] << (64 - bit % 64);
+        }
+        (low & ((1 << self.low_bits) - 1)) as usize
+    }
+
+    /// Returns the number of elements in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the set holds no element.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the `n`-th smallest element, counting from zero, or `None` if
+    /// the set has at most `n` elements.
+    pub fn select(&self, n: usize) -> Option<usize> {
+        if n >= self.len {
+            return None;
+        }
+        // Scan the upper bits from the closest sampled element
+        let pos = self.samples[n / SAMPLE_RATE];
+        let mut remaining = n % SAMPLE_RATE;
+        let mut word_index = pos / 64;
+        let mut word = self.upper[word_index] & (!0 << (pos % 64));
+        loop {
+            let ones = word.count_ones() as usize;
+            if remaining < ones {
+                for _ in 0..remaining {
+                    word &= word - 1;
+                }
+                let pos = word_index * 64 + word.trailing_zeros() as usize;
+                return Some(((pos - n) << self.low_bits) | self.low(n));
+            }
+            remaining -= ones;
+            word_index += 1;
+            word = self.upper[word_index];
+        }
+    }
+
+    /// Returns the index of the first element at or after index `from` that
+    /// is not smaller than `value`, or `len` if there is none. The distance
+    /// is found by doubling steps, then a binary search.
+    fn gallop(&self, from: usize, value: usize) -> usize {
+        let below = |i: usize| self.select(i).is_some_and(|x| x < value);
+        if from >= self.len || !below(from) {
+            return from.min(self.len);
+        }
+        // The answer is in `lo + 1..=hi` while `lo` is below `value`
+        let mut lo = from;
+        let mut step = 1;
+        while lo + step < self.len && below(lo + step) {
+            lo += step;
+            step *= 2;
+        }
+        let mut hi = (lo + step).min(self.len);
+        lo += 1;
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            if below(mid) { lo = mid + 1 } else { hi = mid }
+        }
+        lo
+    }
+
+    /// Returns `true` if this set contains the specified integer.
+    pub fn contains(&self, value: usize) -> bool {
+        let i = self.gallop(0, value);
+        self.select(i) == Some(value)
+    }
+
+    /// Returns the intersection of both sets. Each element of the smaller
+    /// set is looked up in the larger one by galloping forward from the
+    /// previous match, so the cost depends mostly on the smaller set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::EliasFanoSet;
+    ///
+    /// let a: EliasFanoSet = (0..1000).map(|x| x * 7).collect();
+    /// let b: EliasFanoSet = [14, 15, 700].iter().cloned().collect();
+    /// assert_eq!(a.intersection(&b).iter().collect::<Vec<_>>(), [14, 700]);
+    /// ```
+    pub fn intersection(&self, other: &Self) -> Self {
+        let (small, large) = if self.len <= other.len { (self, other) } else { (other, self) };
+        let mut values = Vec::new();
+        let mut i = 0;
+        for value in small.iter() {
+            i = large.gallop(i, value);
+            if i == large.len {
+                break;
+            }
+            if large.select(i) == Some(value) {
+                values.push(value);
+            }
+        }
+        EliasFanoSet::from_sorted(&values)
+    }
+
+    /// Iterator over each value in the set, in ascending order.
+    pub fn iter(&self) -> EliasFanoIter<'_> {
+        EliasFanoIter {
+            set: self,
+            index: 0,
+            word_index: 0,
+            word: self.upper.first().cloned().unwrap_or(0),
+        }
+    }
+
+    /// Returns a `BitSet` holding the values of this set.
+    pub fn to_bit_set<B: BitBlock>(&self) -> BitSet<B> {
+        self.iter().collect()
+    }
+}
+
+impl<'a, B: BitBlock> From<&'a BitSet<B>> for EliasFanoSet {
+    fn from(set: &'a BitSet<B>) -> Self {
+        EliasFanoSet::from_sorted(&set.iter().collect::<Vec<_>>())
+    }
+}
+
+impl FromIterator<usize> for EliasFanoSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut values: Vec<_> = iter.into_iter().collect();
+        values.sort_unstable();
+        values.dedup();
+        EliasFanoSet::from_sorted(&values)
+    }
+}
+
+impl<'a> IntoIterator for &'a EliasFanoSet {
+    type Item = usize;
+    type IntoIter = EliasFanoIter<'a>;
+
+    fn into_iter(self) -> EliasFanoIter<'a> {
+        self.iter()
+    }
+}
+
+impl fmt::Debug for EliasFanoSet {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_set().entries(self).finish()
+    }
+}
+
+/// An iterator for `EliasFanoSet`.
+#[derive(Clone)]
+pub struct EliasFanoIter<'a> {
+    set: &'a EliasFanoSet,
+    index: usize,
+    word_index: usize,
+    /// The bits of the current upper word not visited yet.
+    word: u64,
+}
+
+impl<'a> Iterator for EliasFanoIter<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        if self.index == self.set.len {
+            return None;
+        }
+        while self.word == 0 {
+            self.word_index += 1;
+            self.word = self.set.upper[self.word_index];
+        }
+        let pos = self.word_index * 64 + self.word.trailing_zeros() as usize;
+        self.word &= self.word - 1;
+        let value = ((pos - self.index) << self.set.low_bits) | self.set.low(self.index);
+        self.index += 1;
+        Some(value)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.set.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::EliasFanoSet;
+    use BitSet;
+
+    fn check(values: &[usize]) {
+        let set: EliasFanoSet = values.iter().cloned().collect();
+        assert_eq!(set.len(), values.len());
+        assert_eq!(set.iter().collect::<Vec<_>>(), values);
+        for (n, &v) in values.iter().enumerate() {
+            assert_eq!(set.select(n), Some(v));
+            assert!(set.contains(v));
+            assert!(v == 0 || values.contains(&(v - 1)) || !set.contains(v - 1));
+        }
+        assert_eq!(set.select(values.len()), None);
+    }
+
+    #[test]
+    fn test_elias_fano_encoding() {
+        check(&[]);
+        check(&[0]);
+        check(&[0, 1, 2, 3]);
+        check(&(0..500).map(|x| x * x).collect::<Vec<_>>());
+        check(&(0..300).map(|x| x * 3_333_331 + 17).collect::<Vec<_>>());
+        check(&[1, 1 << 40, (1 << 40) + 1]);
+    }
+
+    #[test]
+    fn test_elias_fano_conversions_and_intersection() {
+        let dense: BitSet = (0..2000).filter(|x| x % 3 == 0).collect();
+        let a = EliasFanoSet::from(&dense);
+        assert_eq!(a.to_bit_set::<u32>(), dense);
+        assert!(!a.contains(1));
+        assert!(!a.contains(5000));
+
+        let b: EliasFanoSet = (0..2000).filter(|x| x % 5 == 0).collect();
+        let both = a.intersection(&b);
+        assert_eq!(both.iter().collect::<Vec<_>>(),
+                   (0..2000).filter(|x| x % 15 == 0).collect::<Vec<_>>());
+        assert_eq!(b.intersection(&a), both);
+        assert!(a.intersection(&EliasFanoSet::default()).is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Lazy set expressions evaluated a block at a time.
+
+use alloc::vec::Vec;
+use core::cmp;
+
+use bit_vec::BitBlock;
+
+use {blocks_for_bits, BitSet, BlockIter};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ExprOp {
+    And,
+    Or,
+    Xor,
+    AndNot,
+}
+
+/// A lazy chain of set operations, applied from left to right, built by
+/// [`BitSet::expr`](struct.BitSet.html#method.expr).
+///
+/// Nothing is computed until the expression is materialized, counted or
+/// iterated, and then each block of the result is computed in one go from
+/// the blocks of all the operands, without intermediate sets.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BitSet;
+///
+/// let a: BitSet = (0..10).collect();
+/// let b: BitSet = (5..15).collect();
+/// let c: BitSet = (20..22).collect();
+/// let d: BitSet = [6, 20].iter().cloned().collect();
+///
+/// // ((a & b) | c) - d
+/// let e = a.expr().and(&b).or(&c).not_in(&d);
+/// assert_eq!(e.len(), 5);
+/// assert_eq!(e.iter().collect::<Vec<_>>(), [5, 7, 8, 9, 21]);
+/// assert_eq!(e.to_bit_set().len(), 5);
+/// ```
+#[derive(Clone)]
+pub struct Expr<'a, B: 'a> {
+    first: &'a BitSet<B>,
+    terms: Vec<(ExprOp, &'a BitSet<B>)>,
+    /// The length of the result, in bits.
+    nbits: usize,
+}
+
+impl<B: BitBlock> BitSet<B> {
+    /// Starts a lazy expression whose first operand is this set.
+    ///
+    /// See [`Expr`](struct.Expr.html).
+    #[inline]
+    pub fn expr(&self) -> Expr<'_, B> {
+        Expr { first: self, terms: Vec::new(), nbits: self.bit_vec.len() }
+    }
+}
+
+impl<'a, B: BitBlock> Expr<'a, B> {
+    fn push(mut self, op: ExprOp, other: &'a BitSet<B>) -> Self {
+        let len = other.bit_vec.len();
+        self.nbits = match op {
+            ExprOp::And => cmp::min(self.nbits, len),
+            ExprOp::Or | ExprOp::Xor => cmp::max(self.nbits, len),
+            ExprOp::AndNot => self.nbits,
+        };
+        self.terms.push((op, other));
+        self
+    }
+
+    /// Intersects the expression so far with `other`.
+    #[inline]
+    pub fn and(self, other: &'a BitSet<B>) -> Self {
+        self.push(ExprOp::And, other)
+    }
+
+    /// Unions the expression so far with `other`.
+    #[inline]
+    pub fn or(self, other: &'a BitSet<B>) -> Self {
+        self.push(ExprOp::Or, other)
+    }
+
+    /// Takes the symmetric difference of the expression so far and `other`.
+    #[inline]
+    pub fn xor(self, other: &'a BitSet<B>) -> Self {
+        self.push(ExprOp::Xor, other)
+    }
+
+    /// Removes the values of `other` from the expression so far.
+    #[inline]
+    pub fn not_in(self, other: &'a BitSet<B>) -> Self {
+        self.push(ExprOp::AndNot, other)
+    }
+
+    /// Computes the block at index `i` of the result.
+    fn block(&self, i: usize) -> B {
+        let load = |set: &BitSet<B>| set.bit_vec.storage().get(i).cloned().unwrap_or(B::zero());
+        self.terms.iter().fold(load(self.first), |acc, &(op, set)| {
+            let block = load(set);
+            match op {
+                ExprOp::And => acc & block,
+                ExprOp::Or => acc | block,
+                ExprOp::Xor => acc ^ block,
+                ExprOp::AndNot => acc & !block,
+            }
+        })
+    }
+
+    fn blocks(self) -> ExprBlocks<'a, B> {
+        let len = blocks_for_bits::<B>(self.nbits);
+        ExprBlocks { expr: self, index: 0, len }
+    }
+
+    /// Returns the number of values in the result, without materializing
+    /// it.
+    pub fn len(&self) -> usize {
+        (0..blocks_for_bits::<B>(self.nbits)).map(|i| self.block(i).count_ones()).sum()
+    }
+
+    /// Returns `true` if the result holds no value, stopping at the first
+    /// non-empty block.
+    pub fn is_empty(&self) -> bool {
+        (0..blocks_for_bits::<B>(self.nbits)).all(|i| self.block(i) == B::zero())
+    }
+
+    /// Iterator over each value of the result, in ascending order.
+    #[inline]
+    pub fn iter(&self) -> ExprIter<'a, B> {
+        self.clone().into_iter()
+    }
+
+    /// Computes the result into a new `BitSet`.
+    pub fn to_bit_set(&self) -> BitSet<B> {
+        let mut set = BitSet::from_blocks(self.clone().blocks().collect());
+        set.bit_vec.truncate(self.nbits);
+        set
+    }
+}
+
+impl<'a, B: BitBlock> IntoIterator for Expr<'a, B> {
+    type Item = usize;
+    type IntoIter = ExprIter<'a, B>;
+
+    #[inline]
+    fn into_iter(self) -> ExprIter<'a, B> {
+        ExprIter(BlockIter::from_blocks(self.blocks()))
+    }
+}
+
+/// The blocks of the result of an `Expr`.
+#[derive(Clone)]
+struct ExprBlocks<'a, B: 'a> {
+    expr: Expr<'a, B>,
+    index: usize,
+    len: usize,
+}
+
+impl<'a, B: BitBlock> Iterator for ExprBlocks<'a, B> {
+    type Item = B;
+
+    fn next(&mut self) -> Option<B> {
+        if self.index == self.len {
+            return None;
+        }
+        self.index += 1;
+        Some(self.expr.block(self.index - 1))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.len - self.index;
+        (remaining, Some(remaining))
+    }
+}
+
+/// An iterator over the values of an `Expr`.
+#[derive(Clone)]
+pub struct ExprIter<'a, B: 'a>(BlockIter<ExprBlocks<'a, B>, B>);
+
+impl<'a, B: BitBlock> Iterator for ExprIter<'a, B> {
+    type Item = usize;
+
+    #[inline] fn next(&mut self) -> Option<usize> { self.0.next() }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+    #[inline] fn fold<Acc, F>(self, init: Acc, f: F) -> Acc where F: FnMut(Acc, usize) -> Acc { self.0.fold(init, f) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use BitSet;
+
+    #[test]
+    fn test_expr() {
+        let a: BitSet<u8> = (0..40).filter(|x| x % 2 == 0).collect();
+        let b: BitSet<u8> = (0..25).filter(|x| x % 3 == 0).collect();
+        let c: BitSet<u8> = (10..60).filter(|x| x % 7 == 0).collect();
+
+        let mut expected = a.clone();
+        expected.intersect_with(&b);
+        expected.symmetric_difference_with(&c);
+        expected.difference_with(&b);
+        expected.union_with(&b);
+
+        let e = a.expr().and(&b).xor(&c).not_in(&b).or(&b);
+        assert_eq!(e.to_bit_set(), expected);
+        assert_eq!(e.len(), expected.len());
+        assert_eq!(e.iter().collect::<Vec<_>>(), expected.iter().collect::<Vec<_>>());
+        assert_eq!(e.to_bit_set().get_ref().len(), 57);
+
+        let empty = a.expr().and(&c).not_in(&c);
+        assert!(empty.is_empty());
+        assert_eq!(empty.to_bit_set().get_ref().len(), 39);
+        assert_eq!(a.expr().to_bit_set(), a);
+    }
+}
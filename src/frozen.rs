@@ -0,0 +1,196 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! An immutable set with an index for fast rank and select queries.
+
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+use bit_vec::BitBlock;
+
+use {lowest_bit, BitSet};
+
+/// The number of values covered by each entry of the rank index.
+const SUPERBLOCK_BITS: usize = 512;
+
+/// An immutable `BitSet` with a precomputed index of the number of elements
+/// before each range of 512 values.
+///
+/// The index answers [rank](#method.rank) by counting the bits of at most
+/// one superblock, and [select](#method.select) by a binary search over the
+/// index followed by the same scan. It costs one `usize` per 512 values. All
+/// the read-only methods of `BitSet` are available through `Deref`.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BitSet;
+///
+/// let s: BitSet = [3, 10, 700, 701].iter().cloned().collect();
+/// let frozen = s.freeze();
+///
+/// assert_eq!(frozen.rank(700), 2);
+/// assert_eq!(frozen.select(2), Some(700));
+/// assert!(frozen.contains(701));
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct FrozenBitSet<B: BitBlock = u32> {
+    set: BitSet<B>,
+    /// The number of elements below each superblock.
+    ranks: Vec<usize>,
+    len: usize,
+}
+
+fn superblock_len<B: BitBlock>() -> usize {
+    SUPERBLOCK_BITS / B::bits()
+}
+
+impl<B: BitBlock> BitSet<B> {
+    /// Turns this set into a `FrozenBitSet`, indexing it for rank and
+    /// select queries.
+    pub fn freeze(self) -> FrozenBitSet<B> {
+        let mut ranks = Vec::new();
+        let mut len = 0;
+        for superblock in self.as_block_slice().chunks(superblock_len::<B>()) {
+            ranks.push(len);
+            len += superblock.iter().map(|b| b.count_ones()).sum::<usize>();
+        }
+        FrozenBitSet { set: self, ranks, len }
+    }
+}
+
+impl<B: BitBlock> Deref for FrozenBitSet<B> {
+    type Target = BitSet<B>;
+
+    #[inline]
+    fn deref(&self) -> &BitSet<B> {
+        &self.set
+    }
+}
+
+impl<B: BitBlock> From<BitSet<B>> for FrozenBitSet<B> {
+    #[inline]
+    fn from(set: BitSet<B>) -> Self {
+        set.freeze()
+    }
+}
+
+impl<B: BitBlock> FrozenBitSet<B> {
+    /// Returns the number of elements in the set, without counting them.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns `true` if the set holds no element.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the number of elements strictly smaller than `value`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = BitSet::from_range(10..20);
+    /// let frozen = s.freeze();
+    /// assert_eq!(frozen.rank(0), 0);
+    /// assert_eq!(frozen.rank(15), 5);
+    /// assert_eq!(frozen.rank(1000), 10);
+    /// ```
+    pub fn rank(&self, value: usize) -> usize {
+        let blocks = self.set.as_block_slice();
+        let block = value / B::bits();
+        if block >= blocks.len() {
+            return self.len;
+        }
+        let first = block - block % superblock_len::<B>();
+        let before: usize = blocks[first..block].iter().map(|b| b.count_ones()).sum();
+        let partial = blocks[block] & !(!B::zero() << (value % B::bits()));
+        self.ranks[first / superblock_len::<B>()] + before + partial.count_ones()
+    }
+
+    /// Returns the `n`-th smallest element, counting from zero, or `None` if
+    /// the set has at most `n` elements.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = BitSet::from_ranges(vec![10..12, 600..602]);
+    /// let frozen = s.freeze();
+    /// assert_eq!(frozen.select(0), Some(10));
+    /// assert_eq!(frozen.select(3), Some(601));
+    /// assert_eq!(frozen.select(4), None);
+    /// ```
+    pub fn select(&self, n: usize) -> Option<usize> {
+        if n >= self.len {
+            return None;
+        }
+        // The last superblock with fewer than `n + 1` elements before it
+        let superblock = self.ranks.partition_point(|&rank| rank <= n) - 1;
+        let mut remaining = n - self.ranks[superblock];
+        let first = superblock * superblock_len::<B>();
+        for (i, &block) in self.set.as_block_slice()[first..].iter().enumerate() {
+            let ones = block.count_ones();
+            if remaining < ones {
+                let mut block = block;
+                for _ in 0..remaining {
+                    block = block & (block - B::one());
+                }
+                return Some((first + i) * B::bits() + lowest_bit(block));
+            }
+            remaining -= ones;
+        }
+        unreachable!("the rank index is out of sync with the blocks")
+    }
+
+    /// Returns the underlying `BitSet`, dropping the index.
+    #[inline]
+    pub fn into_inner(self) -> BitSet<B> {
+        self.set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use BitSet;
+
+    #[test]
+    fn test_frozen_bit_set_rank_select() {
+        let values = [0, 1, 63, 64, 511, 512, 513, 2000, 2047, 5000];
+        check_rank_select(&values);
+
+        let empty: BitSet = BitSet::new();
+        let frozen = empty.freeze();
+        assert_eq!(frozen.rank(100), 0);
+        assert_eq!(frozen.select(0), None);
+    }
+
+    fn check_rank_select(values: &[usize]) {
+        let a: BitSet<u8> = values.iter().cloned().collect();
+        let b: BitSet<u64> = values.iter().cloned().collect();
+        let (a, b) = (a.freeze(), b.freeze());
+        assert_eq!(a.len(), values.len());
+        assert_eq!(b.len(), values.len());
+
+        for v in 0..6000 {
+            let rank = values.iter().filter(|&&x| x < v).count();
+            assert_eq!(a.rank(v), rank);
+            assert_eq!(b.rank(v), rank);
+        }
+        for (n, &v) in values.iter().enumerate() {
+            assert_eq!(a.select(n), Some(v));
+            assert_eq!(b.select(n), Some(v));
+        }
+        assert_eq!(a.select(values.len()), None);
+        assert_eq!(b.into_inner().len(), values.len());
+    }
+}
@@ -0,0 +1,376 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A set keeping bounds on its smallest and largest elements.
+
+use core::cell::Cell;
+use core::cmp;
+use core::iter::FromIterator;
+use core::ops::Deref;
+
+use bit_vec::BitBlock;
+
+use {blocks_for_bits, highest_bit, lowest_bit, BitSet};
+
+/// A `BitSet` keeping track of its smallest and largest elements, so
+/// [`min`](#method.min), [`max`](#method.max), [`universe`](#method.universe)
+/// and [`is_empty`](#method.is_empty) don't scan the set.
+///
+/// Insertions keep the bounds exact. Removals only leave them loose: the
+/// next query scans from them to the actual smallest or largest element, and
+/// caches that, so each block emptied is scanned at most once. All the
+/// read-only methods of `BitSet` are available through `Deref`.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::HintedBitSet;
+///
+/// let mut s = HintedBitSet::new();
+/// s.extend(vec![3, 70, 1000]);
+/// assert_eq!(s.max(), Some(1000));
+/// assert_eq!(s.universe(), 1001);
+///
+/// s.remove(1000);
+/// assert_eq!(s.max(), Some(70));
+/// assert_eq!(s.min(), Some(3));
+/// ```
+#[derive(Clone, Debug)]
+pub struct HintedBitSet<B: BitBlock = u32> {
+    set: BitSet<B>,
+    /// No element is in a block before the block at index `start`.
+    start: Cell<usize>,
+    /// No element is at or past `end`.
+    end: Cell<usize>,
+}
+
+impl<B: BitBlock> Default for HintedBitSet<B> {
+    #[inline]
+    fn default() -> Self {
+        HintedBitSet::from_bit_set(BitSet::default())
+    }
+}
+
+impl HintedBitSet<u32> {
+    /// Creates a new empty `HintedBitSet`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B: BitBlock> HintedBitSet<B> {
+    /// Wraps `set`, starting from the whole of its bit vector as the bounds.
+    #[inline]
+    pub fn from_bit_set(set: BitSet<B>) -> Self {
+        let end = Cell::new(set.get_ref().len());
+        HintedBitSet { set, start: Cell::new(0), end }
+    }
+
+    /// Lowers the start bound to cover the block at index `start`.
+    #[inline]
+    fn lower(&self, start: usize) {
+        self.start.set(cmp::min(self.start.get(), start));
+    }
+
+    /// Raises the end bound to cover everything below `end`.
+    #[inline]
+    fn raise(&self, end: usize) {
+        self.end.set(cmp::max(self.end.get(), end));
+    }
+
+    /// Records that the set is empty, so both bounds cover nothing.
+    #[inline]
+    fn set_empty(&self) {
+        self.start.set(usize::MAX);
+        self.end.set(0);
+    }
+
+    /// Returns the index of the first non-zero block of `blocks`, or
+    /// `usize::MAX` if there is none.
+    fn first_block(blocks: &[B]) -> usize {
+        blocks.iter().position(|&block| block != B::zero()).unwrap_or(usize::MAX)
+    }
+
+    /// Returns the smallest value in the set, or `None` if it is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::HintedBitSet;
+    ///
+    /// let mut s = HintedBitSet::new();
+    /// assert_eq!(s.min(), None);
+    /// s.insert(5);
+    /// s.insert(2);
+    /// assert_eq!(s.min(), Some(2));
+    /// ```
+    pub fn min(&self) -> Option<usize> {
+        let blocks = self.set.as_block_slice();
+        let limit = cmp::min(blocks_for_bits::<B>(self.end.get()), blocks.len());
+        let start = cmp::min(self.start.get(), limit);
+        match blocks[start..limit].iter().position(|&block| block != B::zero()) {
+            Some(i) => {
+                self.start.set(start + i);
+                Some((start + i) * B::bits() + lowest_bit(blocks[start + i]))
+            }
+            None => {
+                self.set_empty();
+                None
+            }
+        }
+    }
+
+    /// Returns `true` if the set holds no value, without scanning it while
+    /// the bounds are exact.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::HintedBitSet;
+    ///
+    /// let mut s = HintedBitSet::new();
+    /// assert!(s.is_empty());
+    /// s.insert(1000);
+    /// assert!(!s.is_empty());
+    /// s.remove(1000);
+    /// assert!(s.is_empty());
+    /// ```
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.min().is_none()
+    }
+
+    /// Returns the largest value in the set, or `None` if it is empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::HintedBitSet;
+    ///
+    /// let mut s = HintedBitSet::new();
+    /// assert_eq!(s.max(), None);
+    /// s.insert(5);
+    /// s.insert(2);
+    /// assert_eq!(s.max(), Some(5));
+    /// ```
+    pub fn max(&self) -> Option<usize> {
+        let blocks = self.set.as_block_slice();
+        let mut i = cmp::min(blocks_for_bits::<B>(self.end.get()), blocks.len());
+        while i > 0 {
+            i -= 1;
+            if blocks[i] != B::zero() {
+                let max = i * B::bits() + highest_bit(blocks[i]);
+                self.end.set(max + 1);
+                return Some(max);
+            }
+        }
+        self.set_empty();
+        None
+    }
+
+    /// Returns one past the largest value in the set, or 0 if it is empty:
+    /// the smallest `universe` holding all the values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::HintedBitSet;
+    ///
+    /// let s: HintedBitSet = [4, 9].iter().cloned().collect();
+    /// let companion = vec![0u8; s.universe()];
+    /// assert_eq!(companion.len(), 10);
+    /// ```
+    #[inline]
+    pub fn universe(&self) -> usize {
+        self.max().map_or(0, |max| max + 1)
+    }
+
+    /// Adds a value to the set. Returns `true` if the value was not already
+    /// present in the set.
+    #[inline]
+    pub fn insert(&mut self, value: usize) -> bool {
+        self.lower(value / B::bits());
+        self.raise(value.saturating_add(1));
+        self.set.insert(value)
+    }
+
+    /// Removes a value from the set. Returns `true` if the value was
+    /// present in the set.
+    #[inline]
+    pub fn remove(&mut self, value: usize) -> bool {
+        self.set.remove(value)
+    }
+
+    /// Removes all values from the set.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.set.clear();
+        self.set_empty();
+    }
+
+    /// Unions in-place with `other`.
+    #[inline]
+    pub fn union_with(&mut self, other: &BitSet<B>) {
+        self.lower(Self::first_block(other.as_block_slice()));
+        self.raise(other.get_ref().len());
+        self.set.union_with(other);
+    }
+
+    /// Intersects in-place with `other`.
+    #[inline]
+    pub fn intersect_with(&mut self, other: &BitSet<B>) {
+        self.end.set(cmp::min(self.end.get(), other.get_ref().len()));
+        self.set.intersect_with(other);
+    }
+
+    /// Removes the values of `other` from the set.
+    #[inline]
+    pub fn difference_with(&mut self, other: &BitSet<B>) {
+        self.set.difference_with(other);
+    }
+
+    /// Takes the symmetric difference in-place with `other`.
+    #[inline]
+    pub fn symmetric_difference_with(&mut self, other: &BitSet<B>) {
+        self.lower(Self::first_block(other.as_block_slice()));
+        self.raise(other.get_ref().len());
+        self.set.symmetric_difference_with(other);
+    }
+
+    /// Returns the set, mutably. The bounds are dropped, to stay valid
+    /// whatever is done with the set, and found again by the next queries.
+    #[inline]
+    pub fn as_bit_set_mut(&mut self) -> &mut BitSet<B> {
+        self.start.set(0);
+        self.end.set(usize::MAX);
+        &mut self.set
+    }
+
+    /// Returns the set.
+    #[inline]
+    pub fn into_inner(self) -> BitSet<B> {
+        self.set
+    }
+}
+
+impl<B: BitBlock> Deref for HintedBitSet<B> {
+    type Target = BitSet<B>;
+
+    #[inline]
+    fn deref(&self) -> &BitSet<B> {
+        &self.set
+    }
+}
+
+impl<B: BitBlock> Extend<usize> for HintedBitSet<B> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<B: BitBlock> FromIterator<usize> for HintedBitSet<B> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        HintedBitSet::from_bit_set(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HintedBitSet;
+    use BitSet;
+
+    #[test]
+    fn test_hinted_max() {
+        let mut s: HintedBitSet<u8> = [1, 9, 17, 40].iter().cloned().collect();
+        assert_eq!(s.max(), Some(40));
+        assert_eq!(s.end.get(), 41);
+
+        // Removals leave the bound loose until the next query
+        s.remove(40);
+        s.remove(17);
+        assert_eq!(s.end.get(), 41);
+        assert_eq!(s.max(), Some(9));
+        assert_eq!(s.end.get(), 10);
+
+        s.insert(8);
+        assert_eq!(s.universe(), 10);
+        s.insert(200);
+        assert_eq!(s.max(), Some(200));
+
+        let other: BitSet<u8> = [3, 300].iter().cloned().collect();
+        s.union_with(&other);
+        assert_eq!(s.max(), Some(300));
+        s.symmetric_difference_with(&other);
+        assert_eq!(s.max(), Some(200));
+        s.intersect_with(&other);
+        assert_eq!(s.max(), None);
+        assert_eq!(s.universe(), 0);
+
+        s.extend(vec![5, 6]);
+        s.difference_with(&[6].iter().cloned().collect());
+        assert_eq!(s.max(), Some(5));
+        s.as_bit_set_mut().insert(77);
+        assert_eq!(s.max(), Some(77));
+        s.clear();
+        assert_eq!(s.max(), None);
+
+        for n in 0..100 {
+            let mut s: HintedBitSet<u8> = (0..n).collect();
+            for value in (0..n).rev() {
+                assert_eq!(s.max(), Some(value));
+                s.remove(value);
+            }
+            assert!(s.max().is_none());
+        }
+    }
+
+    #[test]
+    fn test_hinted_min() {
+        let mut s: HintedBitSet<u8> = [1, 9, 17, 40].iter().cloned().collect();
+        assert_eq!(s.min(), Some(1));
+
+        // Removals leave the bound loose until the next query
+        s.remove(1);
+        s.remove(9);
+        assert_eq!(s.start.get(), 0);
+        assert_eq!(s.min(), Some(17));
+        assert_eq!(s.start.get(), 2);
+
+        s.insert(3);
+        assert_eq!(s.min(), Some(3));
+
+        let other: BitSet<u8> = [0, 300].iter().cloned().collect();
+        s.union_with(&other);
+        assert_eq!(s.min(), Some(0));
+        s.symmetric_difference_with(&other);
+        assert_eq!(s.min(), Some(3));
+        s.intersect_with(&other);
+        assert!(s.is_empty());
+        assert_eq!((s.start.get(), s.end.get()), (usize::MAX, 0));
+
+        s.insert(50);
+        assert_eq!(s.start.get(), 6);
+        assert!(!s.is_empty());
+        s.as_bit_set_mut().insert(2);
+        assert_eq!(s.min(), Some(2));
+        s.clear();
+        assert!(s.is_empty());
+
+        for n in 0..100 {
+            let mut s: HintedBitSet<u8> = (0..n).collect();
+            for value in 0..n {
+                assert_eq!(s.min(), Some(value));
+                assert!(!s.is_empty());
+                s.remove(value);
+            }
+            assert!(s.is_empty());
+        }
+    }
+}
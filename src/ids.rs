@@ -0,0 +1,150 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Variants of the element methods taking `u32` and `u64` values.
+//!
+//! A `u32` always fits in the `usize` of the supported targets. A `u64` may
+//! not on 32-bit targets, where such a value is never in a set and can't be
+//! inserted.
+
+use core::convert::TryFrom;
+
+use bit_vec::BitBlock;
+
+use BitSet;
+
+/// Converts `value`, panicking if it doesn't fit in a `usize`.
+fn to_index(value: u64) -> usize {
+    match usize::try_from(value) {
+        Ok(value) => value,
+        Err(_) => panic!("value {} does not fit in a usize", value),
+    }
+}
+
+impl<B: BitBlock> BitSet<B> {
+    /// Returns `true` if this set contains the specified `u32`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let id: u32 = 42;
+    /// let mut s = BitSet::new();
+    /// assert!(s.insert_u32(id));
+    /// assert!(s.contains_u32(id));
+    /// assert!(s.remove_u32(id));
+    /// ```
+    #[inline]
+    pub fn contains_u32(&self, value: u32) -> bool {
+        self.contains(value as usize)
+    }
+
+    /// Adds a `u32` to the set. Returns `true` if the value was not already
+    /// present in the set.
+    #[inline]
+    pub fn insert_u32(&mut self, value: u32) -> bool {
+        self.insert(value as usize)
+    }
+
+    /// Removes a `u32` from the set. Returns `true` if the value was present
+    /// in the set.
+    #[inline]
+    pub fn remove_u32(&mut self, value: u32) -> bool {
+        self.remove(value as usize)
+    }
+
+    /// Adds every `u32` of `iter` to the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let ids: Vec<u32> = vec![3, 1, 4];
+    /// let mut s = BitSet::new();
+    /// s.extend_u32(ids);
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), [1, 3, 4]);
+    /// ```
+    pub fn extend_u32<I: IntoIterator<Item = u32>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(|value| value as usize));
+    }
+
+    /// Returns `true` if this set contains the specified `u64`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let id: u64 = 42;
+    /// let mut s = BitSet::new();
+    /// assert!(s.insert_u64(id));
+    /// assert!(s.contains_u64(id));
+    /// assert!(!s.contains_u64(u64::MAX));
+    /// assert!(s.remove_u64(id));
+    /// ```
+    #[inline]
+    pub fn contains_u64(&self, value: u64) -> bool {
+        usize::try_from(value).is_ok_and(|value| self.contains(value))
+    }
+
+    /// Adds a `u64` to the set. Returns `true` if the value was not already
+    /// present in the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `value` doesn't fit in a `usize`.
+    #[inline]
+    pub fn insert_u64(&mut self, value: u64) -> bool {
+        self.insert(to_index(value))
+    }
+
+    /// Removes a `u64` from the set. Returns `true` if the value was present
+    /// in the set.
+    #[inline]
+    pub fn remove_u64(&mut self, value: u64) -> bool {
+        usize::try_from(value).is_ok_and(|value| self.remove(value))
+    }
+
+    /// Adds every `u64` of `iter` to the set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a value doesn't fit in a `usize`.
+    pub fn extend_u64<I: IntoIterator<Item = u64>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().map(to_index));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use BitSet;
+
+    #[test]
+    fn test_u32_u64_elements() {
+        let mut s = BitSet::new();
+        s.extend_u32(vec![1u32, 7, 100]);
+        s.extend_u64(vec![7u64, 200]);
+        assert_eq!(s.iter().collect::<Vec<_>>(), [1, 7, 100, 200]);
+
+        assert!(s.contains_u32(100) && s.contains_u64(200));
+        assert!(!s.insert_u32(1));
+        assert!(s.remove_u64(1));
+        assert!(!s.remove_u32(1));
+        assert!(!s.remove_u64(u64::MAX));
+        assert!(!s.contains_u32(u32::MAX));
+    }
+
+    #[cfg(target_pointer_width = "32")]
+    #[test]
+    #[should_panic]
+    fn test_insert_u64_too_large() {
+        BitSet::new().insert_u64(u64::MAX);
+    }
+}
@@ -0,0 +1,102 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Conversions with the `bitvec` crate.
+//!
+//! `bitvec`'s `Lsb0` ordering stores bit `i` of a region at the `i`-th least
+//! significant bit of its elements, exactly like `BitSet` does with its
+//! blocks, so owned `Lsb0` vectors are converted by handing over their
+//! buffer.
+
+use bit_vec::BitBlock;
+use bitvec::order::{BitOrder, Lsb0};
+use bitvec::slice::BitSlice;
+use bitvec::store::BitStore;
+use bitvec::vec::BitVec;
+
+use BitSet;
+
+impl<B: BitBlock + BitStore> From<BitVec<B, Lsb0>> for BitSet<B> {
+    /// Reuses the buffer of the bit vector, without copying it.
+    fn from(mut bits: BitVec<B, Lsb0>) -> Self {
+        let len = bits.len();
+        // A vector made from a slice may start inside its first element
+        bits.force_align();
+        // The bits past the end of the vector may hold garbage
+        bits.set_uninitialized(false);
+        let mut set = BitSet::from_blocks(bits.into_vec());
+        unsafe { set.bit_vec.set_len(len) };
+        set
+    }
+}
+
+impl<B: BitBlock + BitStore> From<BitSet<B>> for BitVec<B, Lsb0> {
+    /// Reuses the blocks of the set, without copying them. The resulting bit
+    /// vector has the same length as the underlying bit vector of the set.
+    fn from(set: BitSet<B>) -> Self {
+        let len = set.bit_vec.len();
+        let mut bits = BitVec::from_vec(set.into_raw_parts());
+        bits.truncate(len);
+        bits
+    }
+}
+
+impl<'a, B: BitBlock, T: BitStore, O: BitOrder> From<&'a BitSlice<T, O>> for BitSet<B> {
+    fn from(bits: &'a BitSlice<T, O>) -> Self {
+        let mut set = BitSet::default();
+        set.reserve_len_exact(bits.len());
+        set.extend(bits.iter_ones());
+        set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use bitvec::order::{Lsb0, Msb0};
+    use bitvec::vec::BitVec;
+
+    use BitSet;
+
+    #[test]
+    fn test_bit_set_bitvec_owned() {
+        let mut bits: BitVec<u32, Lsb0> = BitVec::repeat(false, 40);
+        bits.set(3, true);
+        bits.set(39, true);
+
+        let set = BitSet::from(bits.clone());
+        assert_eq!(set.iter().collect::<Vec<_>>(), [3, 39]);
+        assert_eq!(set.get_ref().len(), 40);
+        assert_eq!(BitVec::from(set), bits);
+
+        // Garbage past the end of the vector is not carried over
+        let mut bits: BitVec<u8, Lsb0> = BitVec::from_vec(vec![0xff]);
+        bits.truncate(2);
+        let set = BitSet::from(bits);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [0, 1]);
+
+        // A vector starting inside its first element is realigned
+        let mut bits: BitVec<u8, Lsb0> = BitVec::repeat(false, 20);
+        bits.set(3, true);
+        bits.set(4, true);
+        bits.set(19, true);
+        let offset = BitVec::from_bitslice(&bits[3..]);
+        let set = BitSet::from(offset);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [0, 1, 16]);
+        assert_eq!(set.get_ref().len(), 17);
+        assert_eq!(set.as_block_slice()[2], 0b1);
+    }
+
+    #[test]
+    fn test_bit_set_bitslice() {
+        let mut bits: BitVec<u8, Msb0> = BitVec::repeat(false, 20);
+        bits.set(0, true);
+        bits.set(17, true);
+        let set: BitSet<u64> = BitSet::from(bits.as_bitslice());
+        assert_eq!(set.iter().collect::<Vec<_>>(), [0, 17]);
+    }
+}
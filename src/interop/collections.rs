@@ -0,0 +1,128 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Conversions and comparisons with the standard `BTreeSet` and `HashSet`.
+
+use alloc::collections::BTreeSet;
+#[cfg(feature = "std")]
+use std::collections::HashSet;
+#[cfg(feature = "std")]
+use std::hash::BuildHasher;
+
+use bit_vec::BitBlock;
+
+use BitSet;
+
+impl<'a, B: BitBlock> From<&'a BitSet<B>> for BTreeSet<usize> {
+    #[inline]
+    fn from(set: &'a BitSet<B>) -> Self {
+        set.iter().collect()
+    }
+}
+
+impl<'a, B: BitBlock> From<&'a BTreeSet<usize>> for BitSet<B> {
+    /// Allocates the storage once for the largest element of `tree`.
+    fn from(tree: &'a BTreeSet<usize>) -> Self {
+        let mut set = BitSet::default();
+        if let Some(&max) = tree.iter().next_back() {
+            set.reserve_len_exact(max.saturating_add(1));
+        }
+        set.extend(tree.iter().cloned());
+        set
+    }
+}
+
+impl<B: BitBlock> PartialEq<BTreeSet<usize>> for BitSet<B> {
+    #[inline]
+    fn eq(&self, other: &BTreeSet<usize>) -> bool {
+        self.iter().eq(other.iter().cloned())
+    }
+}
+
+impl<B: BitBlock> PartialEq<BitSet<B>> for BTreeSet<usize> {
+    #[inline]
+    fn eq(&self, other: &BitSet<B>) -> bool {
+        other == self
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, B: BitBlock, S: BuildHasher + Default> From<&'a BitSet<B>> for HashSet<usize, S> {
+    fn from(set: &'a BitSet<B>) -> Self {
+        let mut hash_set = HashSet::with_capacity_and_hasher(set.len(), S::default());
+        hash_set.extend(set.iter());
+        hash_set
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, B: BitBlock, S> From<&'a HashSet<usize, S>> for BitSet<B> {
+    /// Allocates the storage once for the largest element of `hash_set`.
+    fn from(hash_set: &'a HashSet<usize, S>) -> Self {
+        let mut set = BitSet::default();
+        if let Some(&max) = hash_set.iter().max() {
+            set.reserve_len_exact(max.saturating_add(1));
+        }
+        set.extend(hash_set.iter().cloned());
+        set
+    }
+}
+
+#[cfg(feature = "std")]
+impl<B: BitBlock, S: BuildHasher> PartialEq<HashSet<usize, S>> for BitSet<B> {
+    #[inline]
+    fn eq(&self, other: &HashSet<usize, S>) -> bool {
+        self.len() == other.len() && self.iter().all(|value| other.contains(&value))
+    }
+}
+
+#[cfg(feature = "std")]
+impl<B: BitBlock, S: BuildHasher> PartialEq<BitSet<B>> for HashSet<usize, S> {
+    #[inline]
+    fn eq(&self, other: &BitSet<B>) -> bool {
+        other == self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+    #[cfg(feature = "std")]
+    use std::collections::HashSet;
+
+    use BitSet;
+
+    #[test]
+    fn test_bit_set_btree_set() {
+        let tree: BTreeSet<usize> = [7, 1, 300].iter().cloned().collect();
+        let set: BitSet = BitSet::from(&tree);
+        assert!(set == tree);
+        assert!(tree == set);
+        assert_eq!(BTreeSet::from(&set), tree);
+
+        let mut other = set.clone();
+        other.remove(300);
+        assert!(other != tree);
+        assert!(tree != other);
+        assert!(BitSet::<u32>::from(&BTreeSet::new()).is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_bit_set_hash_set() {
+        let hash_set: HashSet<usize> = [7, 1, 300].iter().cloned().collect();
+        let set: BitSet<u64> = BitSet::from(&hash_set);
+        assert!(set == hash_set);
+        assert!(hash_set == set);
+        assert_eq!(HashSet::<usize>::from(&set), hash_set);
+
+        let mut other = set.clone();
+        other.insert(2);
+        assert!(other != hash_set);
+        other.remove(1);
+        assert!(other != hash_set);
+    }
+}
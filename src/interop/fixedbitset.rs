@@ -0,0 +1,77 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Conversions and comparisons with `fixedbitset::FixedBitSet`.
+
+use bit_vec::BitBlock;
+use fixedbitset::FixedBitSet;
+
+use BitSet;
+
+impl<'a, B: BitBlock> From<&'a FixedBitSet> for BitSet<B> {
+    fn from(fixed: &'a FixedBitSet) -> Self {
+        let mut set = BitSet::default();
+        set.reserve_len_exact(fixed.len());
+        set.extend(fixed.ones());
+        set
+    }
+}
+
+impl<'a, B: BitBlock> From<&'a BitSet<B>> for FixedBitSet {
+    /// The resulting `FixedBitSet` has the same length as the underlying bit
+    /// vector of the set.
+    fn from(set: &'a BitSet<B>) -> Self {
+        let mut fixed = FixedBitSet::with_capacity(set.bit_vec.len());
+        for value in set {
+            fixed.insert(value);
+        }
+        fixed
+    }
+}
+
+impl<B: BitBlock> PartialEq<FixedBitSet> for BitSet<B> {
+    #[inline]
+    fn eq(&self, other: &FixedBitSet) -> bool {
+        self.iter().eq(other.ones())
+    }
+}
+
+impl<B: BitBlock> PartialEq<BitSet<B>> for FixedBitSet {
+    #[inline]
+    fn eq(&self, other: &BitSet<B>) -> bool {
+        other == self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use fixedbitset::FixedBitSet;
+
+    use BitSet;
+
+    #[test]
+    fn test_bit_set_fixedbitset() {
+        let mut fixed = FixedBitSet::with_capacity(200);
+        fixed.insert(2);
+        fixed.insert(199);
+
+        let set: BitSet = BitSet::from(&fixed);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [2, 199]);
+        assert!(set == fixed);
+        assert!(fixed == set);
+
+        let back = FixedBitSet::from(&set);
+        assert_eq!(back, fixed);
+
+        let mut other = set.clone();
+        other.insert(500);
+        assert!(other != fixed);
+        assert!(fixed != other);
+        assert!(BitSet::<u64>::default() == FixedBitSet::with_capacity(10));
+    }
+}
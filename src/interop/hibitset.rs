@@ -0,0 +1,71 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Conversions with `hibitset::BitSet`.
+
+use core::convert::TryFrom;
+
+use bit_vec::BitBlock;
+use hibitset::BitSet as HiBitSet;
+
+use super::OutOfRangeError;
+use {blocks_from_le_bytes, BitSet};
+
+impl<'a, B: BitBlock> From<&'a HiBitSet> for BitSet<B> {
+    /// Copies the lowest layer of the hierarchical set block by block.
+    fn from(hibits: &'a HiBitSet) -> Self {
+        let bytes = hibits.layer0_as_slice().iter().flat_map(|word| word.to_le_bytes());
+        BitSet::from_blocks(blocks_from_le_bytes(bytes))
+    }
+}
+
+impl<'a, B: BitBlock> TryFrom<&'a BitSet<B>> for HiBitSet {
+    type Error = OutOfRangeError;
+
+    /// Fails if the set holds an element that is too large for a
+    /// `hibitset::BitSet`.
+    fn try_from(set: &'a BitSet<B>) -> Result<Self, OutOfRangeError> {
+        let limit = HiBitSet::LAYER2_GRANULARITY * HiBitSet::BITS_PER_USIZE.pow(2);
+        let mut hibits = HiBitSet::new();
+        for value in set {
+            if value >= limit {
+                return Err(OutOfRangeError::new());
+            }
+            hibits.add(value as u32);
+        }
+        Ok(hibits)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::TryFrom;
+    use std::vec::Vec;
+
+    use hibitset::{BitSet as HiBitSet, BitSetLike};
+
+    use BitSet;
+
+    #[test]
+    fn test_bit_set_hibitset() {
+        let mut hibits = HiBitSet::new();
+        hibits.add(1);
+        hibits.add(64);
+        hibits.add(100_000);
+
+        let set: BitSet = BitSet::from(&hibits);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [1, 64, 100_000]);
+        let set: BitSet<u8> = BitSet::from(&hibits);
+        assert_eq!(set.iter().collect::<Vec<_>>(), [1, 64, 100_000]);
+
+        let back = HiBitSet::try_from(&set).unwrap();
+        assert_eq!(back.iter().collect::<Vec<_>>(), [1, 64, 100_000]);
+
+        let mut too_big: BitSet = BitSet::new();
+        too_big.insert(1 << 30);
+        assert!(HiBitSet::try_from(&too_big).is_err());
+    }
+}
@@ -0,0 +1,42 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Conversions between `BitSet` and the sets of other crates.
+
+use core::fmt;
+
+#[cfg(feature = "bitvec")]
+mod bitvec;
+mod collections;
+#[cfg(feature = "fixedbitset")]
+mod fixedbitset;
+#[cfg(feature = "hibitset")]
+mod hibitset;
+#[cfg(feature = "petgraph")]
+mod petgraph;
+#[cfg(feature = "roaring")]
+mod roaring;
+
+/// The error returned when converting a set holding an element that doesn't
+/// fit in the element type of the target set.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct OutOfRangeError(());
+
+impl OutOfRangeError {
+    #[allow(dead_code)]
+    pub(crate) fn new() -> Self {
+        OutOfRangeError(())
+    }
+}
+
+impl fmt::Display for OutOfRangeError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("set element out of range of the target type")
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for OutOfRangeError {}
@@ -0,0 +1,105 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Use of the `petgraph` index types as set elements and of sets as visit
+//! maps.
+
+use bit_vec::BitBlock;
+use petgraph::graph::{EdgeIndex, IndexType, NodeIndex};
+use petgraph::visit::VisitMap;
+
+use {BitIndex, BitSet, TypedBitSet};
+
+impl<Ix: IndexType> BitIndex for NodeIndex<Ix> {
+    #[inline]
+    fn index(self) -> usize {
+        NodeIndex::index(self)
+    }
+
+    #[inline]
+    fn from_index(index: usize) -> Self {
+        NodeIndex::new(index)
+    }
+}
+
+impl<Ix: IndexType> BitIndex for EdgeIndex<Ix> {
+    #[inline]
+    fn index(self) -> usize {
+        EdgeIndex::index(self)
+    }
+
+    #[inline]
+    fn from_index(index: usize) -> Self {
+        EdgeIndex::new(index)
+    }
+}
+
+impl<Ix: IndexType, B: BitBlock> VisitMap<Ix> for BitSet<B> {
+    #[inline]
+    fn visit(&mut self, a: Ix) -> bool {
+        self.insert(a.index())
+    }
+
+    #[inline]
+    fn is_visited(&self, a: &Ix) -> bool {
+        self.contains(a.index())
+    }
+
+    #[inline]
+    fn unvisit(&mut self, a: Ix) -> bool {
+        self.remove(a.index())
+    }
+}
+
+impl<K: BitIndex, B: BitBlock> VisitMap<K> for TypedBitSet<K, B> {
+    #[inline]
+    fn visit(&mut self, a: K) -> bool {
+        self.insert(a)
+    }
+
+    #[inline]
+    fn is_visited(&self, a: &K) -> bool {
+        self.contains(*a)
+    }
+
+    #[inline]
+    fn unvisit(&mut self, a: K) -> bool {
+        self.remove(a)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use petgraph::graph::{EdgeIndex, Graph, NodeIndex};
+    use petgraph::visit::{Dfs, VisitMap};
+
+    use {BitSet, TypedBitSet};
+
+    #[test]
+    fn test_petgraph_indices() {
+        let mut graph = Graph::<(), ()>::new();
+        let nodes: Vec<NodeIndex> = (0..5).map(|_| graph.add_node(())).collect();
+        graph.add_edge(nodes[0], nodes[1], ());
+        graph.add_edge(nodes[1], nodes[3], ());
+        let edge = graph.add_edge(nodes[2], nodes[4], ());
+
+        let mut dfs = Dfs::from_parts(Vec::new(), TypedBitSet::<NodeIndex>::new());
+        dfs.move_to(nodes[0]);
+        while dfs.next(&graph).is_some() {}
+        assert_eq!(dfs.discovered.iter().collect::<Vec<_>>(), [nodes[0], nodes[1], nodes[3]]);
+
+        let mut seen = BitSet::new();
+        assert!(seen.visit(nodes[2]));
+        assert!(!seen.visit(nodes[2]));
+        assert!(seen.is_visited(&nodes[2]));
+        assert!(seen.unvisit(nodes[2]));
+
+        let edges: TypedBitSet<EdgeIndex> = Some(edge).into_iter().collect();
+        assert_eq!(edges.iter().next(), Some(EdgeIndex::new(2)));
+    }
+}
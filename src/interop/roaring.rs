@@ -0,0 +1,98 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Conversions with `roaring::RoaringBitmap` and `roaring::RoaringTreemap`.
+
+use core::convert::TryFrom;
+
+use bit_vec::BitBlock;
+use roaring::{RoaringBitmap, RoaringTreemap};
+
+use super::OutOfRangeError;
+use BitSet;
+
+impl<'a, B: BitBlock> From<&'a RoaringBitmap> for BitSet<B> {
+    fn from(bitmap: &'a RoaringBitmap) -> Self {
+        let mut set = BitSet::default();
+        if let Some(max) = bitmap.max() {
+            set.reserve_len_exact(max as usize + 1);
+        }
+        set.extend(bitmap.iter().map(|value| value as usize));
+        set
+    }
+}
+
+impl<'a, B: BitBlock> TryFrom<&'a RoaringTreemap> for BitSet<B> {
+    type Error = OutOfRangeError;
+
+    /// Fails if the treemap holds an element that doesn't fit in a `usize`.
+    fn try_from(treemap: &'a RoaringTreemap) -> Result<Self, OutOfRangeError> {
+        let mut set = BitSet::default();
+        if let Some(max) = treemap.max() {
+            let max = usize::try_from(max).map_err(|_| OutOfRangeError::new())?;
+            set.reserve_len_exact(max.checked_add(1).ok_or_else(OutOfRangeError::new)?);
+        }
+        set.extend(treemap.iter().map(|value| value as usize));
+        Ok(set)
+    }
+}
+
+impl<'a, B: BitBlock> TryFrom<&'a BitSet<B>> for RoaringBitmap {
+    type Error = OutOfRangeError;
+
+    /// Fails if the set holds an element that doesn't fit in a `u32`.
+    fn try_from(set: &'a BitSet<B>) -> Result<Self, OutOfRangeError> {
+        let mut bitmap = RoaringBitmap::new();
+        for value in set {
+            bitmap.push(u32::try_from(value).map_err(|_| OutOfRangeError::new())?);
+        }
+        Ok(bitmap)
+    }
+}
+
+impl<'a, B: BitBlock> From<&'a BitSet<B>> for RoaringTreemap {
+    fn from(set: &'a BitSet<B>) -> Self {
+        let mut treemap = RoaringTreemap::new();
+        for value in set {
+            treemap.push(value as u64);
+        }
+        treemap
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::convert::TryFrom;
+
+    use roaring::{RoaringBitmap, RoaringTreemap};
+
+    use BitSet;
+
+    #[test]
+    fn test_bit_set_roaring_bitmap() {
+        let bitmap: RoaringBitmap = [0, 5, 64, 100_000].iter().cloned().collect();
+        let set: BitSet = BitSet::from(&bitmap);
+        assert_eq!(set.iter().collect::<std::vec::Vec<_>>(), [0, 5, 64, 100_000]);
+        assert_eq!(RoaringBitmap::try_from(&set), Ok(bitmap));
+
+        let mut too_big: BitSet = BitSet::new();
+        too_big.insert(1 << 32);
+        assert!(RoaringBitmap::try_from(&too_big).is_err());
+    }
+
+    #[test]
+    fn test_bit_set_roaring_treemap() {
+        let treemap: RoaringTreemap = [3, 1 << 33].iter().cloned().collect();
+        let set: BitSet<u64> = BitSet::try_from(&treemap).unwrap();
+        assert!(set.contains(3));
+        assert!(set.contains(1 << 33));
+        assert_eq!(set.len(), 2);
+        assert_eq!(RoaringTreemap::from(&set), treemap);
+
+        let empty: BitSet = BitSet::try_from(&RoaringTreemap::new()).unwrap();
+        assert!(empty.is_empty());
+    }
+}
@@ -0,0 +1,395 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Streaming the block data of a `BitSet` through `std::io`.
+
+use std::cmp;
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+use std::iter;
+use std::vec::Vec;
+
+use bit_vec::BitBlock;
+
+use super::{block_byte, blocks_for_bits, blocks_from_le_bytes, primitive, BitSet};
+
+/// Size of the scratch buffer used to batch block reads and writes.
+const BUF_LEN: usize = 4096;
+
+/// Magic bytes starting the portable format of `serialize_into`.
+const MAGIC: [u8; 4] = *b"BSET";
+
+/// Current version of the portable format of `serialize_into`.
+const VERSION: u8 = 1;
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn read_u64<R: Read>(reader: &mut R) -> io::Result<u64> {
+    let mut bytes = [0u8; 8];
+    reader.read_exact(&mut bytes)?;
+    Ok(u64::from_le_bytes(bytes))
+}
+
+/// Writes exactly `len` bytes made of the little-endian bytes of `blocks`,
+/// truncated or padded with zeros as needed.
+fn write_blocks<B: BitBlock, W: Write>(writer: &mut W, blocks: &[B], len: usize)
+    -> io::Result<()>
+{
+    if !primitive::is_primitive::<B>() {
+        return write_generic_blocks(writer, blocks, len);
+    }
+    let data_len = cmp::min(len, blocks.len() * B::bytes());
+    match primitive::as_bytes(blocks) {
+        // The blocks are already laid out in little-endian order
+        Some(bytes) if cfg!(target_endian = "little") => writer.write_all(&bytes[..data_len])?,
+        _ => {
+            let mut buf = [0u8; BUF_LEN];
+            let mut remaining = data_len;
+            for chunk in blocks.chunks(BUF_LEN / B::bytes()) {
+                if remaining == 0 {
+                    break;
+                }
+                let written = primitive::write_le_bytes(chunk, &mut buf).unwrap_or(0);
+                let n = cmp::min(remaining, written);
+                writer.write_all(&buf[..n])?;
+                remaining -= n;
+            }
+        }
+    }
+    let zeros = [0u8; BUF_LEN];
+    let mut padding = len - data_len;
+    while padding > 0 {
+        let n = cmp::min(padding, BUF_LEN);
+        writer.write_all(&zeros[..n])?;
+        padding -= n;
+    }
+    Ok(())
+}
+
+/// Like `write_blocks`, for blocks that aren't primitive integers, whose
+/// bytes are extracted a bit at a time.
+fn write_generic_blocks<B: BitBlock, W: Write>(writer: &mut W, blocks: &[B], len: usize)
+    -> io::Result<()>
+{
+    let bytes = blocks.iter()
+        .flat_map(|&block| (0..B::bytes()).map(move |k| block_byte(block, k)))
+        .chain(iter::repeat(0))
+        .take(len);
+
+    let mut buf = [0u8; BUF_LEN];
+    let mut pos = 0;
+    for byte in bytes {
+        if pos == BUF_LEN {
+            writer.write_all(&buf)?;
+            pos = 0;
+        }
+        buf[pos] = byte;
+        pos += 1;
+    }
+    writer.write_all(&buf[..pos])
+}
+
+/// Reads `len` bytes into blocks, least significant byte first. The last
+/// block is padded with zeros if `len` is not a multiple of the block size.
+fn read_blocks<B: BitBlock, R: Read>(reader: &mut R, len: usize) -> io::Result<Vec<B>> {
+    // Don't trust `len` for the allocation size, a truncated or corrupted
+    // stream should fail with an error rather than abort.
+    let block_len = B::bytes();
+    let chunk_len = BUF_LEN - BUF_LEN % block_len;
+    let mut blocks = Vec::with_capacity(cmp::min(len, chunk_len) / block_len);
+    let mut buf = [0u8; BUF_LEN];
+    let mut remaining = len;
+    while remaining > 0 {
+        let chunk = &mut buf[..cmp::min(remaining, chunk_len)];
+        reader.read_exact(chunk)?;
+        blocks.extend(blocks_from_le_bytes::<B, _>(chunk.iter().cloned()));
+        remaining -= chunk.len();
+    }
+    Ok(blocks)
+}
+
+impl<B: BitBlock> BitSet<B> {
+    /// Writes the blocks of this set to `writer`.
+    ///
+    /// The data starts with a small header made of the block size in bytes
+    /// (as a little-endian `u32`) and the number of blocks (as a little-endian
+    /// `u64`), followed by each block in little-endian byte order. The blocks
+    /// are streamed through a small fixed-size buffer, so no copy of the
+    /// whole set is ever built. Use [read_from](#method.read_from) to load
+    /// the set back.
+    ///
+    /// This layout depends on the block type, see
+    /// [serialize_into](#method.serialize_into) for a portable format.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s = BitSet::new();
+    /// s.insert(1);
+    /// s.insert(40);
+    ///
+    /// let mut buf = Vec::new();
+    /// s.write_into(&mut buf).unwrap();
+    ///
+    /// let t = BitSet::read_from(&buf[..]).unwrap();
+    /// assert_eq!(s, t);
+    /// ```
+    pub fn write_into<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let storage = self.bit_vec.storage();
+        writer.write_all(&(B::bytes() as u32).to_le_bytes())?;
+        writer.write_all(&(storage.len() as u64).to_le_bytes())?;
+        write_blocks(&mut writer, storage, storage.len() * B::bytes())
+    }
+
+    /// Reads a set written by [write_into](#method.write_into) from `reader`.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `InvalidData` if the data was written with a
+    /// different block size, and forwards any error from `reader`, including
+    /// `UnexpectedEof` if the data is truncated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet<u64> = (0..100).filter(|x| x % 7 == 0).collect();
+    ///
+    /// let mut buf = Vec::new();
+    /// s.write_into(&mut buf).unwrap();
+    ///
+    /// assert_eq!(BitSet::<u64>::read_from(&buf[..]).unwrap(), s);
+    /// assert!(BitSet::<u32>::read_from(&buf[..]).is_err());
+    /// ```
+    pub fn read_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut header = [0u8; 4];
+        reader.read_exact(&mut header)?;
+        if u32::from_le_bytes(header) as usize != B::bytes() {
+            return Err(invalid_data("mismatched block size"));
+        }
+        let nblocks = read_u64(&mut reader)?;
+        let len = usize::try_from(nblocks)
+            .ok()
+            .filter(|&n| n.checked_mul(B::bits()).is_some())
+            .ok_or_else(|| invalid_data("too many blocks"))?
+            * B::bytes();
+        Ok(BitSet::from_blocks(read_blocks(&mut reader, len)?))
+    }
+
+    /// Writes this set to `writer` in a stable, portable binary format.
+    ///
+    /// Unlike [write_into](#method.write_into), the format doesn't depend on
+    /// the block type nor on the endianness of the machine, so a set written
+    /// by a `BitSet<u64>` can be read back as a `BitSet<u32>`. It is made of:
+    ///
+    /// * the magic bytes `b"BSET"`,
+    /// * the format version, currently `1`, as a single byte,
+    /// * the length in bits of the underlying bit vector, `nbits`, as a
+    ///   little-endian `u64`,
+    /// * `ceil(nbits / 64)` words as little-endian `u64`s, where the element
+    ///   `64 * i + j` is in the set if bit `j` of the `i`-th word is set. Bits
+    ///   at or past `nbits` are zero.
+    ///
+    /// Use [deserialize_from](#method.deserialize_from) to load the set back.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s: BitSet<u64> = BitSet::default();
+    /// s.insert(3);
+    /// s.insert(64);
+    ///
+    /// let mut buf = Vec::new();
+    /// s.serialize_into(&mut buf).unwrap();
+    /// assert_eq!(&buf[..5], b"BSET\x01");
+    ///
+    /// let t = BitSet::<u32>::deserialize_from(&buf[..]).unwrap();
+    /// assert_eq!(t.iter().collect::<Vec<_>>(), [3, 64]);
+    /// ```
+    pub fn serialize_into<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let nbits = self.bit_vec.len();
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&[VERSION])?;
+        writer.write_all(&(nbits as u64).to_le_bytes())?;
+        let nwords = nbits.div_ceil(64);
+        write_blocks(&mut writer, self.bit_vec.storage(), nwords * 8)
+    }
+
+    /// Reads a set written by [serialize_into](#method.serialize_into) from
+    /// `reader`, whatever the block type it was written with.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `InvalidData` if the data doesn't start with
+    /// the expected magic bytes, has an unsupported version, has bits set
+    /// past its length or is too large for this platform. Forwards any error
+    /// from `reader`, including `UnexpectedEof` if the data is truncated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = (0..100).filter(|x| x % 7 == 0).collect();
+    ///
+    /// let mut buf = Vec::new();
+    /// s.serialize_into(&mut buf).unwrap();
+    ///
+    /// let t = BitSet::<u64>::deserialize_from(&buf[..]).unwrap();
+    /// assert!(t.iter().eq(s.iter()));
+    /// ```
+    pub fn deserialize_from<R: Read>(mut reader: R) -> io::Result<Self> {
+        let mut magic = [0u8; 5];
+        reader.read_exact(&mut magic)?;
+        if magic[..4] != MAGIC {
+            return Err(invalid_data("not a serialized bit set"));
+        }
+        if magic[4] != VERSION {
+            return Err(invalid_data("unsupported format version"));
+        }
+        let nbits = read_u64(&mut reader)?;
+        let nbits = usize::try_from(nbits)
+            .map_err(|_| invalid_data("bit set too large for this platform"))?;
+        let nwords = nbits.div_ceil(64);
+
+        let mut blocks = read_blocks::<B, _>(&mut reader, nwords * 8)?;
+        // The words may hold more blocks than needed, and the last block more
+        // bits than needed, but all of those must be padding
+        let nblocks = blocks_for_bits::<B>(nbits);
+        let extra_bits = nbits % B::bits();
+        if blocks[nblocks..].iter().any(|&block| block != B::zero())
+            || (extra_bits != 0 && blocks[nblocks - 1] >> extra_bits != B::zero())
+        {
+            return Err(invalid_data("bits set past the end of the bit set"));
+        }
+        blocks.truncate(nblocks);
+
+        let mut set = BitSet::from_blocks(blocks);
+        unsafe { set.bit_vec.set_len(nbits) };
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::super::BitSet;
+    use super::{write_blocks, write_generic_blocks};
+
+    #[test]
+    fn test_write_blocks() {
+        let blocks: Vec<u16> = (0..5000u16).map(|i| i.wrapping_mul(31)).collect();
+        for &len in &[0, 3, 10_000, 10_001, 20_000] {
+            let (mut fast, mut generic) = (Vec::new(), Vec::new());
+            write_blocks(&mut fast, &blocks, len).unwrap();
+            write_generic_blocks(&mut generic, &blocks, len).unwrap();
+            assert_eq!(fast.len(), len);
+            assert_eq!(fast, generic);
+        }
+    }
+
+    #[test]
+    fn test_bit_set_stream_round_trip() {
+        let empty = BitSet::new();
+        let mut buf = Vec::new();
+        empty.write_into(&mut buf).unwrap();
+        assert_eq!(buf, [4, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(BitSet::read_from(&buf[..]).unwrap(), empty);
+
+        let mut a = BitSet::new();
+        a.insert(0);
+        a.insert(9);
+        buf.clear();
+        a.write_into(&mut buf).unwrap();
+        assert_eq!(&buf[12..], [0b00000001, 0b00000010, 0, 0]);
+
+        // Large enough to span several internal buffers
+        let big: BitSet<u64> = (0..100_000).filter(|x| x % 3 == 1).collect();
+        buf.clear();
+        big.write_into(&mut buf).unwrap();
+        assert_eq!(BitSet::<u64>::read_from(&buf[..]).unwrap(), big);
+    }
+
+    #[test]
+    fn test_bit_set_stream_errors() {
+        let a: BitSet = (0..100).collect();
+        let mut buf = Vec::new();
+        a.write_into(&mut buf).unwrap();
+
+        assert!(BitSet::<u64>::read_from(&buf[..]).is_err());
+        assert!(BitSet::<u32>::read_from(&buf[..buf.len() - 1]).is_err());
+        assert!(BitSet::<u32>::read_from(&buf[..3]).is_err());
+
+        // A bogus block count fails on the truncated data
+        buf[4..12].copy_from_slice(&u64::MAX.to_le_bytes());
+        assert!(BitSet::<u32>::read_from(&buf[..]).is_err());
+    }
+
+    #[test]
+    fn test_bit_set_serialize_portable() {
+        let mut a: BitSet<u64> = BitSet::default();
+        a.insert(0);
+        a.insert(9);
+        a.insert(64);
+        let mut buf = Vec::new();
+        a.serialize_into(&mut buf).unwrap();
+        assert_eq!(&buf[..5], b"BSET\x01");
+        assert_eq!(&buf[5..13], [65, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(&buf[13..], [1, 2, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0]);
+
+        // The same set written with another block size is the same data
+        let b: BitSet<u32> = a.iter().collect();
+        let mut buf_b = Vec::new();
+        b.serialize_into(&mut buf_b).unwrap();
+        assert_eq!(buf, buf_b);
+
+        for &(bytes, set) in &[(&buf, &a), (&buf_b, &a)] {
+            let c = BitSet::<u32>::deserialize_from(&bytes[..]).unwrap();
+            assert!(c.iter().eq(set.iter()));
+            assert_eq!(c.get_ref().len(), 65);
+            let d = BitSet::<u8>::deserialize_from(&bytes[..]).unwrap();
+            assert!(d.iter().eq(set.iter()));
+            assert_eq!(d.as_block_slice().len(), 9);
+        }
+
+        let empty = BitSet::new();
+        buf.clear();
+        empty.serialize_into(&mut buf).unwrap();
+        assert_eq!(buf.len(), 13);
+        assert!(BitSet::<u64>::deserialize_from(&buf[..]).unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_bit_set_deserialize_errors() {
+        let a: BitSet = (0..70).collect();
+        let mut buf = Vec::new();
+        a.serialize_into(&mut buf).unwrap();
+        assert!(BitSet::<u32>::deserialize_from(&buf[..]).is_ok());
+        assert!(BitSet::<u32>::deserialize_from(&buf[..buf.len() - 1]).is_err());
+
+        let mut bad_magic = buf.clone();
+        bad_magic[0] = b'X';
+        assert!(BitSet::<u32>::deserialize_from(&bad_magic[..]).is_err());
+
+        let mut bad_version = buf.clone();
+        bad_version[4] = 2;
+        assert!(BitSet::<u32>::deserialize_from(&bad_version[..]).is_err());
+
+        // Bit 70 is past the declared length of 70 bits
+        let mut bad_padding = buf.clone();
+        bad_padding[13 + 8] |= 0b0100_0000;
+        assert!(BitSet::<u32>::deserialize_from(&bad_padding[..]).is_err());
+        assert!(BitSet::<u64>::deserialize_from(&bad_padding[..]).is_err());
+    }
+}
@@ -0,0 +1,237 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A set whose changes can be rolled back to earlier snapshots.
+
+use alloc::vec::Vec;
+use core::ops::Deref;
+
+use bit_vec::BitBlock;
+
+use BitSet;
+
+/// A point a `JournaledBitSet` can be rolled back to, returned by
+/// [`snapshot`](struct.JournaledBitSet.html#method.snapshot).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Snapshot {
+    journal_len: usize,
+    nbits: usize,
+}
+
+/// A `BitSet` with a journal of its changes, so that it can be rolled back
+/// to a snapshot in time proportional to the changes since.
+///
+/// While a snapshot is held, each change saves the previous content of its
+/// block, which makes taking a snapshot free and suits backtracking
+/// searches. Snapshots nest: rolling back to one discards the newer ones.
+/// All the read-only methods of `BitSet` are available through `Deref`.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::JournaledBitSet;
+///
+/// let mut s = JournaledBitSet::new();
+/// s.insert(1);
+///
+/// let before = s.snapshot();
+/// s.insert(2);
+/// s.remove(1);
+/// s.insert(1000);
+///
+/// s.rollback(before);
+/// assert_eq!(s.iter().collect::<Vec<_>>(), [1]);
+/// ```
+#[derive(Debug)]
+pub struct JournaledBitSet<B: BitBlock = u32> {
+    set: BitSet<B>,
+    /// The previous content of each changed block, with its index.
+    journal: Vec<(usize, B)>,
+    /// Whether a snapshot was taken since the last commit.
+    recording: bool,
+}
+
+impl<B: BitBlock> Clone for JournaledBitSet<B> {
+    #[inline]
+    fn clone(&self) -> Self {
+        JournaledBitSet {
+            set: self.set.clone(),
+            journal: self.journal.clone(),
+            recording: self.recording,
+        }
+    }
+
+    #[inline]
+    fn clone_from(&mut self, other: &Self) {
+        self.set.clone_from(&other.set);
+        self.journal.clone_from(&other.journal);
+        self.recording = other.recording;
+    }
+}
+
+impl<B: BitBlock> Default for JournaledBitSet<B> {
+    #[inline]
+    fn default() -> Self {
+        JournaledBitSet::from_bit_set(BitSet::default())
+    }
+}
+
+impl JournaledBitSet<u32> {
+    /// Creates a new empty `JournaledBitSet`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B: BitBlock> JournaledBitSet<B> {
+    /// Wraps `set`, with an empty journal.
+    #[inline]
+    pub fn from_bit_set(set: BitSet<B>) -> Self {
+        JournaledBitSet { set, journal: Vec::new(), recording: false }
+    }
+
+    /// Saves the content of the block at `index` if a snapshot is held.
+    fn record(&mut self, index: usize) {
+        if self.recording {
+            let block = self.set.as_block_slice().get(index).cloned().unwrap_or(B::zero());
+            self.journal.push((index, block));
+        }
+    }
+
+    /// Adds a value to the set. Returns `true` if the value was not already
+    /// present in the set.
+    pub fn insert(&mut self, value: usize) -> bool {
+        if self.set.contains(value) {
+            return false;
+        }
+        self.record(value / B::bits());
+        self.set.insert(value)
+    }
+
+    /// Removes a value from the set. Returns `true` if the value was
+    /// present in the set.
+    pub fn remove(&mut self, value: usize) -> bool {
+        if !self.set.contains(value) {
+            return false;
+        }
+        self.record(value / B::bits());
+        self.set.remove(value)
+    }
+
+    /// Removes all values from the set.
+    pub fn clear(&mut self) {
+        for index in 0..self.set.as_block_slice().len() {
+            if self.set.as_block_slice()[index] != B::zero() {
+                self.record(index);
+            }
+        }
+        self.set.clear();
+    }
+
+    /// Returns a snapshot of the current state, to pass to
+    /// [`rollback`](#method.rollback).
+    #[inline]
+    pub fn snapshot(&mut self) -> Snapshot {
+        self.recording = true;
+        Snapshot { journal_len: self.journal.len(), nbits: self.set.bit_vec.len() }
+    }
+
+    /// Restores the state at the time of `snapshot`, undoing every change
+    /// since. The snapshot stays valid, as do the older ones, but not the
+    /// newer ones.
+    ///
+    /// Rolling back to a snapshot discarded by a rollback to an older one or
+    /// by [`commit`](#method.commit) is a logic error.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `snapshot` is detected to be discarded, which is the case
+    /// until as many changes as were discarded are made.
+    pub fn rollback(&mut self, snapshot: Snapshot) {
+        assert!(self.recording && snapshot.journal_len <= self.journal.len(),
+                "rollback to a discarded snapshot");
+        let blocks = unsafe { self.set.bit_vec.storage_mut() };
+        for (index, block) in self.journal.drain(snapshot.journal_len..).rev() {
+            blocks[index] = block;
+        }
+        self.set.truncate(snapshot.nbits);
+    }
+
+    /// Discards the journal and all the snapshots, keeping the current
+    /// state.
+    #[inline]
+    pub fn commit(&mut self) {
+        self.journal.clear();
+        self.recording = false;
+    }
+
+    /// Returns the underlying `BitSet`, dropping the journal.
+    #[inline]
+    pub fn into_inner(self) -> BitSet<B> {
+        self.set
+    }
+}
+
+impl<B: BitBlock> Deref for JournaledBitSet<B> {
+    type Target = BitSet<B>;
+
+    #[inline]
+    fn deref(&self) -> &BitSet<B> {
+        &self.set
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use BitSet;
+    use super::JournaledBitSet;
+
+    #[test]
+    fn test_journaled_bit_set() {
+        let set: BitSet<u8> = [1, 9].iter().cloned().collect();
+        let mut s = JournaledBitSet::from_bit_set(set.clone());
+
+        let outer = s.snapshot();
+        s.insert(2);
+        s.remove(9);
+        let inner = s.snapshot();
+        s.insert(200);
+        s.clear();
+        s.insert(3);
+        assert_eq!(s.iter().collect::<Vec<_>>(), [3]);
+
+        s.rollback(inner);
+        assert_eq!(s.iter().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(s.get_ref().len(), 10);
+        s.insert(50);
+        s.rollback(inner);
+        assert_eq!(s.iter().collect::<Vec<_>>(), [1, 2]);
+
+        s.rollback(outer);
+        assert_eq!(*s, set);
+        assert_eq!(s.get_ref().len(), set.get_ref().len());
+
+        s.insert(4);
+        s.commit();
+        s.insert(5);
+        assert_eq!(s.into_inner().iter().collect::<Vec<_>>(), [1, 4, 5, 9]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_rollback_discarded() {
+        let mut s = JournaledBitSet::new();
+        let outer = s.snapshot();
+        s.insert(1);
+        let inner = s.snapshot();
+        s.insert(2);
+        s.rollback(outer);
+        s.rollback(inner);
+    }
+}
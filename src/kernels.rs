@@ -0,0 +1,151 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! The set operations on raw blocks, shared by `BitSet`, `BitSetView` and
+//! `GenericBitSet`.
+//!
+//! The kernels only see slices: growing the storage, when an operation needs
+//! it, is left to the callers. Bit `i` of block `n` is the value
+//! `n * B::bits() + i`, and the values past the end of a slice are absent.
+
+use core::cmp;
+
+use bit_vec::BitBlock;
+
+#[cfg(feature = "simd")]
+use simd;
+
+/// A bitwise operation combining the blocks of two sets.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Op {
+    Or,
+    And,
+    AndNot,
+    Xor,
+}
+
+/// Sets each block of `blocks` to `f` of it and the block of `other` at the
+/// same index.
+#[inline]
+fn zip_with<B: BitBlock, F: Fn(B, B) -> B>(blocks: &mut [B], other: &[B], f: F) {
+    for (block, &w) in blocks.iter_mut().zip(other) {
+        *block = f(*block, w);
+    }
+}
+
+/// Applies `op` to the blocks both slices have, leaving the blocks of
+/// `blocks` past the end of `other` as they are.
+pub(crate) fn apply<B: BitBlock>(blocks: &mut [B], other: &[B], op: Op) {
+    let n = cmp::min(blocks.len(), other.len());
+    let (blocks, other) = (&mut blocks[..n], &other[..n]);
+    #[cfg(feature = "simd")]
+    {
+        if simd::is_supported::<B>() {
+            simd::apply(blocks, other, op);
+            return;
+        }
+    }
+    match op {
+        Op::Or => zip_with(blocks, other, |a, b| a | b),
+        Op::And => zip_with(blocks, other, |a, b| a & b),
+        Op::AndNot => zip_with(blocks, other, |a, b| a & !b),
+        Op::Xor => zip_with(blocks, other, |a, b| a ^ b),
+    }
+}
+
+/// Intersects `blocks` with `other`, clearing the blocks past its end.
+pub(crate) fn intersect<B: BitBlock>(blocks: &mut [B], other: &[B]) {
+    apply(blocks, other, Op::And);
+    for block in blocks.iter_mut().skip(other.len()) {
+        *block = B::zero();
+    }
+}
+
+/// Sets the bit of `value`, which must be inside `blocks`. Returns `true`
+/// if it wasn't set.
+#[inline]
+pub(crate) fn insert<B: BitBlock>(blocks: &mut [B], value: usize) -> bool {
+    let block = &mut blocks[value / B::bits()];
+    let mask = B::one() << (value % B::bits());
+    let inserted = *block & mask == B::zero();
+    *block = *block | mask;
+    inserted
+}
+
+/// Clears the bit of `value`. Returns `true` if it was set.
+#[inline]
+pub(crate) fn remove<B: BitBlock>(blocks: &mut [B], value: usize) -> bool {
+    match blocks.get_mut(value / B::bits()) {
+        Some(block) => {
+            let mask = B::one() << (value % B::bits());
+            let removed = *block & mask != B::zero();
+            *block = *block & !mask;
+            removed
+        }
+        None => false,
+    }
+}
+
+/// Returns the number of values in `blocks`.
+#[inline]
+pub(crate) fn count_ones<B: BitBlock>(blocks: &[B]) -> usize {
+    #[cfg(feature = "simd")]
+    {
+        if simd::is_supported::<B>() {
+            return simd::count_ones(blocks);
+        }
+    }
+    blocks.iter().map(|block| block.count_ones()).sum()
+}
+
+/// Returns `true` if `blocks` hold no value.
+#[inline]
+pub(crate) fn is_empty<B: BitBlock>(blocks: &[B]) -> bool {
+    blocks.iter().all(|&block| block == B::zero())
+}
+
+/// Returns `true` if `a` and `b` have no value in common.
+#[inline]
+pub(crate) fn is_disjoint<B: BitBlock>(a: &[B], b: &[B]) -> bool {
+    a.iter().zip(b).all(|(&a, &b)| a & b == B::zero())
+}
+
+/// Returns `true` if every value of `a` is in `b`.
+#[inline]
+pub(crate) fn is_subset<B: BitBlock>(a: &[B], b: &[B]) -> bool {
+    let n = cmp::min(a.len(), b.len());
+    a[..n].iter().zip(b).all(|(&a, &b)| a & !b == B::zero()) && is_empty(&a[n..])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{apply, count_ones, insert, intersect, is_disjoint, is_empty, is_subset, remove, Op};
+
+    #[test]
+    fn test_kernels() {
+        let mut a = [0b1100u8, 0b0001, 0b1000];
+        let b = [0b1010u8, 0b0011];
+        apply(&mut a, &b, Op::Xor);
+        assert_eq!(a, [0b0110, 0b0010, 0b1000]);
+        apply(&mut a, &b, Op::Or);
+        assert_eq!(a, [0b1110, 0b0011, 0b1000]);
+        apply(&mut a, &b, Op::AndNot);
+        assert_eq!(a, [0b0100, 0, 0b1000]);
+        let mut c = a;
+        intersect(&mut c, &b[..1]);
+        assert_eq!(c, [0, 0, 0]);
+        assert!(is_empty(&c) && !is_empty(&a));
+
+        assert_eq!(count_ones(&a), 2);
+        assert!(insert(&mut a, 8) && !insert(&mut a, 8));
+        assert!(remove(&mut a, 2) && !remove(&mut a, 2) && !remove(&mut a, 100));
+        assert_eq!(a, [0, 1, 0b1000]);
+
+        assert!(is_subset(&[0b0010u8], &b) && !is_subset(&a, &b));
+        assert!(is_subset(&[0b0010u8, 0, 0], &b));
+        assert!(is_disjoint(&[0b0101u8], &b) && !is_disjoint(&a, &b));
+    }
+}
@@ -0,0 +1,265 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A set with summary layers to skip empty regions quickly.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FromIterator;
+
+use bit_vec::BitBlock;
+
+use BitSet;
+
+/// Sets bit `index` in `layer`, growing it as needed.
+fn set_bit(layer: &mut Vec<u64>, index: usize) {
+    if index / 64 >= layer.len() {
+        layer.resize(index / 64 + 1, 0);
+    }
+    layer[index / 64] |= 1 << (index % 64);
+}
+
+/// A set of `usize`s stored as 64-bit words, with two summary layers above
+/// them: each bit of the first summary tells whether a word holds values,
+/// and each bit of the second whether a word of the first summary is
+/// non-zero.
+///
+/// Iteration descends from the top layer, so empty ranges of up to 2^18
+/// values are skipped by looking at a single word, and `is_empty` only looks
+/// at the top layer. This suits sets with a few values scattered over a large
+/// universe, at the cost of updating the summaries on insertion and removal.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::LayeredBitSet;
+///
+/// let mut s = LayeredBitSet::new();
+/// s.insert(10);
+/// s.insert(100_000_000);
+///
+/// assert_eq!(s.iter().collect::<Vec<_>>(), [10, 100_000_000]);
+/// s.remove(10);
+/// s.remove(100_000_000);
+/// assert!(s.is_empty());
+/// ```
+#[derive(Clone, Default)]
+pub struct LayeredBitSet {
+    layer0: Vec<u64>,
+    layer1: Vec<u64>,
+    layer2: Vec<u64>,
+}
+
+impl LayeredBitSet {
+    /// Creates a new empty `LayeredBitSet`.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Returns `true` if this set contains the specified integer.
+    #[inline]
+    pub fn contains(&self, value: usize) -> bool {
+        self.layer0.get(value / 64).is_some_and(|&word| word & (1 << (value % 64)) != 0)
+    }
+
+    /// Adds a value to the set. Returns `true` if the value was not already
+    /// present in the set.
+    pub fn insert(&mut self, value: usize) -> bool {
+        if self.contains(value) {
+            return false;
+        }
+        set_bit(&mut self.layer0, value);
+        set_bit(&mut self.layer1, value / 64);
+        set_bit(&mut self.layer2, value / 64 / 64);
+        true
+    }
+
+    /// Removes a value from the set. Returns `true` if the value was
+    /// present in the set.
+    pub fn remove(&mut self, value: usize) -> bool {
+        if !self.contains(value) {
+            return false;
+        }
+        let (i0, i1) = (value / 64, value / 64 / 64);
+        self.layer0[i0] &= !(1 << (value % 64));
+        if self.layer0[i0] == 0 {
+            self.layer1[i1] &= !(1 << (i0 % 64));
+            if self.layer1[i1] == 0 {
+                self.layer2[i1 / 64] &= !(1 << (i1 % 64));
+            }
+        }
+        true
+    }
+
+    /// Returns the number of values in the set.
+    pub fn len(&self) -> usize {
+        self.iter_words().map(|(_, word)| word.count_ones() as usize).sum()
+    }
+
+    /// Returns `true` if the set holds no value, looking only at the top
+    /// summary layer.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.layer2.iter().all(|&word| word == 0)
+    }
+
+    /// Removes all values from the set.
+    pub fn clear(&mut self) {
+        self.layer0.clear();
+        self.layer1.clear();
+        self.layer2.clear();
+    }
+
+    /// Iterator over the non-zero words of the bottom layer with their
+    /// index, skipping the empty ones through the summaries.
+    fn iter_words(&self) -> Words<'_> {
+        Words { set: self, top: 0, w2: 0, i1: 0, w1: 0 }
+    }
+
+    /// Iterator over each value in the set, in ascending order.
+    pub fn iter(&self) -> LayeredIter<'_> {
+        LayeredIter { words: self.iter_words(), base: 0, word: 0 }
+    }
+
+    /// Returns a `BitSet` holding the values of this set.
+    pub fn to_bit_set<B: BitBlock>(&self) -> BitSet<B> {
+        self.iter().collect()
+    }
+}
+
+impl<'a, B: BitBlock> From<&'a BitSet<B>> for LayeredBitSet {
+    fn from(set: &'a BitSet<B>) -> Self {
+        set.iter().collect()
+    }
+}
+
+impl FromIterator<usize> for LayeredBitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = LayeredBitSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl Extend<usize> for LayeredBitSet {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl PartialEq for LayeredBitSet {
+    fn eq(&self, other: &Self) -> bool {
+        self.iter_words().eq(other.iter_words())
+    }
+}
+
+impl Eq for LayeredBitSet {}
+
+impl<'a> IntoIterator for &'a LayeredBitSet {
+    type Item = usize;
+    type IntoIter = LayeredIter<'a>;
+
+    fn into_iter(self) -> LayeredIter<'a> {
+        self.iter()
+    }
+}
+
+impl fmt::Debug for LayeredBitSet {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_set().entries(self).finish()
+    }
+}
+
+/// The non-zero bottom words of a `LayeredBitSet` and their index.
+#[derive(Clone)]
+struct Words<'a> {
+    set: &'a LayeredBitSet,
+    /// The index of the next top layer word to load.
+    top: usize,
+    /// The bits of the current top word and first summary word not
+    /// visited yet.
+    w2: u64,
+    i1: usize,
+    w1: u64,
+}
+
+impl<'a> Iterator for Words<'a> {
+    type Item = (usize, u64);
+
+    fn next(&mut self) -> Option<(usize, u64)> {
+        loop {
+            if self.w1 != 0 {
+                let i0 = self.i1 * 64 + self.w1.trailing_zeros() as usize;
+                self.w1 &= self.w1 - 1;
+                return Some((i0, self.set.layer0[i0]));
+            }
+            if self.w2 != 0 {
+                self.i1 = (self.top - 1) * 64 + self.w2.trailing_zeros() as usize;
+                self.w2 &= self.w2 - 1;
+                self.w1 = self.set.layer1[self.i1];
+                continue;
+            }
+            self.w2 = *self.set.layer2.get(self.top)?;
+            self.top += 1;
+        }
+    }
+}
+
+/// An iterator for `LayeredBitSet`.
+#[derive(Clone)]
+pub struct LayeredIter<'a> {
+    words: Words<'a>,
+    base: usize,
+    word: u64,
+}
+
+impl<'a> Iterator for LayeredIter<'a> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.word == 0 {
+            let (index, word) = self.words.next()?;
+            self.base = index * 64;
+            self.word = word;
+        }
+        let value = self.base + self.word.trailing_zeros() as usize;
+        self.word &= self.word - 1;
+        Some(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::LayeredBitSet;
+    use BitSet;
+
+    #[test]
+    fn test_layered_bit_set() {
+        let values = [0, 63, 64, 4095, 4096, 262_143, 262_144, 50_000_000];
+        let mut s: LayeredBitSet = values.iter().cloned().collect();
+        assert_eq!(s.iter().collect::<Vec<_>>(), values);
+        assert_eq!(s.len(), values.len());
+        assert!(!s.insert(4096));
+        assert!(!s.contains(1));
+
+        let dense: BitSet = s.to_bit_set();
+        assert_eq!(LayeredBitSet::from(&dense), s);
+
+        for &v in values.iter().rev() {
+            assert!(s.remove(v));
+            assert!(!s.remove(v));
+        }
+        assert!(s.is_empty());
+        assert_eq!(s.iter().next(), None);
+        // Storage is kept, but the summaries are all clear
+        assert_eq!(s, LayeredBitSet::new());
+    }
+}
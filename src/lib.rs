@@ -51,21 +51,158 @@
 
 #![cfg_attr(all(test, feature = "nightly"), feature(test))]
 #[cfg(all(test, feature = "nightly"))] extern crate test;
-#[cfg(all(test, feature = "nightly"))] extern crate rand;
+extern crate alloc;
 extern crate bit_vec;
-
+#[cfg(feature = "bitvec")]
+extern crate bitvec;
+#[cfg(feature = "borsh")]
+extern crate borsh;
+#[cfg(feature = "fixedbitset")]
+extern crate fixedbitset;
+#[cfg(feature = "hibitset")]
+extern crate hibitset;
+#[cfg(feature = "lz4")]
+extern crate lz4_flex;
+#[cfg(feature = "petgraph")]
+extern crate petgraph;
+#[cfg(feature = "proptest")]
+extern crate proptest;
+#[cfg(any(feature = "rand", test))]
+extern crate rand;
+#[cfg(feature = "rayon")]
+extern crate rayon;
+#[cfg(feature = "rkyv")]
+extern crate rkyv;
+#[cfg(feature = "roaring")]
+extern crate roaring;
+#[cfg(feature = "serde")]
+extern crate serde;
 #[cfg(test)]
+extern crate serde_test;
+
+#[cfg(any(test, feature = "std"))]
 #[macro_use]
 extern crate std;
 
+use alloc::collections::TryReserveError;
+use alloc::vec::Vec;
 use bit_vec::{BitVec, Blocks, BitBlock};
+use core::borrow::Borrow;
 use core::cmp::Ordering;
 use core::cmp;
-use core::fmt;
+use core::fmt::{self, Write};
 use core::hash;
-use core::iter::{self, Chain, Enumerate, FromIterator, Repeat, Skip, Take};
+use core::iter::{FromIterator, Take};
+use core::mem;
+use core::ops::{Index, Range};
+
+pub use adaptive::{AdaptiveBitSet, AdaptiveIter};
+pub use allocator::BitSetAllocator;
+#[cfg(feature = "rkyv")]
+pub use archive::{ArchivedBitSet, ArchivedIter};
+pub use bounded::{BoundedBitSet, LimitError};
+pub use buffered::BufferedBitSet;
+#[cfg(target_has_atomic = "64")]
+pub use atomic::{AtomicBitSet, AtomicDrain};
+pub use const_set::{ConstBitSet, ConstIter};
+#[cfg(target_has_atomic = "ptr")]
+pub use cow::CowBitSet;
+pub use elias_fano::{EliasFanoIter, EliasFanoSet};
+pub use expr::{Expr, ExprIter};
+pub use frozen::FrozenBitSet;
+pub use hinted::HintedBitSet;
+pub use interop::OutOfRangeError;
+pub use journaled::{JournaledBitSet, Snapshot};
+pub use layered::{LayeredBitSet, LayeredIter};
+pub use matrix::{BitMatrix, Column, Row, RowIter};
+pub use parse::ParseBitSetError;
+#[cfg(target_has_atomic = "ptr")]
+pub use persistent::{PersistentBitSet, PersistentIter};
+pub use pool::{BitSetPool, PooledBitSet};
+#[cfg(feature = "std")]
+pub use published::{PublishedBitSet, PublishedReader};
+pub use rle::{RleBitSet, RleIter};
+#[cfg(feature = "std")]
+pub use sharded::ShardedBitSet;
+pub use small::{SmallBitSet, SmallBlocks};
+pub use sparse::{SparseBitSet, SparseIter};
+pub use storage::{BitSetMut, BlockStorage, BlockStorageMut, GenericBitSet};
+pub use tracked::{Changes, TrackedBitSet};
+pub use two_phase::TwoPhaseBitSet;
+pub use typed::{BitIndex, TypedBitSet, TypedIter};
+pub use varint::DecodeVarintError;
+pub use view::{BitSetView, ViewError, ViewIter};
+#[doc(hidden)]
+pub use macros::{__from_items, __BitSetItem};
 
-type MatchWords<'a, B> = Chain<Enumerate<Blocks<'a, B>>, Skip<Take<Enumerate<Repeat<B>>>>>;
+#[macro_use]
+mod macros;
+mod adaptive;
+mod allocator;
+#[cfg(feature = "rkyv")]
+mod archive;
+mod bounded;
+mod buffered;
+#[cfg(target_has_atomic = "64")]
+mod atomic;
+#[cfg(feature = "borsh")]
+mod borsh_format;
+#[cfg(feature = "lz4")]
+mod compressed;
+mod const_set;
+#[cfg(target_has_atomic = "ptr")]
+mod cow;
+mod digest;
+mod elias_fano;
+mod expr;
+mod frozen;
+mod hinted;
+mod ids;
+mod kernels;
+mod interop;
+#[cfg(feature = "std")]
+mod io;
+mod journaled;
+mod layered;
+mod matrix;
+#[cfg(any(test, fuzzing))]
+#[doc(hidden)]
+pub mod oracle;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod parse;
+#[cfg(target_has_atomic = "ptr")]
+mod persistent;
+mod pool;
+mod primitive;
+#[cfg(feature = "proptest")]
+pub mod prop;
+#[cfg(feature = "std")]
+mod published;
+#[cfg(feature = "rand")]
+mod random;
+mod rle;
+#[cfg(feature = "std")]
+mod roaring_format;
+#[cfg(feature = "serde")]
+mod serde_format;
+#[cfg(feature = "simd")]
+mod simd;
+#[cfg(feature = "std")]
+mod sharded;
+mod shift;
+mod similarity;
+mod small;
+mod sparse;
+mod storage;
+mod tracked;
+mod two_phase;
+mod typed;
+mod varint;
+mod view;
+
+/// The number of blocks whose counts `BitSet::threshold` keeps at once.
+const THRESHOLD_STRIP: usize = 64;
 
 /// Computes how many blocks are needed to store that many bits
 fn blocks_for_bits<B: BitBlock>(bits: usize) -> usize {
@@ -77,35 +214,134 @@ fn blocks_for_bits<B: BitBlock>(bits: usize) -> usize {
     //
     // Note that we can technically avoid this branch with the expression
     // `(nbits + BITS - 1) / 32::BITS`, but if nbits is almost usize::MAX this will overflow.
-    if bits % B::bits() == 0 {
+    if bits.is_multiple_of(B::bits()) {
         bits / B::bits()
     } else {
         bits / B::bits() + 1
     }
 }
 
-// Take two BitVec's, and return iterators of their words, where the shorter one
-// has been padded with 0's
-fn match_words<'a, 'b, B: BitBlock>(a: &'a BitVec<B>, b: &'b BitVec<B>)
-    -> (MatchWords<'a, B>, MatchWords<'b, B>)
-{
-    let a_len = a.storage().len();
-    let b_len = b.storage().len();
-
-    // have to uselessly pretend to pad the longer one for type matching
-    if a_len < b_len {
-        (a.blocks().enumerate().chain(iter::repeat(B::zero()).enumerate().take(b_len).skip(a_len)),
-         b.blocks().enumerate().chain(iter::repeat(B::zero()).enumerate().take(0).skip(0)))
-    } else {
-        (a.blocks().enumerate().chain(iter::repeat(B::zero()).enumerate().take(0).skip(0)),
-         b.blocks().enumerate().chain(iter::repeat(B::zero()).enumerate().take(a_len).skip(b_len)))
+/// Returns the `k`-th least significant byte of `block`.
+fn block_byte<B: BitBlock>(block: B, k: usize) -> u8 {
+    let mut byte = 0;
+    for bit in 0..8 {
+        if (block >> (8 * k + bit)) & B::one() != B::zero() {
+            byte |= 1 << bit;
+        }
+    }
+    byte
+}
+
+/// Packs a stream of bytes into blocks, least significant byte first. The
+/// last block is padded with zeros.
+fn blocks_from_le_bytes<B: BitBlock, I>(bytes: I) -> Vec<B> where I: IntoIterator<Item = u8> {
+    let mut blocks = Vec::new();
+    let mut block = B::zero();
+    let mut k = 0;
+    for byte in bytes {
+        block = block | (B::from_byte(byte) << (8 * k));
+        k += 1;
+        if k == B::bytes() {
+            blocks.push(block);
+            block = B::zero();
+            k = 0;
+        }
+    }
+    if k > 0 {
+        blocks.push(block);
+    }
+    blocks
+}
+
+/// Returns the index of the least significant set bit of a non-zero `block`.
+#[inline]
+fn lowest_bit<B: BitBlock>(block: B) -> usize {
+    // The primitive integers use their `trailing_zeros`, a single
+    // instruction on most CPUs. The check is on the type, so only one branch
+    // is left after inlining.
+    if let Some(zeros) = primitive::trailing_zeros(block) {
+        return zeros;
+    }
+    // Isolate the LSB and subtract 1, leaving as many set bits as its index
+    ((block & (!block + B::one())) - B::one()).count_ones()
+}
+
+/// Returns the index of the most significant set bit of a non-zero `block`.
+#[inline]
+fn highest_bit<B: BitBlock>(block: B) -> usize {
+    // Same dispatch as `lowest_bit`, to use `leading_zeros`
+    if let Some(zeros) = primitive::leading_zeros(block) {
+        return B::bits() - 1 - zeros;
+    }
+    // Smear the MSB into all the bits below it, leaving one more set bit
+    // than its index
+    let (mut smeared, mut shift) = (block, 1);
+    while shift < B::bits() {
+        smeared = smeared | (smeared >> shift);
+        shift *= 2;
+    }
+    smeared.count_ones() - 1
+}
+
+/// Returns `true` if the bit `value` is set in `blocks`, reading the block
+/// directly. The bits past the length of a bit vector are clear, so this
+/// matches `contains` on its storage.
+#[inline]
+fn block_contains<B: BitBlock>(blocks: &[B], value: usize) -> bool {
+    match blocks.get(value / B::bits()) {
+        Some(&block) => (block >> (value % B::bits())) & B::one() != B::zero(),
+        None => false,
+    }
+}
+
+/// Returns the index of the first bit at or after `from` that is equal to
+/// `value`, looking only at the bits stored in `blocks`.
+fn find_bit<B: BitBlock>(blocks: &[B], from: usize, value: bool) -> Option<usize> {
+    let load = |i: usize| if value { blocks[i] } else { !blocks[i] };
+    let mut i = from / B::bits();
+    if i >= blocks.len() {
+        return None;
+    }
+    let mut block = load(i) & (!B::zero() << (from % B::bits()));
+    while block == B::zero() {
+        i += 1;
+        if i == blocks.len() {
+            return None;
+        }
+        block = load(i);
     }
+    Some(i * B::bits() + lowest_bit(block))
 }
 
 pub struct BitSet<B = u32> {
     bit_vec: BitVec<B>,
 }
 
+/// A `BitSet` with 64-bit blocks, halving the number of blocks visited by
+/// the word-level operations compared to the default `u32` blocks.
+///
+/// The default block type of `BitSet` stays `u32`, as changing it from a
+/// feature would change the type of `BitSet` for every crate in the build.
+///
+/// These are the widest blocks available: the blocks must implement
+/// `bit_vec::BitBlock`, which `bit-vec` 0.6 provides for the unsigned
+/// integers up to `u64` and `usize` but not for `u128`, and which this crate
+/// can't implement for a foreign type.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BitSet64;
+///
+/// let mut s = BitSet64::default();
+/// s.insert(100);
+/// assert_eq!(s.as_block_slice(), [0, 1 << 36]);
+/// ```
+pub type BitSet64 = BitSet<u64>;
+
+/// A `BitSet` with blocks of the native word size, `usize`.
+pub type NativeBitSet = BitSet<usize>;
+
 impl<B: BitBlock> Clone for BitSet<B> {
     fn clone(&self) -> Self {
         BitSet {
@@ -113,8 +349,15 @@ impl<B: BitBlock> Clone for BitSet<B> {
         }
     }
 
+    /// Copies the blocks of `other` into the existing storage, which is only
+    /// reallocated if it is too small.
     fn clone_from(&mut self, other: &Self) {
-        self.bit_vec.clone_from(&other.bit_vec);
+        unsafe {
+            let blocks = self.bit_vec.storage_mut();
+            blocks.clear();
+            blocks.extend_from_slice(other.bit_vec.storage());
+            self.bit_vec.set_len(other.bit_vec.len());
+        }
     }
 }
 
@@ -140,10 +383,119 @@ impl<B: BitBlock> Extend<usize> for BitSet<B> {
     }
 }
 
+impl<'a, B: BitBlock> FromIterator<&'a usize> for BitSet<B> {
+    fn from_iter<I: IntoIterator<Item = &'a usize>>(iter: I) -> Self {
+        let mut ret = Self::default();
+        ret.extend(iter);
+        ret
+    }
+}
+
+/// Extends the set with the values of an iterator over references, such as
+/// `slice.iter()`. `u32` and `u64` values are taken by
+/// [`extend_u32`](#method.extend_u32) and [`extend_u64`](#method.extend_u64),
+/// as more `Extend` impls would leave integer literals ambiguous.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BitSet;
+///
+/// let ids = [4, 1, 4];
+/// let mut s: BitSet = ids.iter().collect();
+/// s.extend(&[7, 1]);
+/// assert_eq!(s.iter().collect::<Vec<_>>(), [1, 4, 7]);
+/// ```
+impl<'a, B: BitBlock> Extend<&'a usize> for BitSet<B> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = &'a usize>>(&mut self, iter: I) {
+        self.extend(iter.into_iter().cloned());
+    }
+}
+
+static TRUE: bool = true;
+static FALSE: bool = false;
+
+/// Collects a stream of booleans, the `i`-th one telling whether `i` is in
+/// the set. The set is built a whole block at a time.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BitSet;
+///
+/// let s: BitSet = [true, false, false, true].iter().cloned().collect();
+/// assert_eq!(s.iter().collect::<Vec<_>>(), [0, 3]);
+/// ```
+impl<B: BitBlock> FromIterator<bool> for BitSet<B> {
+    fn from_iter<I: IntoIterator<Item = bool>>(iter: I) -> Self {
+        let iter = iter.into_iter();
+        let bits = B::bits();
+        let mut blocks = Vec::with_capacity(blocks_for_bits::<B>(iter.size_hint().0));
+        let (mut block, mut nbits) = (B::zero(), 0);
+        for bit in iter {
+            if bit {
+                block = block | (B::one() << (nbits % bits));
+            }
+            nbits += 1;
+            if nbits.is_multiple_of(bits) {
+                blocks.push(block);
+                block = B::zero();
+            }
+        }
+        if !nbits.is_multiple_of(bits) {
+            blocks.push(block);
+        }
+        let mut set = BitSet::from_blocks(blocks);
+        set.bit_vec.truncate(nbits);
+        set
+    }
+}
+
+/// Adds `i` to the set for each `i`-th boolean of the stream that is
+/// `true`. The values of the `false` ones are left as they are.
+impl<B: BitBlock> Extend<bool> for BitSet<B> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = bool>>(&mut self, iter: I) {
+        let values = iter.into_iter().enumerate().filter(|&(_, bit)| bit).map(|(i, _)| i);
+        self.insert_grouped(values);
+    }
+}
+
+impl<B: BitBlock> From<BitVec<B>> for BitSet<B> {
+    /// Creates a set from a bit vector with any block type, like
+    /// [`from_bit_vec`](struct.BitSet.html#method.from_bit_vec).
+    #[inline]
+    fn from(bit_vec: BitVec<B>) -> Self {
+        BitSet { bit_vec }
+    }
+}
+
+impl<B: BitBlock> Index<usize> for BitSet<B> {
+    type Output = bool;
+
+    /// Returns `true` if the set contains `value`, like
+    /// [`contains`](#method.contains).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = [1, 5].iter().cloned().collect();
+    /// assert!(s[5]);
+    /// assert!(!s[2] && !s[100]);
+    /// ```
+    #[inline]
+    fn index(&self, value: usize) -> &bool {
+        if self.contains(value) { &TRUE } else { &FALSE }
+    }
+}
+
 impl<B: BitBlock> PartialOrd for BitSet<B> {
     #[inline]
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        self.iter().partial_cmp(other)
+        Some(self.cmp(other))
     }
 }
 
@@ -181,6 +533,9 @@ impl BitSet<u32> {
     /// Creates a new `BitSet` with initially no contents, able to
     /// hold `nbits` elements without resizing.
     ///
+    /// Only the storage is allocated: the set is empty and its universe
+    /// length is zero until elements are inserted.
+    ///
     /// # Examples
     ///
     /// ```
@@ -188,10 +543,12 @@ impl BitSet<u32> {
     ///
     /// let mut s = BitSet::with_capacity(100);
     /// assert!(s.capacity() >= 100);
+    /// assert_eq!(s.memory_usage().universe_len, 0);
+    /// assert_eq!(s, BitSet::new());
     /// ```
     #[inline]
     pub fn with_capacity(nbits: usize) -> Self {
-        let bit_vec = BitVec::from_elem(nbits, false);
+        let bit_vec = BitVec::with_capacity(nbits);
         Self::from_bit_vec(bit_vec)
     }
 
@@ -218,9 +575,24 @@ impl BitSet<u32> {
     /// ```
     #[inline]
     pub fn from_bit_vec(bit_vec: BitVec) -> Self {
-        BitSet { bit_vec: bit_vec }
+        BitSet { bit_vec }
     }
 
+    /// Creates a new `BitSet` from a byte buffer.
+    ///
+    /// The bytes are read in order, most significant bit first: the element
+    /// `8 * i + j` is in the set if bit `7 - j` of `bytes[i]` is set. This is
+    /// the same bit order as `BitVec::from_bytes` and
+    /// [to_bytes](#method.to_bytes).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s = BitSet::from_bytes(&[0b10000001, 0b01000000]);
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), [0, 7, 9]);
+    /// ```
     pub fn from_bytes(bytes: &[u8]) -> Self {
         BitSet { bit_vec: BitVec::from_bytes(bytes) }
     }
@@ -228,6 +600,120 @@ impl BitSet<u32> {
 
 impl<B: BitBlock> BitSet<B> {
 
+    /// Creates a new `BitSet` from raw blocks of data.
+    ///
+    /// Bit `i` of block `n` becomes the element `n * B::bits() + i`. This is
+    /// the inverse of [into_raw_parts](#method.into_raw_parts).
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s = BitSet::from_blocks(vec![0b101u32, 0b1]);
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), [0, 2, 32]);
+    /// ```
+    pub fn from_blocks(blocks: Vec<B>) -> Self {
+        let nbits = blocks.len().checked_mul(B::bits()).expect("capacity overflow");
+        let mut bit_vec = BitVec::default();
+        unsafe {
+            *bit_vec.storage_mut() = blocks;
+            bit_vec.set_len(nbits);
+        }
+        BitSet { bit_vec }
+    }
+
+    /// Creates a new `BitSet` holding every value in `0..nbits`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = BitSet::full(5);
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), [0, 1, 2, 3, 4]);
+    /// ```
+    pub fn full(nbits: usize) -> Self {
+        let mut bit_vec = BitVec::default();
+        bit_vec.grow(nbits, true);
+        BitSet { bit_vec }
+    }
+
+    /// Creates a new `BitSet` holding every value in `range`.
+    ///
+    /// The storage is allocated once and filled a whole block at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = BitSet::from_range(3..7);
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), [3, 4, 5, 6]);
+    /// ```
+    pub fn from_range(range: Range<usize>) -> Self {
+        let mut set = Self::default();
+        set.fill_range(range);
+        set
+    }
+
+    /// Creates a new `BitSet` holding every value in each of `ranges`.
+    ///
+    /// The ranges may overlap and come in any order. Each of them is filled a
+    /// whole block at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = BitSet::from_ranges(vec![0..2, 40..42, 1..3]);
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), [0, 1, 2, 40, 41]);
+    /// ```
+    pub fn from_ranges<I: IntoIterator<Item = Range<usize>>>(ranges: I) -> Self {
+        let mut set = Self::default();
+        for range in ranges {
+            set.fill_range(range);
+        }
+        set
+    }
+
+    /// Creates a new `BitSet` from values in ascending order, setting the
+    /// bits of the values falling in the same block at once.
+    ///
+    /// Values out of order are still inserted, only with less grouping.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = BitSet::from_sorted_iter((0..1000).filter(|x| x % 3 == 0));
+    /// assert_eq!(s.len(), 334);
+    /// assert!(s.contains(999));
+    /// ```
+    pub fn from_sorted_iter<I: IntoIterator<Item = usize>>(values: I) -> Self {
+        let mut set = Self::default();
+        set.insert_grouped(values);
+        set
+    }
+
+    /// Creates a new `BitSet` holding the values below `nbits` for which `f`
+    /// returns `true`, built a whole block at a time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let odd: BitSet = BitSet::from_fn(10, |i| i % 2 == 1);
+    /// assert_eq!(odd.iter().collect::<Vec<_>>(), [1, 3, 5, 7, 9]);
+    /// assert_eq!(odd.get_ref().len(), 10);
+    /// ```
+    pub fn from_fn<F: FnMut(usize) -> bool>(nbits: usize, f: F) -> Self {
+        (0..nbits).map(f).collect()
+    }
+
     /// Returns the capacity in bits for this bit vector. Inserting any
     /// element less than this amount will not trigger a resizing.
     ///
@@ -292,6 +778,61 @@ impl<B: BitBlock> BitSet<B> {
         }
     }
 
+    /// Reserves capacity like [`reserve_len`](#method.reserve_len), but
+    /// returns an error instead of aborting if the allocation fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s = BitSet::new();
+    /// assert!(s.try_reserve_len(10).is_ok());
+    /// assert!(s.capacity() >= 10);
+    ///
+    /// assert!(s.try_reserve_len(usize::MAX).is_err());
+    /// ```
+    pub fn try_reserve_len(&mut self, len: usize) -> Result<(), TryReserveError> {
+        let storage = unsafe { self.bit_vec.storage_mut() };
+        let blocks = blocks_for_bits::<B>(len);
+        if blocks > storage.len() {
+            storage.try_reserve(blocks - storage.len())?;
+        }
+        Ok(())
+    }
+
+    /// Reserves capacity like [`reserve_len_exact`](#method.reserve_len_exact),
+    /// but returns an error instead of aborting if the allocation fails.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s = BitSet::new();
+    /// assert!(s.try_reserve_len_exact(10).is_ok());
+    /// assert!(s.capacity() >= 10);
+    /// ```
+    pub fn try_reserve_len_exact(&mut self, len: usize) -> Result<(), TryReserveError> {
+        let storage = unsafe { self.bit_vec.storage_mut() };
+        let blocks = blocks_for_bits::<B>(len);
+        if blocks > storage.len() {
+            storage.try_reserve_exact(blocks - storage.len())?;
+        }
+        Ok(())
+    }
+
+    /// Grows the bit vector to `len` bits, reserving the blocks fallibly so
+    /// growing can't reallocate.
+    fn try_grow(&mut self, len: usize) -> Result<(), TryReserveError> {
+        let cur_len = self.bit_vec.len();
+        if len > cur_len {
+            self.try_reserve_len(len)?;
+            self.bit_vec.grow(len - cur_len, false);
+        }
+        Ok(())
+    }
+
     /// Consumes this set to return the underlying bit vector.
     ///
     /// # Examples
@@ -312,6 +853,58 @@ impl<B: BitBlock> BitSet<B> {
         self.bit_vec
     }
 
+    /// Returns a copy of the set stored in blocks of another type.
+    ///
+    /// The storage is rebuilt from the little-endian bytes of the blocks,
+    /// without visiting the elements, and keeps the same length in bits.
+    /// When both block types are primitive integers, the bytes are copied
+    /// all at once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let narrow: BitSet<u32> = [1, 40, 100].iter().cloned().collect();
+    /// let wide: BitSet<u64> = narrow.convert();
+    /// assert_eq!(wide.as_block_slice(), [1 << 1 | 1 << 40, 1 << 36]);
+    /// assert!(wide.eq_elements(&narrow));
+    /// ```
+    pub fn convert<C: BitBlock>(&self) -> BitSet<C> {
+        let storage = self.bit_vec.storage();
+        let blocks = primitive::repack(storage).unwrap_or_else(|| {
+            let bytes = storage.iter()
+                .flat_map(|&block| (0..B::bytes()).map(move |k| block_byte(block, k)));
+            blocks_from_le_bytes(bytes)
+        });
+        let mut set = BitSet::from_blocks(blocks);
+        set.bit_vec.truncate(self.bit_vec.len());
+        set
+    }
+
+    /// Returns the memory used by this set: the bytes allocated on the heap
+    /// for its blocks, including spare capacity, and the length of the
+    /// universe it currently covers.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s: BitSet<u64> = BitSet::default();
+    /// s.insert(100);
+    ///
+    /// let usage = s.memory_usage();
+    /// assert_eq!(usage.universe_len, 101);
+    /// assert!(usage.heap_bytes >= 2 * 8);
+    /// ```
+    pub fn memory_usage(&self) -> MemoryUsage {
+        MemoryUsage {
+            heap_bytes: self.bit_vec.capacity() / B::bits() * mem::size_of::<B>(),
+            universe_len: self.bit_vec.len(),
+        }
+    }
+
     /// Returns a reference to the underlying bit vector.
     ///
     /// # Examples
@@ -330,37 +923,11 @@ impl<B: BitBlock> BitSet<B> {
         &self.bit_vec
     }
 
-    #[inline]
-    fn other_op<F>(&mut self, other: &Self, mut f: F) where F: FnMut(B, B) -> B {
-        // Unwrap BitVecs
-        let self_bit_vec = &mut self.bit_vec;
-        let other_bit_vec = &other.bit_vec;
-
-        let self_len = self_bit_vec.len();
-        let other_len = other_bit_vec.len();
-
-        // Expand the vector if necessary
-        if self_len < other_len {
-            self_bit_vec.grow(other_len - self_len, false);
-        }
-
-        // virtually pad other with 0's for equal lengths
-        let other_words = {
-            let (_, result) = match_words(self_bit_vec, other_bit_vec);
-            result
-        };
-
-        // Apply values found in other
-        for (i, w) in other_words {
-            let old = self_bit_vec.storage()[i];
-            let new = f(old, w);
-            unsafe {
-                self_bit_vec.storage_mut()[i] = new;
-            }
-        }
-    }
-
-    /// Truncates the underlying vector to the least length required.
+    /// Returns a byte buffer holding the elements of this set.
+    ///
+    /// The element `8 * i + j` is in the set if bit `7 - j` of the `i`-th
+    /// byte is set, so `BitSet::from_bytes(&s.to_bytes()) == s`. The last
+    /// byte is padded with zero bits.
     ///
     /// # Examples
     ///
@@ -368,89 +935,424 @@ impl<B: BitBlock> BitSet<B> {
     /// use bit_set::BitSet;
     ///
     /// let mut s = BitSet::new();
-    /// s.insert(32183231);
-    /// s.remove(32183231);
+    /// s.insert(0);
+    /// s.insert(7);
+    /// s.insert(9);
+    /// assert_eq!(s.to_bytes(), [0b10000001, 0b01000000]);
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        self.bit_vec.to_bytes()
+    }
+
+    /// Returns the underlying blocks of data as a slice.
     ///
-    /// // Internal storage will probably be bigger than necessary
-    /// println!("old capacity: {}", s.capacity());
+    /// # Examples
     ///
-    /// // Now should be smaller
-    /// s.shrink_to_fit();
-    /// println!("new capacity: {}", s.capacity());
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s = BitSet::new();
+    /// s.insert(3);
+    /// assert_eq!(s.as_block_slice(), &[0b1000]);
     /// ```
     #[inline]
-    pub fn shrink_to_fit(&mut self) {
-        let bit_vec = &mut self.bit_vec;
-        // Obtain original length
-        let old_len = bit_vec.storage().len();
-        // Obtain coarse trailing zero length
-        let n = bit_vec.storage().iter().rev().take_while(|&&n| n == B::zero()).count();
-        // Truncate
-        let trunc_len = cmp::max(old_len - n, 1);
-        unsafe {
-            bit_vec.storage_mut().truncate(trunc_len);
-            bit_vec.set_len(trunc_len * B::bits());
-        }
+    pub fn as_block_slice(&self) -> &[B] {
+        self.bit_vec.storage()
     }
 
-    /// Iterator over each usize stored in the `BitSet`.
+    /// Consumes this set to return the underlying blocks of data.
+    ///
+    /// Bits past the largest element ever held are always zero, so the
+    /// blocks fully describe the set and can be turned back into an equal
+    /// one with [from_blocks](#method.from_blocks).
     ///
     /// # Examples
     ///
     /// ```
     /// use bit_set::BitSet;
     ///
-    /// let s = BitSet::from_bytes(&[0b01001010]);
+    /// let mut s = BitSet::new();
+    /// s.insert(1);
+    /// s.insert(32);
     ///
-    /// // Print 1, 4, 6 in arbitrary order
-    /// for x in s.iter() {
-    ///     println!("{}", x);
-    /// }
+    /// let blocks = s.clone().into_raw_parts();
+    /// assert_eq!(blocks, [0b10, 0b1]);
+    /// assert_eq!(BitSet::from_blocks(blocks), s);
     /// ```
     #[inline]
-    pub fn iter(&self) -> Iter<B> {
-        Iter(BlockIter::from_blocks(self.bit_vec.blocks()))
+    pub fn into_raw_parts(self) -> Vec<B> {
+        let mut bit_vec = self.bit_vec;
+        unsafe { core::mem::take(bit_vec.storage_mut()) }
     }
 
-    /// Iterator over each usize stored in `self` union `other`.
-    /// See [union_with](#method.union_with) for an efficient in-place version.
+    /// Iterator over the underlying blocks of data.
+    ///
+    /// Bit `i` of block `n` corresponds to the element `n * B::bits() + i`.
     ///
     /// # Examples
     ///
     /// ```
     /// use bit_set::BitSet;
     ///
-    /// let a = BitSet::from_bytes(&[0b01101000]);
-    /// let b = BitSet::from_bytes(&[0b10100000]);
+    /// let mut s = BitSet::new();
+    /// s.insert(1);
+    /// s.insert(33);
     ///
-    /// // Print 0, 1, 2, 4 in arbitrary order
-    /// for x in a.union(&b) {
-    ///     println!("{}", x);
-    /// }
+    /// let blocks: Vec<u32> = s.blocks().collect();
+    /// assert_eq!(blocks, [0b10, 0b10]);
     /// ```
     #[inline]
-    pub fn union<'a>(&'a self, other: &'a Self) -> Union<'a, B> {
-        fn or<B: BitBlock>(w1: B, w2: B) -> B { w1 | w2 }
-
-        Union(BlockIter::from_blocks(TwoBitPositions {
-            set: self.bit_vec.blocks(),
-            other: other.bit_vec.blocks(),
-            merge: or,
-        }))
+    pub fn blocks(&self) -> Blocks<'_, B> {
+        self.bit_vec.blocks()
     }
 
-    /// Iterator over each usize stored in `self` intersect `other`.
-    /// See [intersect_with](#method.intersect_with) for an efficient in-place version.
+    /// Returns the underlying blocks of data as a mutable slice.
+    ///
+    /// Every bit of every block is considered part of the set, so the
+    /// caller is free to write arbitrary words. The number of blocks can not
+    /// be changed through the returned slice; [insert](#method.insert) a
+    /// larger element first to grow it.
     ///
     /// # Examples
     ///
     /// ```
     /// use bit_set::BitSet;
     ///
-    /// let a = BitSet::from_bytes(&[0b01101000]);
-    /// let b = BitSet::from_bytes(&[0b10100000]);
+    /// let mut s = BitSet::new();
+    /// s.insert(40);
     ///
-    /// // Print 2
+    /// for block in s.blocks_mut() {
+    ///     *block |= 0b11;
+    /// }
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), [0, 1, 32, 33, 40]);
+    /// ```
+    #[inline]
+    pub fn blocks_mut(&mut self) -> &mut [B] {
+        let bit_vec = &mut self.bit_vec;
+        // Make the padding bits of the last block part of the set, so that
+        // whatever gets written there stays consistent with `contains`.
+        let nbits = bit_vec.storage().len() * B::bits();
+        unsafe {
+            bit_vec.set_len(nbits);
+            &mut bit_vec.storage_mut()[..]
+        }
+    }
+
+    /// Grows `self` to the length of `other`, then applies `op` to the
+    /// blocks of both. `op` must keep a block unchanged against zero.
+    fn grow_op(&mut self, other: &Self, op: kernels::Op) {
+        let other_len = other.bit_vec.len();
+        if self.bit_vec.len() < other_len {
+            self.bit_vec.grow(other_len - self.bit_vec.len(), false);
+        }
+        kernels::apply(unsafe { self.bit_vec.storage_mut() }, other.bit_vec.storage(), op);
+    }
+
+    /// Sets each block of `self` to `f` of it and the blocks of `b` and `c`
+    /// at the same index, growing `self` to the length of the longest set.
+    fn ternary_op<F>(&mut self, b: &Self, c: &Self, f: F) where F: Fn(B, B, B) -> B {
+        let len = cmp::max(b.bit_vec.len(), c.bit_vec.len());
+        if self.bit_vec.len() < len {
+            self.bit_vec.grow(len - self.bit_vec.len(), false);
+        }
+        let (b, c) = (b.bit_vec.storage(), c.bit_vec.storage());
+        let blocks = unsafe { self.bit_vec.storage_mut() };
+        for (i, block) in blocks.iter_mut().enumerate() {
+            let load = |blocks: &[B]| blocks.get(i).cloned().unwrap_or(B::zero());
+            *block = f(*block, load(b), load(c));
+        }
+    }
+
+    /// Calls `f` with the index of a block and the mask of the values of
+    /// `values` falling in it, once per run of consecutive values in the
+    /// same block. The bits of a run are merged with `combine`.
+    fn for_each_block_mask<I, F>(values: I, combine: fn(B, B) -> B, mut f: F)
+        where I: IntoIterator<Item = usize>, F: FnMut(usize, B)
+    {
+        let bits = B::bits();
+        let mut current: Option<(usize, B)> = None;
+        for value in values {
+            let (i, mask) = (value / bits, B::one() << (value % bits));
+            current = match current {
+                Some((j, block)) if j == i => Some((i, combine(block, mask))),
+                Some((j, block)) => {
+                    f(j, block);
+                    Some((i, mask))
+                }
+                None => Some((i, mask)),
+            };
+        }
+        if let Some((j, block)) = current {
+            f(j, block);
+        }
+    }
+
+    /// Inserts each value of `values`, setting the bits of a run of values
+    /// falling in the same block at once.
+    fn insert_grouped<I: IntoIterator<Item = usize>>(&mut self, values: I) {
+        let mut nbits = self.bit_vec.len();
+        let blocks = unsafe { self.bit_vec.storage_mut() };
+        let values = values.into_iter().inspect(|&value| nbits = cmp::max(nbits, value + 1));
+        Self::for_each_block_mask(values, |a, b| a | b, |i, mask| {
+            if i >= blocks.len() {
+                blocks.resize(i + 1, B::zero());
+            }
+            blocks[i] = blocks[i] | mask;
+        });
+        unsafe { self.bit_vec.set_len(nbits) };
+    }
+
+    /// Inserts every value in `range`, growing the storage at most once and
+    /// setting whole blocks at a time.
+    fn fill_range(&mut self, range: Range<usize>) {
+        if range.start >= range.end {
+            return;
+        }
+        let len = self.bit_vec.len();
+        if range.end > len {
+            self.bit_vec.grow(range.end - len, false);
+        }
+
+        let bits = B::bits();
+        let first = range.start / bits;
+        let last = (range.end - 1) / bits;
+        let low_mask = !B::zero() << (range.start % bits);
+        let high_mask = !B::zero() >> (bits - 1 - (range.end - 1) % bits);
+        let storage = unsafe { self.bit_vec.storage_mut() };
+        if first == last {
+            storage[first] = storage[first] | (low_mask & high_mask);
+        } else {
+            storage[first] = storage[first] | low_mask;
+            for block in &mut storage[first + 1..last] {
+                *block = !B::zero();
+            }
+            storage[last] = storage[last] | high_mask;
+        }
+    }
+
+    /// Truncates the underlying vector to the least length required.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s = BitSet::new();
+    /// s.insert(32183231);
+    /// s.remove(32183231);
+    ///
+    /// // Internal storage will probably be bigger than necessary
+    /// println!("old capacity: {}", s.capacity());
+    ///
+    /// // Now should be smaller
+    /// s.shrink_to_fit();
+    /// println!("new capacity: {}", s.capacity());
+    /// ```
+    #[inline]
+    pub fn shrink_to_fit(&mut self) {
+        let bit_vec = &mut self.bit_vec;
+        // Obtain original length
+        let old_len = bit_vec.storage().len();
+        // Obtain coarse trailing zero length
+        let n = bit_vec.storage().iter().rev().take_while(|&&n| n == B::zero()).count();
+        // Truncate, keeping a block unless there is none, as the length
+        // must match the storage
+        let trunc_len = cmp::max(old_len - n, cmp::min(old_len, 1));
+        unsafe {
+            bit_vec.storage_mut().truncate(trunc_len);
+            bit_vec.set_len(trunc_len * B::bits());
+        }
+    }
+
+    /// Removes every element greater than or equal to `limit`, dropping the
+    /// blocks past it and clearing the unused bits of the last one.
+    ///
+    /// The allocated capacity is kept; call
+    /// [`shrink_to_fit`](#method.shrink_to_fit) to release it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s: BitSet = [1, 5, 40, 100].iter().cloned().collect();
+    /// s.truncate(40);
+    ///
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), [1, 5]);
+    /// assert_eq!(s.memory_usage().universe_len, 40);
+    /// ```
+    #[inline]
+    pub fn truncate(&mut self, limit: usize) {
+        self.bit_vec.truncate(limit);
+    }
+
+    /// Iterator over each usize stored in the `BitSet`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s = BitSet::from_bytes(&[0b01001010]);
+    ///
+    /// // Print 1, 4, 6 in arbitrary order
+    /// for x in s.iter() {
+    ///     println!("{}", x);
+    /// }
+    /// ```
+    #[inline]
+    pub fn iter(&self) -> Iter<'_, B> {
+        Iter(BlockIter::from_blocks(self.bit_vec.blocks()))
+    }
+
+    /// Returns the elements of the set in ascending order, in a vector
+    /// allocated once with room for exactly [`len`](#method.len) of them.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = [7, 1, 3].iter().cloned().collect();
+    /// assert_eq!(s.to_vec(), [1, 3, 7]);
+    /// ```
+    pub fn to_vec(&self) -> Vec<usize> {
+        let mut values = Vec::new();
+        self.collect_into(&mut values);
+        values
+    }
+
+    /// Appends the elements of the set in ascending order to `values`,
+    /// reserving room for all of them at once and reusing its capacity.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut buffer = Vec::with_capacity(16);
+    /// let s: BitSet = [7, 1, 3].iter().cloned().collect();
+    /// buffer.push(0);
+    /// s.collect_into(&mut buffer);
+    /// assert_eq!(buffer, [0, 1, 3, 7]);
+    /// ```
+    pub fn collect_into(&self, values: &mut Vec<usize>) {
+        values.reserve_exact(self.len());
+        values.extend(self.iter());
+    }
+
+    /// Calls `f` on each element in ascending order, stopping at the first
+    /// error and returning it.
+    ///
+    /// The elements are visited a block at a time, like `for_each` on the
+    /// iterator; `Iterator::try_for_each` can't be specialized that way, as
+    /// `try_fold` can only be overridden on nightly.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = [2, 4, 7, 8].iter().cloned().collect();
+    /// let mut checked = 0;
+    /// let result = s.try_for_each(|x| {
+    ///     if x % 2 == 1 {
+    ///         return Err(x);
+    ///     }
+    ///     checked += 1;
+    ///     Ok(())
+    /// });
+    /// assert_eq!(result, Err(7));
+    /// assert_eq!(checked, 2);
+    /// ```
+    pub fn try_for_each<E, F>(&self, mut f: F) -> Result<(), E>
+        where F: FnMut(usize) -> Result<(), E>
+    {
+        for (i, &block) in self.bit_vec.storage().iter().enumerate() {
+            let mut block = block;
+            while block != B::zero() {
+                f(i * B::bits() + lowest_bit(block))?;
+                block = block & (block - B::one());
+            }
+        }
+        Ok(())
+    }
+
+    /// Iterator over the maximal runs of consecutive elements of the set, as
+    /// ranges in ascending order.
+    ///
+    /// Runs are found a block at a time, so long runs and long gaps are
+    /// skipped without visiting each element.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = [1, 2, 3, 7, 40, 41].iter().cloned().collect();
+    /// assert_eq!(s.runs().collect::<Vec<_>>(), [1..4, 7..8, 40..42]);
+    /// ```
+    #[inline]
+    pub fn runs(&self) -> Runs<'_, B> {
+        Runs { blocks: self.bit_vec.storage(), pos: 0 }
+    }
+
+    /// Iterator over the maximal runs of values below `universe` that are
+    /// not in the set, as ranges in ascending order.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = [1, 2, 3, 7].iter().cloned().collect();
+    /// assert_eq!(s.gaps(10).collect::<Vec<_>>(), [0..1, 4..7, 8..10]);
+    /// assert_eq!(s.gaps(6).collect::<Vec<_>>(), [0..1, 4..6]);
+    /// ```
+    #[inline]
+    pub fn gaps(&self, universe: usize) -> Gaps<'_, B> {
+        Gaps { blocks: self.bit_vec.storage(), pos: 0, end: universe }
+    }
+
+    /// Iterator over each usize stored in `self` union `other`.
+    /// See [union_with](#method.union_with) for an efficient in-place version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let a = BitSet::from_bytes(&[0b01101000]);
+    /// let b = BitSet::from_bytes(&[0b10100000]);
+    ///
+    /// // Print 0, 1, 2, 4 in arbitrary order
+    /// for x in a.union(&b) {
+    ///     println!("{}", x);
+    /// }
+    /// ```
+    #[inline]
+    pub fn union<'a>(&'a self, other: &'a Self) -> Union<'a, B> {
+        fn or<B: BitBlock>(w1: B, w2: B) -> B { w1 | w2 }
+
+        Union(BlockIter::from_blocks(TwoBitPositions {
+            set: self.bit_vec.blocks(),
+            other: other.bit_vec.blocks(),
+            merge: or,
+        }))
+    }
+
+    /// Iterator over each usize stored in `self` intersect `other`.
+    /// See [intersect_with](#method.intersect_with) for an efficient in-place version.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let a = BitSet::from_bytes(&[0b01101000]);
+    /// let b = BitSet::from_bytes(&[0b10100000]);
+    ///
+    /// // Print 2
     /// for x in a.intersection(&b) {
     ///     println!("{}", x);
     /// }
@@ -549,7 +1451,7 @@ impl<B: BitBlock> BitSet<B> {
     /// ```
     #[inline]
     pub fn union_with(&mut self, other: &Self) {
-        self.other_op(other, |w1, w2| w1 | w2);
+        self.grow_op(other, kernels::Op::Or);
     }
 
     /// Intersects in-place with the specified other bit vector.
@@ -570,9 +1472,26 @@ impl<B: BitBlock> BitSet<B> {
     /// a.intersect_with(&b);
     /// assert_eq!(a, res);
     /// ```
+    ///
+    /// The result can't hold values past the length of `other`, so the set
+    /// is truncated to it, dropping the blocks beyond:
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut a: BitSet = BitSet::from_range(0..10_000);
+    /// let b: BitSet = BitSet::from_range(5..10);
+    /// a.intersect_with(&b);
+    /// assert_eq!(a.memory_usage().universe_len, 10);
+    /// ```
     #[inline]
     pub fn intersect_with(&mut self, other: &Self) {
-        self.other_op(other, |w1, w2| w1 & w2);
+        let other_len = other.bit_vec.len();
+        if other_len < self.bit_vec.len() {
+            self.bit_vec.truncate(other_len);
+        }
+        // `self` is now no longer than `other`, so it never grows
+        kernels::intersect(unsafe { self.bit_vec.storage_mut() }, other.bit_vec.storage());
     }
 
     /// Makes this bit vector the difference with the specified other bit vector
@@ -604,7 +1523,9 @@ impl<B: BitBlock> BitSet<B> {
     /// ```
     #[inline]
     pub fn difference_with(&mut self, other: &Self) {
-        self.other_op(other, |w1, w2| w1 & !w2);
+        // The result holds no value past the end of `self`, so it never grows
+        let blocks = unsafe { self.bit_vec.storage_mut() };
+        kernels::apply(blocks, other.bit_vec.storage(), kernels::Op::AndNot);
     }
 
     /// Makes this bit vector the symmetric difference with the specified other
@@ -628,64 +1549,393 @@ impl<B: BitBlock> BitSet<B> {
     /// ```
     #[inline]
     pub fn symmetric_difference_with(&mut self, other: &Self) {
-        self.other_op(other, |w1, w2| w1 ^ w2);
+        self.grow_op(other, kernels::Op::Xor);
     }
 
-/*
-    /// Moves all elements from `other` into `Self`, leaving `other` empty.
+    /// Writes `f` of the blocks of `self` and `other` into `out`, reusing
+    /// its allocation, and gives it a length of `nbits`.
+    fn binary_into<F>(&self, other: &Self, out: &mut Self, nbits: usize, f: F)
+        where F: Fn(B, B) -> B
+    {
+        let (a, b) = (self.bit_vec.storage(), other.bit_vec.storage());
+        let load = |blocks: &[B], i: usize| blocks.get(i).cloned().unwrap_or(B::zero());
+        unsafe {
+            let blocks = out.bit_vec.storage_mut();
+            blocks.clear();
+            blocks.extend((0..blocks_for_bits::<B>(nbits)).map(|i| f(load(a, i), load(b, i))));
+            out.bit_vec.set_len(nbits);
+        }
+    }
+
+    /// Writes the union of `self` and `other` into `out`, replacing its
+    /// contents but reusing its allocation.
     ///
     /// # Examples
     ///
     /// ```
     /// use bit_set::BitSet;
     ///
-    /// let mut a = BitSet::new();
-    /// a.insert(2);
-    /// a.insert(6);
+    /// let a: BitSet = [1, 2].iter().cloned().collect();
+    /// let b: BitSet = [2, 3].iter().cloned().collect();
+    /// let mut out = BitSet::with_capacity(64);
+    ///
+    /// a.union_into(&b, &mut out);
+    /// assert_eq!(out.iter().collect::<Vec<_>>(), [1, 2, 3]);
+    /// a.intersection_into(&b, &mut out);
+    /// assert_eq!(out.iter().collect::<Vec<_>>(), [2]);
+    /// a.difference_into(&b, &mut out);
+    /// assert_eq!(out.iter().collect::<Vec<_>>(), [1]);
+    /// a.symmetric_difference_into(&b, &mut out);
+    /// assert_eq!(out.iter().collect::<Vec<_>>(), [1, 3]);
+    /// ```
+    pub fn union_into(&self, other: &Self, out: &mut Self) {
+        let nbits = cmp::max(self.bit_vec.len(), other.bit_vec.len());
+        self.binary_into(other, out, nbits, |a, b| a | b);
+    }
+
+    /// Writes the intersection of `self` and `other` into `out`, replacing
+    /// its contents but reusing its allocation.
+    pub fn intersection_into(&self, other: &Self, out: &mut Self) {
+        let nbits = cmp::min(self.bit_vec.len(), other.bit_vec.len());
+        self.binary_into(other, out, nbits, |a, b| a & b);
+    }
+
+    /// Writes the values of `self` that aren't in `other` into `out`,
+    /// replacing its contents but reusing its allocation.
+    pub fn difference_into(&self, other: &Self, out: &mut Self) {
+        let nbits = self.bit_vec.len();
+        self.binary_into(other, out, nbits, |a, b| a & !b);
+    }
+
+    /// Writes the symmetric difference of `self` and `other` into `out`,
+    /// replacing its contents but reusing its allocation.
+    pub fn symmetric_difference_into(&self, other: &Self, out: &mut Self) {
+        let nbits = cmp::max(self.bit_vec.len(), other.bit_vec.len());
+        self.binary_into(other, out, nbits, |a, b| a ^ b);
+    }
+
+    /// Computes the values added and removed going from `old` to `new`, in
+    /// a single pass over the blocks of both. Returns `(added, removed)`,
+    /// that is `new - old` and `old - new`.
     ///
-    /// let mut b = BitSet::new();
-    /// b.insert(1);
-    /// b.insert(3);
-    /// b.insert(6);
+    /// # Examples
     ///
-    /// a.append(&mut b);
+    /// ```
+    /// use bit_set::BitSet;
     ///
-    /// assert_eq!(a.len(), 4);
-    /// assert_eq!(b.len(), 0);
-    /// assert_eq!(a, BitSet::from_bytes(&[0b01110010]));
+    /// let old: BitSet = [1, 2, 3].iter().cloned().collect();
+    /// let new: BitSet = [2, 3, 40].iter().cloned().collect();
+    ///
+    /// let (added, removed) = BitSet::diff(&old, &new);
+    /// assert_eq!(added.iter().collect::<Vec<_>>(), [40]);
+    /// assert_eq!(removed.iter().collect::<Vec<_>>(), [1]);
     /// ```
-    pub fn append(&mut self, other: &mut Self) {
-        self.union_with(other);
-        other.clear();
+    pub fn diff(old: &Self, new: &Self) -> (Self, Self) {
+        let (old_blocks, new_blocks) = (old.bit_vec.storage(), new.bit_vec.storage());
+        let load = |blocks: &[B], i: usize| blocks.get(i).cloned().unwrap_or(B::zero());
+        let mut added = Vec::with_capacity(new_blocks.len());
+        let mut removed = Vec::with_capacity(old_blocks.len());
+        for i in 0..cmp::max(old_blocks.len(), new_blocks.len()) {
+            let (o, n) = (load(old_blocks, i), load(new_blocks, i));
+            if i < new_blocks.len() {
+                added.push(n & !o);
+            }
+            if i < old_blocks.len() {
+                removed.push(o & !n);
+            }
+        }
+        let (mut added, mut removed) = (BitSet::from_blocks(added), BitSet::from_blocks(removed));
+        unsafe {
+            added.bit_vec.set_len(new.bit_vec.len());
+            removed.bit_vec.set_len(old.bit_vec.len());
+        }
+        (added, removed)
     }
 
-    /// Splits the `BitSet` into two at the given key including the key.
-    /// Retains the first part in-place while returning the second part.
+    /// Unions in-place with the intersection of `b` and `c`, that is
+    /// `self |= b & c`, in a single pass without a temporary set.
     ///
     /// # Examples
     ///
     /// ```
     /// use bit_set::BitSet;
     ///
-    /// let mut a = BitSet::new();
-    /// a.insert(2);
-    /// a.insert(6);
-    /// a.insert(1);
-    /// a.insert(3);
+    /// let mut a: BitSet = [1].iter().cloned().collect();
+    /// let b: BitSet = [2, 3].iter().cloned().collect();
+    /// let c: BitSet = [3, 4].iter().cloned().collect();
     ///
-    /// let b = a.split_off(3);
+    /// a.union_with_intersection(&b, &c);
+    /// assert_eq!(a.iter().collect::<Vec<_>>(), [1, 3]);
+    /// ```
+    pub fn union_with_intersection(&mut self, b: &Self, c: &Self) {
+        self.ternary_op(b, c, |a, b, c| a | (b & c));
+    }
+
+    /// Intersects in-place with the union of `b` and `c`, that is
+    /// `self &= b | c`, in a single pass without a temporary set.
+    ///
+    /// # Examples
     ///
-    /// assert_eq!(a.len(), 2);
-    /// assert_eq!(b.len(), 2);
-    /// assert_eq!(a, BitSet::from_bytes(&[0b01100000]));
-    /// assert_eq!(b, BitSet::from_bytes(&[0b00010010]));
     /// ```
-    pub fn split_off(&mut self, at: usize) -> Self {
-        let mut other = BitSet::new();
+    /// use bit_set::BitSet;
+    ///
+    /// let mut a: BitSet = [1, 2, 3].iter().cloned().collect();
+    /// let b: BitSet = [2].iter().cloned().collect();
+    /// let c: BitSet = [3, 4].iter().cloned().collect();
+    ///
+    /// a.intersect_with_union(&b, &c);
+    /// assert_eq!(a.iter().collect::<Vec<_>>(), [2, 3]);
+    /// ```
+    pub fn intersect_with_union(&mut self, b: &Self, c: &Self) {
+        self.ternary_op(b, c, |a, b, c| a & (b | c));
+    }
 
-        if at == 0 {
-            swap(self, &mut other);
-            return other;
+    /// Removes in-place the values of `b` and of `c`, that is
+    /// `self -= b | c`, in a single pass without a temporary set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut a: BitSet = [1, 2, 3].iter().cloned().collect();
+    /// let b: BitSet = [2].iter().cloned().collect();
+    /// let c: BitSet = [3, 4].iter().cloned().collect();
+    ///
+    /// a.difference_with_union(&b, &c);
+    /// assert_eq!(a.iter().collect::<Vec<_>>(), [1]);
+    /// ```
+    pub fn difference_with_union(&mut self, b: &Self, c: &Self) {
+        self.ternary_op(b, c, |a, b, c| a & !(b | c));
+    }
+
+    /// Unions in-place with the values of `b` that aren't in `c`, that is
+    /// `self |= b - c`, in a single pass without a temporary set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// // live_in = uses | (live_out - defs)
+    /// let mut live_in: BitSet = [1].iter().cloned().collect();
+    /// let live_out: BitSet = [2, 3].iter().cloned().collect();
+    /// let defs: BitSet = [3].iter().cloned().collect();
+    ///
+    /// live_in.union_with_difference(&live_out, &defs);
+    /// assert_eq!(live_in.iter().collect::<Vec<_>>(), [1, 2]);
+    /// ```
+    pub fn union_with_difference(&mut self, b: &Self, c: &Self) {
+        self.ternary_op(b, c, |a, b, c| a | (b & !c));
+    }
+
+    /// Returns the union of all the given sets.
+    ///
+    /// The result is allocated once, at the length of the longest operand,
+    /// and the blocks of each operand are ORed into it, unlike repeated calls
+    /// to `union_with` which may grow it several times.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let sets: Vec<BitSet> = (0..4).map(|i| BitSet::from_range(i * 10..i * 10 + 2)).collect();
+    /// let all = BitSet::union_all(&sets);
+    /// assert_eq!(all.iter().collect::<Vec<_>>(), [0, 1, 10, 11, 20, 21, 30, 31]);
+    /// ```
+    pub fn union_all<'a, I>(sets: I) -> Self
+        where I: IntoIterator<Item = &'a Self>, B: 'a
+    {
+        let sets: Vec<&Self> = sets.into_iter().collect();
+        let nbits = sets.iter().map(|set| set.bit_vec.len()).max().unwrap_or(0);
+        let mut blocks = alloc::vec![B::zero(); blocks_for_bits::<B>(nbits)];
+        for set in &sets {
+            kernels::apply(&mut blocks, set.bit_vec.storage(), kernels::Op::Or);
+        }
+        let mut set = BitSet::from_blocks(blocks);
+        set.bit_vec.truncate(nbits);
+        set
+    }
+
+    /// Returns the intersection of all the given sets, or an empty set if
+    /// there are none.
+    ///
+    /// Like [`union_all`](#method.union_all), the result is allocated once.
+    /// It can't be longer than the shortest operand, so only that many
+    /// blocks of each operand are visited.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let sets: Vec<BitSet> = (0..4).map(|i| BitSet::from_range(i..10)).collect();
+    /// let common = BitSet::intersect_all(&sets);
+    /// assert_eq!(common.iter().collect::<Vec<_>>(), (3..10).collect::<Vec<_>>());
+    /// ```
+    pub fn intersect_all<'a, I>(sets: I) -> Self
+        where I: IntoIterator<Item = &'a Self>, B: 'a
+    {
+        let sets: Vec<&Self> = sets.into_iter().collect();
+        let nbits = sets.iter().map(|set| set.bit_vec.len()).min().unwrap_or(0);
+        let mut blocks = match sets.first() {
+            Some(first) => first.bit_vec.storage()[..blocks_for_bits::<B>(nbits)].to_vec(),
+            None => Vec::new(),
+        };
+        for set in sets.iter().skip(1) {
+            kernels::apply(&mut blocks, set.bit_vec.storage(), kernels::Op::And);
+        }
+        let mut set = BitSet::from_blocks(blocks);
+        set.bit_vec.truncate(nbits);
+        set
+    }
+
+    /// Returns the set of values present in at least `k` of the given sets.
+    ///
+    /// The count of each value is kept in bit-sliced counters, so each block
+    /// of an operand is added to the counts of all its bits at once in
+    /// `O(log n)` operations for `n` sets. The counts are computed a strip of
+    /// blocks at a time, from only the operands long enough to reach it, so
+    /// each operand is visited over its own blocks, and the strips reached by
+    /// fewer than `k` operands are skipped. With `k == 1` this is the union,
+    /// and with `k` equal to the number of sets, the intersection.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `k` is zero.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let replicas: Vec<BitSet> = vec![
+    ///     [1, 2, 3].iter().cloned().collect(),
+    ///     [2, 3, 4].iter().cloned().collect(),
+    ///     [3, 4, 5].iter().cloned().collect(),
+    /// ];
+    /// let majority = BitSet::threshold(&replicas, 2);
+    /// assert_eq!(majority.iter().collect::<Vec<_>>(), [2, 3, 4]);
+    /// ```
+    pub fn threshold<'a, I>(sets: I, k: usize) -> Self
+        where I: IntoIterator<Item = &'a Self>, B: 'a
+    {
+        assert!(k > 0, "the threshold must be positive");
+        let sets: Vec<&Self> = sets.into_iter().collect();
+        if k > sets.len() {
+            return BitSet::default();
+        }
+        let nbits = sets.iter().map(|set| set.bit_vec.len()).max().unwrap_or(0);
+        let mut sets: Vec<&[B]> = sets.iter().map(|set| set.bit_vec.storage()).collect();
+        sets.sort_by_key(|blocks| cmp::Reverse(blocks.len()));
+        let width = (usize::BITS - sets.len().leading_zeros()) as usize;
+        let mut counter = alloc::vec![B::zero(); width * THRESHOLD_STRIP];
+        let mut blocks = Vec::with_capacity(blocks_for_bits::<B>(nbits));
+        let mut start = 0;
+        loop {
+            // The sets are sorted by length, so those reaching the strip
+            // are a prefix, and the values past it are in fewer than `k`
+            let reaching = sets.iter().take_while(|blocks| blocks.len() > start).count();
+            if reaching < k {
+                break;
+            }
+            let end = cmp::min(start + THRESHOLD_STRIP, sets[0].len());
+            let counter = &mut counter[..width * (end - start)];
+            for slice in counter.iter_mut() {
+                *slice = B::zero();
+            }
+            for set in &sets[..reaching] {
+                let strip = &set[start..cmp::min(end, set.len())];
+                for (&block, count) in strip.iter().zip(counter.chunks_mut(width)) {
+                    // Ripple-carry add the block to its counter
+                    let mut carry = block;
+                    for slice in count.iter_mut() {
+                        if carry == B::zero() {
+                            break;
+                        }
+                        let sum = *slice ^ carry;
+                        carry = *slice & carry;
+                        *slice = sum;
+                    }
+                }
+            }
+            for count in counter.chunks(width) {
+                // Compare each count with `k`, from the most significant bit
+                let (mut greater, mut equal) = (B::zero(), !B::zero());
+                for (bit, &slice) in count.iter().enumerate().rev() {
+                    if k >> bit & 1 == 1 {
+                        equal = equal & slice;
+                    } else {
+                        greater = greater | (equal & slice);
+                        equal = equal & !slice;
+                    }
+                }
+                blocks.push(greater | equal);
+            }
+            start = end;
+        }
+        blocks.resize(blocks_for_bits::<B>(nbits), B::zero());
+        let mut set = BitSet::from_blocks(blocks);
+        set.bit_vec.truncate(nbits);
+        set
+    }
+
+/*
+    /// Moves all elements from `other` into `Self`, leaving `other` empty.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut a = BitSet::new();
+    /// a.insert(2);
+    /// a.insert(6);
+    ///
+    /// let mut b = BitSet::new();
+    /// b.insert(1);
+    /// b.insert(3);
+    /// b.insert(6);
+    ///
+    /// a.append(&mut b);
+    ///
+    /// assert_eq!(a.len(), 4);
+    /// assert_eq!(b.len(), 0);
+    /// assert_eq!(a, BitSet::from_bytes(&[0b01110010]));
+    /// ```
+    pub fn append(&mut self, other: &mut Self) {
+        self.union_with(other);
+        other.clear();
+    }
+
+    /// Splits the `BitSet` into two at the given key including the key.
+    /// Retains the first part in-place while returning the second part.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut a = BitSet::new();
+    /// a.insert(2);
+    /// a.insert(6);
+    /// a.insert(1);
+    /// a.insert(3);
+    ///
+    /// let b = a.split_off(3);
+    ///
+    /// assert_eq!(a.len(), 2);
+    /// assert_eq!(b.len(), 2);
+    /// assert_eq!(a, BitSet::from_bytes(&[0b01100000]));
+    /// assert_eq!(b, BitSet::from_bytes(&[0b00010010]));
+    /// ```
+    pub fn split_off(&mut self, at: usize) -> Self {
+        let mut other = BitSet::new();
+
+        if at == 0 {
+            swap(self, &mut other);
+            return other;
         } else if at >= self.bit_vec.len() {
             return other;
         }
@@ -714,13 +1964,52 @@ impl<B: BitBlock> BitSet<B> {
     /// Returns the number of set bits in this set.
     #[inline]
     pub fn len(&self) -> usize  {
-        self.bit_vec.blocks().fold(0, |acc, n| acc + n.count_ones() as usize)
+        kernels::count_ones(self.bit_vec.storage())
     }
 
     /// Returns whether there are no bits set in this set
     #[inline]
     pub fn is_empty(&self) -> bool {
-        self.bit_vec.none()
+        kernels::is_empty(self.bit_vec.storage())
+    }
+
+    /// Returns the number of values of the set below `universe`, counting
+    /// whole blocks at a time.
+    fn count_below(&self, universe: usize) -> usize {
+        let blocks = self.bit_vec.storage();
+        let (q, r) = (universe / B::bits(), universe % B::bits());
+        let full: usize = blocks.iter().take(q).map(|block| block.count_ones()).sum();
+        match blocks.get(q) {
+            Some(&block) if r > 0 => full + (block & !(!B::zero() << r)).count_ones(),
+            _ => full,
+        }
+    }
+
+    /// Returns the number of values in `0..universe` that are absent from
+    /// the set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = [1, 2, 50].iter().cloned().collect();
+    /// assert_eq!(s.count_zeros(10), 8);
+    /// assert_eq!(s.density(10), 0.2);
+    /// ```
+    #[inline]
+    pub fn count_zeros(&self, universe: usize) -> usize {
+        universe - self.count_below(universe)
+    }
+
+    /// Returns the fraction of the values in `0..universe` that are in the
+    /// set, or 0 if `universe` is 0.
+    #[inline]
+    pub fn density(&self, universe: usize) -> f64 {
+        if universe == 0 {
+            return 0.0;
+        }
+        self.count_below(universe) as f64 / universe as f64
     }
 
     /// Clears all bits in this set
@@ -732,28 +2021,80 @@ impl<B: BitBlock> BitSet<B> {
     /// Returns `true` if this set contains the specified integer.
     #[inline]
     pub fn contains(&self, value: usize) -> bool {
-        let bit_vec = &self.bit_vec;
-        value < bit_vec.len() && bit_vec[value]
+        block_contains(self.bit_vec.storage(), value)
+    }
+
+    /// Returns whether the set contains `value`, or `None` if `value` is
+    /// past the length of the underlying bit vector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = [1, 5].iter().cloned().collect();
+    /// assert_eq!(s.get(5), Some(true));
+    /// assert_eq!(s.get(2), Some(false));
+    /// assert_eq!(s.get(6), None);
+    /// ```
+    #[inline]
+    pub fn get(&self, value: usize) -> Option<bool> {
+        self.bit_vec.get(value)
+    }
+
+    /// Returns `true` if the set contains every value of `values`, stopping
+    /// at the first missing one. Accepts values or references to them, such
+    /// as a slice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let capabilities: BitSet = [1, 2, 5, 8].iter().cloned().collect();
+    /// assert!(capabilities.contains_all(&[2, 8]));
+    /// assert!(!capabilities.contains_all(2..6));
+    /// assert!(capabilities.contains_all(&[]));
+    /// ```
+    pub fn contains_all<I>(&self, values: I) -> bool
+        where I: IntoIterator, I::Item: Borrow<usize>
+    {
+        let blocks = self.bit_vec.storage();
+        values.into_iter().all(|value| block_contains(blocks, *value.borrow()))
+    }
+
+    /// Returns `true` if the set contains at least one value of `values`,
+    /// stopping at the first one found. Accepts values or references to
+    /// them, such as a slice.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = [1, 2, 5, 8].iter().cloned().collect();
+    /// assert!(s.contains_any(&[3, 5]));
+    /// assert!(!s.contains_any(vec![0, 100]));
+    /// assert!(!s.contains_any(&[]));
+    /// ```
+    pub fn contains_any<I>(&self, values: I) -> bool
+        where I: IntoIterator, I::Item: Borrow<usize>
+    {
+        let blocks = self.bit_vec.storage();
+        values.into_iter().any(|value| block_contains(blocks, *value.borrow()))
     }
 
     /// Returns `true` if the set has no elements in common with `other`.
     /// This is equivalent to checking for an empty intersection.
     #[inline]
     pub fn is_disjoint(&self, other: &Self) -> bool {
-        self.intersection(other).next().is_none()
+        kernels::is_disjoint(self.bit_vec.storage(), other.bit_vec.storage())
     }
 
     /// Returns `true` if the set is a subset of another.
     #[inline]
     pub fn is_subset(&self, other: &Self) -> bool {
-        let self_bit_vec = &self.bit_vec;
-        let other_bit_vec = &other.bit_vec;
-        let other_blocks = blocks_for_bits::<B>(other_bit_vec.len());
-
-        // Check that `self` intersect `other` is self
-        self_bit_vec.blocks().zip(other_bit_vec.blocks()).all(|(w1, w2)| w1 & w2 == w1) &&
-        // Make sure if `self` has any more blocks than `other`, they're all 0
-        self_bit_vec.blocks().skip(other_blocks).all(|w| w == B::zero())
+        kernels::is_subset(self.bit_vec.storage(), other.bit_vec.storage())
     }
 
     /// Returns `true` if the set is a superset of another.
@@ -762,6 +2103,133 @@ impl<B: BitBlock> BitSet<B> {
         other.is_subset(self)
     }
 
+    /// Returns `true` if both sets hold the same elements, like `==`, also
+    /// between sets with different block types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let a: BitSet<u32> = [1, 40].iter().cloned().collect();
+    /// let b: BitSet<u64> = [1, 40].iter().cloned().collect();
+    /// assert!(a.eq_elements(&b));
+    /// ```
+    #[inline]
+    pub fn eq_elements<C: BitBlock>(&self, other: &BitSet<C>) -> bool {
+        self.iter().eq(other)
+    }
+
+    /// Compares the elements of the sets in ascending order, like `Ord`,
+    /// also between sets with different block types.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    /// use std::cmp::Ordering;
+    ///
+    /// let a: BitSet<u32> = [1, 40].iter().cloned().collect();
+    /// let b: BitSet<u8> = [1, 41].iter().cloned().collect();
+    /// assert_eq!(a.cmp_elements(&b), Ordering::Less);
+    /// ```
+    #[inline]
+    pub fn cmp_elements<C: BitBlock>(&self, other: &BitSet<C>) -> Ordering {
+        self.iter().cmp(other)
+    }
+
+    /// Returns the smallest value not in the set, also known as its minimum
+    /// excluded value (mex). Whole blocks of present values are skipped at
+    /// once.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s: BitSet = BitSet::from_range(0..5);
+    /// assert_eq!(s.first_absent(), 5);
+    ///
+    /// s.remove(2);
+    /// assert_eq!(s.first_absent(), 2);
+    /// ```
+    #[inline]
+    pub fn first_absent(&self) -> usize {
+        let blocks = self.bit_vec.storage();
+        find_bit(blocks, 0, false).unwrap_or(blocks.len() * B::bits())
+    }
+
+    /// Returns the smallest value starting a run of `len` consecutive values
+    /// that are all absent from the set. The search skips over whole blocks
+    /// of present values, and values past the end of the storage count as
+    /// absent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = [0, 1, 4, 9].iter().cloned().collect();
+    /// assert_eq!(s.find_free_run(1), 2);
+    /// assert_eq!(s.find_free_run(3), 5);
+    /// assert_eq!(s.find_free_run(5), 10);
+    /// ```
+    pub fn find_free_run(&self, len: usize) -> usize {
+        if len == 0 {
+            return 0;
+        }
+        let end = self.bit_vec.storage().len() * B::bits();
+        for gap in self.gaps(end) {
+            // The last gap extends past the storage without bound
+            if gap.len() >= len || gap.end == end {
+                return gap.start;
+            }
+        }
+        end
+    }
+
+    /// Returns `true` if the elements of the set form a single range of
+    /// consecutive values. The empty set is considered contiguous.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s: BitSet = BitSet::from_range(3..10);
+    /// assert!(s.is_contiguous());
+    ///
+    /// s.remove(5);
+    /// assert!(!s.is_contiguous());
+    /// ```
+    #[inline]
+    pub fn is_contiguous(&self) -> bool {
+        self.as_range().is_some()
+    }
+
+    /// Returns the range formed by the elements of the set, or `None` if they
+    /// aren't consecutive. The empty set gives the empty range `0..0`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s: BitSet = BitSet::from_range(3..10);
+    /// assert_eq!(s.as_range(), Some(3..10));
+    ///
+    /// s.insert(11);
+    /// assert_eq!(s.as_range(), None);
+    /// ```
+    pub fn as_range(&self) -> Option<Range<usize>> {
+        let mut runs = self.runs();
+        match runs.next() {
+            None => Some(0..0),
+            Some(run) if runs.next().is_none() => Some(run),
+            Some(_) => None,
+        }
+    }
+
     /// Adds a value to the set. Returns `true` if the value was not already
     /// present in the set.
     pub fn insert(&mut self, value: usize) -> bool {
@@ -775,26 +2243,307 @@ impl<B: BitBlock> BitSet<B> {
             self.bit_vec.grow(value - len + 1, false)
         }
 
+        kernels::insert(unsafe { self.bit_vec.storage_mut() }, value)
+    }
+
+    /// Adds every value of a slice sorted in ascending order to the set,
+    /// reserving the storage once and setting the bits of the values falling
+    /// in the same block at once.
+    ///
+    /// Values out of order are still inserted, only with less grouping.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s = BitSet::new();
+    /// s.insert(5);
+    /// s.insert_sorted(&[1, 2, 3, 40, 41]);
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), [1, 2, 3, 5, 40, 41]);
+    /// ```
+    pub fn insert_sorted(&mut self, values: &[usize]) {
+        if let Some(&last) = values.last() {
+            self.reserve_len(last + 1);
+        }
+        self.insert_grouped(values.iter().cloned());
+    }
+
+    /// Flips the membership of each value of `values`: absent values are
+    /// added and present ones removed, as a symmetric difference with the
+    /// stream. A value given twice is flipped twice. Accepts values or
+    /// references to them, such as a slice; sorted values are grouped best.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut parity: BitSet = [1, 2].iter().cloned().collect();
+    /// parity.toggle_all(&[2, 3, 7, 7]);
+    /// assert_eq!(parity.iter().collect::<Vec<_>>(), [1, 3]);
+    /// ```
+    pub fn toggle_all<I>(&mut self, values: I)
+        where I: IntoIterator, I::Item: Borrow<usize>
+    {
+        let mut nbits = self.bit_vec.len();
+        let blocks = unsafe { self.bit_vec.storage_mut() };
+        let values = values.into_iter().map(|value| {
+            let value = *value.borrow();
+            nbits = cmp::max(nbits, value + 1);
+            value
+        });
+        Self::for_each_block_mask(values, |a, b| a ^ b, |i, mask| {
+            if i >= blocks.len() {
+                blocks.resize(i + 1, B::zero());
+            }
+            blocks[i] = blocks[i] ^ mask;
+        });
+        unsafe { self.bit_vec.set_len(nbits) };
+    }
+
+    /// Adds a value to the set like [`insert`](#method.insert), but returns
+    /// an error instead of aborting if the storage can't grow to hold it.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s = BitSet::new();
+    /// assert_eq!(s.try_insert(5), Ok(true));
+    /// assert_eq!(s.try_insert(5), Ok(false));
+    ///
+    /// assert!(s.try_insert(usize::MAX).is_err());
+    /// assert_eq!(s.len(), 1);
+    /// ```
+    pub fn try_insert(&mut self, value: usize) -> Result<bool, TryReserveError> {
+        if self.contains(value) {
+            return Ok(false);
+        }
+
+        let len = self.bit_vec.len();
+        if value >= len {
+            // Reserve the blocks fallibly, so growing can't reallocate
+            self.try_reserve_len(value.saturating_add(1))?;
+            self.bit_vec.grow(value - len + 1, false);
+        }
+
         self.bit_vec.set(value, true);
-        return true;
+        Ok(true)
+    }
+
+    /// Unions in-place like [`union_with`](#method.union_with), but returns
+    /// an error instead of aborting if this set can't grow to the length of
+    /// `other`. On error, the set is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut a: BitSet = [1, 2].iter().cloned().collect();
+    /// let b: BitSet = [2, 100].iter().cloned().collect();
+    /// assert!(a.try_union_with(&b).is_ok());
+    /// assert_eq!(a.iter().collect::<Vec<_>>(), [1, 2, 100]);
+    /// ```
+    pub fn try_union_with(&mut self, other: &Self) -> Result<(), TryReserveError> {
+        self.try_grow(other.bit_vec.len())?;
+        self.union_with(other);
+        Ok(())
+    }
+
+    /// Takes the symmetric difference in-place like
+    /// [`symmetric_difference_with`](#method.symmetric_difference_with), but
+    /// returns an error instead of aborting if this set can't grow to the
+    /// length of `other`. On error, the set is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut a: BitSet = [1, 2].iter().cloned().collect();
+    /// let b: BitSet = [2, 100].iter().cloned().collect();
+    /// assert!(a.try_symmetric_difference_with(&b).is_ok());
+    /// assert_eq!(a.iter().collect::<Vec<_>>(), [1, 100]);
+    /// ```
+    pub fn try_symmetric_difference_with(&mut self, other: &Self) -> Result<(), TryReserveError> {
+        self.try_grow(other.bit_vec.len())?;
+        self.symmetric_difference_with(other);
+        Ok(())
+    }
+
+    /// Removes a value from the set. Returns `true` if the value was
+    /// present in the set.
+    pub fn remove(&mut self, value: usize) -> bool {
+        kernels::remove(unsafe { self.bit_vec.storage_mut() }, value)
+    }
+
+    /// Removes every value of `values` from the set, clearing the bits of
+    /// the values falling in the same block at once, without checking
+    /// whether they are present. Accepts values or references to them, such
+    /// as a slice; sorted values are grouped best.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s: BitSet = (0..10).collect();
+    /// s.remove_all(&[1, 2, 3, 100]);
+    /// s.remove_all(8..10);
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), [0, 4, 5, 6, 7]);
+    /// ```
+    pub fn remove_all<I>(&mut self, values: I)
+        where I: IntoIterator, I::Item: Borrow<usize>
+    {
+        let blocks = unsafe { self.bit_vec.storage_mut() };
+        let nblocks = blocks.len();
+        let values = values.into_iter().map(|value| *value.borrow());
+        Self::for_each_block_mask(values, |a, b| a | b, |i, mask| {
+            if i < nblocks {
+                blocks[i] = blocks[i] & !mask;
+            }
+        });
+    }
+}
+
+/// Formats the set as a list of its elements, like `{1, 2, 10}`.
+///
+/// The alternate flag (`{:#?}`) instead prints a summary of the set holding
+/// its number of elements, the length of its underlying bit vector, its
+/// capacity, and its elements with consecutive values compressed into
+/// ranges.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BitSet;
+///
+/// let s: BitSet = [1, 2, 3, 4, 10].iter().cloned().collect();
+/// assert_eq!(format!("{:?}", s), "{1, 2, 3, 4, 10}");
+/// println!("{:#?}", s);
+/// // BitSet {
+/// //     len: 5,
+/// //     universe: 11,
+/// //     capacity: 128,
+/// //     elements: {1..=4, 10},
+/// // }
+/// ```
+impl<B: BitBlock> fmt::Debug for BitSet<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if fmt.alternate() {
+            fmt.debug_struct("BitSet")
+                .field("len", &self.len())
+                .field("universe", &self.bit_vec.len())
+                .field("capacity", &self.capacity())
+                .field("elements", &DebugRuns(self))
+                .finish()
+        } else {
+            fmt.debug_set().entries(self).finish()
+        }
+    }
+}
+
+/// Formats the elements of a set on a single line, with runs of consecutive
+/// values written as inclusive ranges.
+struct DebugRuns<'a, B: 'a>(&'a BitSet<B>);
+
+impl<'a, B: BitBlock> fmt::Debug for DebugRuns<'a, B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_char('{')?;
+        for (i, run) in self.0.runs().enumerate() {
+            let sep = if i == 0 { "" } else { ", " };
+            if run.len() == 1 {
+                write!(fmt, "{}{}", sep, run.start)?;
+            } else {
+                write!(fmt, "{}{}..={}", sep, run.start, run.end - 1)?;
+            }
+        }
+        fmt.write_char('}')
+    }
+}
+
+impl<B: BitBlock> BitSet<B> {
+    /// Writes the nibbles of the blocks in storage as hex digits.
+    fn fmt_hex(&self, fmt: &mut fmt::Formatter, digits: &[u8; 16], prefix: &str) -> fmt::Result {
+        if fmt.alternate() {
+            fmt.write_str(prefix)?;
+        }
+        for block in self.bit_vec.blocks() {
+            for k in 0..B::bytes() {
+                let byte = block_byte(block, k);
+                fmt.write_char(digits[(byte & 0xf) as usize] as char)?;
+                fmt.write_char(digits[(byte >> 4) as usize] as char)?;
+            }
+        }
+        Ok(())
     }
+}
 
-    /// Removes a value from the set. Returns `true` if the value was
-    /// present in the set.
-    pub fn remove(&mut self, value: usize) -> bool {
-        if !self.contains(value) {
-            return false;
+/// Formats the bit pattern of the underlying blocks, least significant bit
+/// first: the `i`-th digit is `1` if the set contains `i`. All the bits of the
+/// blocks in storage are written, so there are `B::bits()` digits per block.
+/// The alternate flag (`{:#b}`) adds a `0b` prefix.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BitSet;
+///
+/// let s: BitSet<u8> = [0, 2, 9].iter().cloned().collect();
+/// assert_eq!(format!("{:b}", s), "1010000001000000");
+/// ```
+impl<B: BitBlock> fmt::Binary for BitSet<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        if fmt.alternate() {
+            fmt.write_str("0b")?;
         }
+        for block in self.bit_vec.blocks() {
+            for i in 0..B::bits() {
+                let bit = (block >> i) & B::one() != B::zero();
+                fmt.write_char(if bit { '1' } else { '0' })?;
+            }
+        }
+        Ok(())
+    }
+}
 
-        self.bit_vec.set(value, false);
-
-        return true;
+/// Formats the bit pattern of the underlying blocks as hex digits, least
+/// significant nibble first: the `k`-th digit holds the elements `4 * k` to
+/// `4 * k + 3`, with `4 * k` as its least significant bit. All the bits of the
+/// blocks in storage are written, and the alternate flag (`{:#x}`) adds a
+/// `0x` prefix.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BitSet;
+///
+/// let s: BitSet<u8> = [0, 2, 9].iter().cloned().collect();
+/// assert_eq!(format!("{:x}", s), "5020");
+/// ```
+impl<B: BitBlock> fmt::LowerHex for BitSet<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_hex(fmt, b"0123456789abcdef", "0x")
     }
 }
 
-impl<B: BitBlock> fmt::Debug for BitSet<B> {
+/// Formats the bit pattern of the underlying blocks as upper case hex digits,
+/// in the same order as the `LowerHex` implementation.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BitSet;
+///
+/// let s: BitSet<u8> = [4, 5, 6, 7, 9].iter().cloned().collect();
+/// assert_eq!(format!("{:#X}", s), "0x0F20");
+/// ```
+impl<B: BitBlock> fmt::UpperHex for BitSet<B> {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        fmt.debug_set().entries(self).finish()
+        self.fmt_hex(fmt, b"0123456789ABCDEF", "0x")
     }
 }
 
@@ -806,6 +2555,17 @@ impl<B: BitBlock> hash::Hash for BitSet<B> {
     }
 }
 
+/// The memory used by a `BitSet`, as returned by
+/// [`memory_usage`](struct.BitSet.html#method.memory_usage).
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MemoryUsage {
+    /// The bytes allocated on the heap for the blocks.
+    pub heap_bytes: usize,
+    /// The number of bits stored, one past the largest value the set can
+    /// hold without growing its length.
+    pub universe_len: usize,
+}
+
 #[derive(Clone)]
 struct BlockIter<T, B> {
     head: B,
@@ -818,6 +2578,30 @@ impl<T, B: BitBlock> BlockIter<T, B> where T: Iterator<Item=B> {
         let h = blocks.next().unwrap_or(B::zero());
         BlockIter {tail: blocks, head: h, head_offset: 0}
     }
+
+    /// Writes the next values to the start of `buf`, as many as fit, and
+    /// returns how many were written.
+    fn fill(&mut self, buf: &mut [usize]) -> usize {
+        let mut head = self.head;
+        let mut filled = 0;
+        while filled < buf.len() {
+            if head == B::zero() {
+                match self.tail.next() {
+                    Some(block) => {
+                        head = block;
+                        self.head_offset += B::bits();
+                        continue;
+                    }
+                    None => break,
+                }
+            }
+            buf[filled] = self.head_offset + lowest_bit(head);
+            head = head & (head - B::one());
+            filled += 1;
+        }
+        self.head = head;
+        filled
+    }
 }
 
 /// An iterator combining two `BitSet` iterators.
@@ -840,27 +2624,35 @@ pub struct Difference<'a, B: 'a>(BlockIter<TwoBitPositions<'a, B>, B>);
 #[derive(Clone)]
 pub struct SymmetricDifference<'a, B: 'a>(BlockIter<TwoBitPositions<'a, B>, B>);
 
-impl<'a, T, B: BitBlock> Iterator for BlockIter<T, B> where T: Iterator<Item=B> {
+/// An iterator over the runs of consecutive elements of a `BitSet`.
+#[derive(Clone)]
+pub struct Runs<'a, B: 'a> {
+    blocks: &'a [B],
+    pos: usize,
+}
+
+/// An iterator over the runs of values missing from a `BitSet`.
+#[derive(Clone)]
+pub struct Gaps<'a, B: 'a> {
+    blocks: &'a [B],
+    pos: usize,
+    end: usize,
+}
+
+impl<T, B: BitBlock> Iterator for BlockIter<T, B> where T: Iterator<Item=B> {
     type Item = usize;
 
     fn next(&mut self) -> Option<usize> {
-        while self.head == B::zero() {
-            match self.tail.next() {
-                Some(w) => self.head = w,
-                None => return None
-            }
+        // Work on a local copy of the head so it can stay in a register
+        let mut head = self.head;
+        while head == B::zero() {
+            head = self.tail.next()?;
             self.head_offset += B::bits();
         }
-
-        // from the current block, isolate the
-        // LSB and subtract 1, producing k:
-        // a block with a number of set bits
-        // equal to the index of the LSB
-        let k = (self.head & (!self.head + B::one())) - B::one();
+        let bit = lowest_bit(head);
         // update block, removing the LSB
-        self.head = self.head & (self.head - B::one());
-        // return offset + (index of LSB)
-        Some(self.head_offset + (B::count_ones(k) as usize))
+        self.head = head & (head - B::one());
+        Some(self.head_offset + bit)
     }
 
     #[inline]
@@ -870,6 +2662,25 @@ impl<'a, T, B: BitBlock> Iterator for BlockIter<T, B> where T: Iterator<Item=B>
             _ => (0, None)
         }
     }
+
+    /// Visits the values block by block, in a tight loop over the bits of
+    /// each block without updating the iterator state. `for_each` and most
+    /// consumers of the iterators go through `fold`.
+    fn fold<Acc, F>(self, init: Acc, mut f: F) -> Acc where F: FnMut(Acc, usize) -> Acc {
+        let mut visit = |mut acc, mut block: B, offset| {
+            while block != B::zero() {
+                acc = f(acc, offset + lowest_bit(block));
+                block = block & (block - B::one());
+            }
+            acc
+        };
+        let mut offset = self.head_offset;
+        let acc = visit(init, self.head, offset);
+        self.tail.fold(acc, |acc, block| {
+            offset += B::bits();
+            visit(acc, block, offset)
+        })
+    }
 }
 
 impl<'a, B: BitBlock> Iterator for TwoBitPositions<'a, B> {
@@ -880,7 +2691,7 @@ impl<'a, B: BitBlock> Iterator for TwoBitPositions<'a, B> {
             (Some(a), Some(b)) => Some((self.merge)(a, b)),
             (Some(a), None) => Some((self.merge)(a, B::zero())),
             (None, Some(b)) => Some((self.merge)(B::zero(), b)),
-            _ => return None
+            _ => None
         }
     }
 
@@ -898,11 +2709,41 @@ impl<'a, B: BitBlock> Iterator for TwoBitPositions<'a, B> {
     }
 }
 
+impl<'a, B: BitBlock> Iter<'a, B> {
+    /// Writes the next values to `buf`, filling it unless the iterator runs
+    /// out, and returns the part written, or `None` if nothing was: once the
+    /// iterator is exhausted, or if `buf` is empty. The values are extracted
+    /// a block at a time, so consumers working on batches of indices avoid a
+    /// call per value.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = (0..20).filter(|x| x % 3 == 0).collect();
+    /// let mut iter = s.iter();
+    /// let mut buf = [0; 4];
+    /// let mut batches = Vec::new();
+    /// while let Some(batch) = iter.next_batch(&mut buf) {
+    ///     batches.push(batch.to_vec());
+    /// }
+    /// assert_eq!(batches, [vec![0, 3, 6, 9], vec![12, 15, 18]]);
+    /// ```
+    pub fn next_batch<'b>(&mut self, buf: &'b mut [usize]) -> Option<&'b [usize]> {
+        match self.0.fill(buf) {
+            0 => None,
+            n => Some(&buf[..n]),
+        }
+    }
+}
+
 impl<'a, B: BitBlock> Iterator for Iter<'a, B> {
     type Item = usize;
 
     #[inline] fn next(&mut self) -> Option<usize> { self.0.next() }
     #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+    #[inline] fn fold<Acc, F>(self, init: Acc, f: F) -> Acc where F: FnMut(Acc, usize) -> Acc { self.0.fold(init, f) }
 }
 
 impl<'a, B: BitBlock> Iterator for Union<'a, B> {
@@ -910,6 +2751,7 @@ impl<'a, B: BitBlock> Iterator for Union<'a, B> {
 
     #[inline] fn next(&mut self) -> Option<usize> { self.0.next() }
     #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+    #[inline] fn fold<Acc, F>(self, init: Acc, f: F) -> Acc where F: FnMut(Acc, usize) -> Acc { self.0.fold(init, f) }
 }
 
 impl<'a, B: BitBlock> Iterator for Intersection<'a, B> {
@@ -917,6 +2759,7 @@ impl<'a, B: BitBlock> Iterator for Intersection<'a, B> {
 
     #[inline] fn next(&mut self) -> Option<usize> { self.0.next() }
     #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+    #[inline] fn fold<Acc, F>(self, init: Acc, f: F) -> Acc where F: FnMut(Acc, usize) -> Acc { self.0.fold(init, f) }
 }
 
 impl<'a, B: BitBlock> Iterator for Difference<'a, B> {
@@ -924,6 +2767,7 @@ impl<'a, B: BitBlock> Iterator for Difference<'a, B> {
 
     #[inline] fn next(&mut self) -> Option<usize> { self.0.next() }
     #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+    #[inline] fn fold<Acc, F>(self, init: Acc, f: F) -> Acc where F: FnMut(Acc, usize) -> Acc { self.0.fold(init, f) }
 }
 
 impl<'a, B: BitBlock> Iterator for SymmetricDifference<'a, B> {
@@ -931,6 +2775,37 @@ impl<'a, B: BitBlock> Iterator for SymmetricDifference<'a, B> {
 
     #[inline] fn next(&mut self) -> Option<usize> { self.0.next() }
     #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+    #[inline] fn fold<Acc, F>(self, init: Acc, f: F) -> Acc where F: FnMut(Acc, usize) -> Acc { self.0.fold(init, f) }
+}
+
+impl<'a, B: BitBlock> Iterator for Runs<'a, B> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        let start = find_bit(self.blocks, self.pos, true)?;
+        let end = find_bit(self.blocks, start, false)
+            .unwrap_or(self.blocks.len() * B::bits());
+        self.pos = end;
+        Some(start..end)
+    }
+}
+
+impl<'a, B: BitBlock> Iterator for Gaps<'a, B> {
+    type Item = Range<usize>;
+
+    fn next(&mut self) -> Option<Range<usize>> {
+        // Every value past the stored blocks is absent
+        let start = find_bit(self.blocks, self.pos, false)
+            .unwrap_or(cmp::max(self.pos, self.blocks.len() * B::bits()));
+        if start >= self.end {
+            self.pos = self.end;
+            return None;
+        }
+        let end = find_bit(self.blocks, start, true)
+            .map_or(self.end, |end| cmp::min(end, self.end));
+        self.pos = end;
+        Some(start..end)
+    }
 }
 
 impl<'a, B: BitBlock> IntoIterator for &'a BitSet<B> {
@@ -959,6 +2834,24 @@ mod tests {
         assert_eq!("{1, 2, 10, 50}", format!("{:?}", s));
     }
 
+    #[test]
+    fn test_bit_set_show_alternate() {
+        let mut s = BitSet::with_capacity(64);
+        s.insert(1);
+        s.insert(10);
+        s.insert(11);
+        s.insert(12);
+        s.insert(50);
+        assert_eq!(format!("{:#?}", s), format!(
+            "BitSet {{\n    len: 5,\n    universe: 51,\n    capacity: {},\n    \
+             elements: {{1, 10..=12, 50}},\n}}", s.capacity()));
+
+        let empty = BitSet::new();
+        assert_eq!(format!("{:#?}", empty),
+                   "BitSet {\n    len: 0,\n    universe: 0,\n    capacity: 0,\n    \
+                    elements: {},\n}");
+    }
+
     #[test]
     fn test_bit_set_from_usizes() {
         let usizes = vec![0, 2, 2, 3];
@@ -993,7 +2886,7 @@ mod tests {
             for &l in &lengths {
                 let bitset = BitSet::from_bit_vec(BitVec::from_elem(l, b));
                 assert_eq!(bitset.contains(1), b);
-                assert_eq!(bitset.contains((l-1)), b);
+                assert_eq!(bitset.contains(l - 1), b);
                 assert!(!bitset.contains(l));
             }
         }
@@ -1027,6 +2920,232 @@ mod tests {
         assert_eq!(b.len(), 3);
     }
 
+    #[test]
+    fn test_bit_set_get_index() {
+        let mut s = BitSet::from_bit_vec(BitVec::from_elem(16, false));
+        s.insert(3);
+        assert_eq!((s.get(3), s.get(15), s.get(16)), (Some(true), Some(false), None));
+        assert!(s[3] && !s[4] && !s[usize::MAX]);
+        s.remove(3);
+        assert_eq!(s.get(3), Some(false));
+    }
+
+    #[test]
+    fn test_bit_set_contains_all_any() {
+        let s: BitSet<u8> = [0, 7, 8, 30].iter().cloned().collect();
+        let wanted: &[usize] = &[30, 0, 8];
+        assert!(s.contains_all(wanted) && s.contains_all(vec![7]));
+        assert!(!s.contains_all([0, 31]) && !s.contains_all([usize::MAX]));
+        assert!(s.contains_any(wanted.iter().map(|x| x * 2)) && !s.contains_any(9..30));
+        let empty: &[usize] = &[];
+        assert!(s.contains_all(empty) && !s.contains_any(empty));
+    }
+
+    #[test]
+    fn test_bit_set_insert_sorted() {
+        let mut s: BitSet<u8> = [4, 100].iter().cloned().collect();
+        s.insert_sorted(&[0, 1, 7, 8, 8, 30, 31, 32]);
+        assert_eq!(s.iter().collect::<Vec<_>>(), [0, 1, 4, 7, 8, 30, 31, 32, 100]);
+        assert_eq!(s.get_ref().len(), 101);
+        s.insert_sorted(&[200, 3, 150]);
+        assert_eq!(s.get_ref().len(), 201);
+        assert!(s.contains_all([3, 150, 200]));
+        s.insert_sorted(&[]);
+        assert_eq!(s.len(), 12);
+
+        let values = [9, 17, 16, 300, 2, 2];
+        let t: BitSet<u8> = BitSet::from_sorted_iter(values.iter().cloned());
+        assert_eq!(t, values.iter().cloned().collect::<BitSet<u8>>());
+        assert_eq!(t.get_ref().storage().len(), 38);
+    }
+
+    #[test]
+    fn test_bit_set_remove_all() {
+        let mut s: BitSet<u8> = (0..40).collect();
+        let removed: &[usize] = &[0, 1, 9, 9, 20, 1000];
+        s.remove_all(removed);
+        s.remove_all(vec![39, 2, 38]);
+        assert_eq!(s.len(), 33);
+        assert!(!s.contains_any([0, 1, 2, 9, 20, 38, 39]));
+        assert_eq!(s.get_ref().len(), 40);
+        s.remove_all(0..40);
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn test_bit_set_toggle_all() {
+        let mut s: BitSet<u8> = [0, 9, 20].iter().cloned().collect();
+        let toggled: &[usize] = &[0, 1, 1, 1, 9, 30, 2, 30];
+        s.toggle_all(toggled);
+        assert_eq!(s.iter().collect::<Vec<_>>(), [1, 2, 20]);
+        assert_eq!(s.get_ref().len(), 31);
+        s.toggle_all(vec![1, 2, 20]);
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn test_bit_set_extend_ref() {
+        let values = [3, 40, 3];
+        let mut s: BitSet<u8> = values.iter().collect();
+        s.extend(values.iter().filter(|&&v| v > 3));
+        s.extend(&[0]);
+        assert_eq!(s.iter().collect::<Vec<_>>(), [0, 3, 40]);
+    }
+
+    #[test]
+    fn test_bit_set_from_bools() {
+        let primes: BitSet<u8> = BitSet::from_fn(20, |i| i > 1 && (2..i).all(|d| i % d != 0));
+        assert_eq!(primes.iter().collect::<Vec<_>>(), [2, 3, 5, 7, 11, 13, 17, 19]);
+        assert_eq!(primes.get_ref().len(), 20);
+
+        let bools = [false, true, false, false, false, false, false, false, true, false];
+        let mut s: BitSet<u8> = bools.iter().cloned().collect();
+        assert_eq!(s.iter().collect::<Vec<_>>(), [1, 8]);
+        assert_eq!(s.get_ref().len(), 10);
+        assert!(BitSet::<u8>::from_fn(0, |_| true).get_ref().is_empty());
+
+        s.insert(3);
+        s.extend(vec![true, false, false, false, true]);
+        assert_eq!(s.iter().collect::<Vec<_>>(), [0, 1, 3, 4, 8]);
+    }
+
+    #[test]
+    fn test_bit_set_to_vec() {
+        let s: BitSet<u8> = [0, 9, 200].iter().cloned().collect();
+        let v = s.to_vec();
+        assert_eq!(v, [0, 9, 200]);
+        assert_eq!(v.capacity(), 3);
+        assert!(BitSet::<u8>::default().to_vec().is_empty());
+
+        let mut buffer = vec![1];
+        s.collect_into(&mut buffer);
+        assert_eq!(buffer, [1, 0, 9, 200]);
+    }
+
+    #[test]
+    fn test_bit_set_density() {
+        let s: BitSet<u8> = [0, 7, 8, 15, 16, 100].iter().cloned().collect();
+        let counts: Vec<_> = [0, 1, 7, 8, 9, 16, 17, 101, 1000].iter()
+            .map(|&u| u - s.count_zeros(u))
+            .collect();
+        assert_eq!(counts, [0, 1, 1, 2, 3, 4, 5, 6, 6]);
+        assert_eq!(s.density(0), 0.0);
+        assert_eq!(s.density(16), 0.25);
+        assert_eq!(BitSet::<u8>::from_range(0..24).density(24), 1.0);
+    }
+
+    #[test]
+    fn test_bit_set_cmp_across_blocks() {
+        let a: BitSet<u8> = [3, 9, 300].iter().cloned().collect();
+        let mut b: BitSet<u64> = a.iter().collect();
+        b.reserve_len(1000);
+        b.insert(2000);
+        b.remove(2000);
+        assert!(a.eq_elements(&b));
+        assert_eq!(a.cmp_elements(&b), Equal);
+        b.insert(4);
+        assert!(!a.eq_elements(&b));
+        assert_eq!(a.cmp_elements(&b), Greater);
+        assert_eq!(BitSet::<u16>::default().cmp_elements(&a), Less);
+
+        // Comparisons within a block type still infer the other operand
+        let c = a.clone();
+        assert_eq!(BitSet::<u8>::default(), BitSet::default());
+        assert!(a == c && BitSet::default() < a);
+    }
+
+    #[test]
+    fn test_bit_set_convert() {
+        use primitive::tests::Wrapped;
+
+        let s: BitSet<u16> = [0, 15, 16, 63, 64, 70].iter().cloned().collect();
+        let wide: BitSet<u64> = s.convert();
+        assert_eq!(wide.as_block_slice(), [1 | 1 << 15 | 1 << 16 | 1 << 63, 1 | 1 << 6]);
+        assert_eq!(wide.get_ref().len(), 71);
+        let narrow: BitSet<u8> = wide.convert();
+        assert_eq!(narrow.as_block_slice().len(), 9);
+        assert_eq!(narrow.get_ref().len(), 71);
+        assert!(narrow.eq_elements(&s));
+        assert_eq!(narrow.convert::<u16>().get_ref(), s.get_ref());
+        assert!(BitSet::<u8>::default().convert::<u32>().get_ref().is_empty());
+
+        // Blocks that aren't primitive integers go through the generic path
+        let generic: BitSet<Wrapped> = s.convert();
+        assert_eq!(generic.as_block_slice(),
+                   [Wrapped(1 | 1 << 15 | 1 << 16), Wrapped(1 << 31), Wrapped(1 | 1 << 6)]);
+        assert_eq!(generic.convert::<u16>().get_ref(), s.get_ref());
+    }
+
+    #[test]
+    fn test_bit_set_wide_aliases() {
+        use super::{BitSet64, NativeBitSet};
+
+        let mut a = BitSet64::from((0..10).map(|_| false).collect::<BitVec<u64>>());
+        a.insert_sorted(&[0, 63, 64, 200]);
+        let mut b = NativeBitSet::default();
+        b.extend(vec![63, 64, 65]);
+        assert_eq!(a.intersection(&a).count(), 4);
+        a.intersect_with(&b.convert());
+        assert_eq!(a.iter().collect::<Vec<_>>(), [63, 64]);
+        assert_eq!(b.len(), 3);
+        assert!(a.cmp_elements(&b) == Less && a.is_subset(&b.convert()));
+    }
+
+    #[test]
+    fn test_bit_set_next_batch() {
+        let values = [0, 1, 7, 8, 9, 30, 31, 32, 63, 64, 100];
+        let s: BitSet<u8> = values.iter().collect();
+        for size in 1..13 {
+            let mut iter = s.iter();
+            let mut buf = vec![0; size];
+            let mut seen = Vec::new();
+            while let Some(batch) = iter.next_batch(&mut buf) {
+                assert!(batch.len() == size || seen.len() + batch.len() == values.len());
+                seen.extend_from_slice(batch);
+            }
+            assert_eq!(seen, values);
+            assert_eq!(iter.next(), None);
+        }
+
+        let mut iter = s.iter();
+        iter.next();
+        assert_eq!(iter.next_batch(&mut [0; 2]), Some(&[1, 7][..]));
+        assert_eq!(iter.next(), Some(8));
+        assert_eq!(iter.next_batch(&mut []), None);
+    }
+
+    #[test]
+    fn test_bit_set_fold() {
+        let values = [0, 1, 7, 8, 9, 30, 31, 32, 63, 64, 100];
+        let s: BitSet<u8> = values.iter().collect();
+        let mut seen = Vec::new();
+        s.iter().for_each(|v| seen.push(v));
+        assert_eq!(seen, values);
+        for skip in 0..values.len() + 1 {
+            let mut iter = s.iter();
+            for _ in 0..skip {
+                iter.next();
+            }
+            assert_eq!(iter.fold(Vec::new(), |mut acc, v| { acc.push(v); acc }), &values[skip..]);
+        }
+        let t: BitSet<u8> = (5..40).collect();
+        assert_eq!(s.union(&t).fold(0, |n, _| n + 1), s.union(&t).count());
+        assert_eq!(s.difference(&t).fold(Vec::new(), |mut a, v| { a.push(v); a }), [0, 1, 63, 64, 100]);
+        assert_eq!(s.intersection(&t).map(|v| v * 2).sum::<usize>(), (7 + 8 + 9 + 30 + 31 + 32) * 2);
+    }
+
+    #[test]
+    fn test_bit_set_try_for_each() {
+        let s: BitSet<u8> = [1, 9, 30, 100].iter().collect();
+        let mut seen = Vec::new();
+        assert_eq!(s.try_for_each(|v| { seen.push(v); Ok::<(), ()>(()) }), Ok(()));
+        assert_eq!(seen, [1, 9, 30, 100]);
+        seen.clear();
+        let result = s.try_for_each(|v| if v < 30 { seen.push(v); Ok(()) } else { Err(v) });
+        assert_eq!((result, seen), (Err(30), vec![1, 9]));
+        assert_eq!(BitSet::<u8>::default().try_for_each(Err), Ok(()));
+    }
+
     #[test]
     fn test_bit_set_intersection() {
         let mut a = BitSet::new();
@@ -1291,6 +3410,518 @@ mod tests {
         assert_eq!(c.cmp(&b), Equal);
     }
 
+    #[test]
+    fn test_bit_set_blocks_mut() {
+        let mut a = BitSet::from_bit_vec(BitVec::from_elem(10, false));
+        a.blocks_mut()[0] = 0b1000_0000_0001;
+        assert!(a.contains(0));
+        assert!(a.contains(11));
+        assert_eq!(a.len(), 2);
+        assert_eq!(a.iter().collect::<Vec<_>>(), [0, 11]);
+        assert_eq!(a.blocks().collect::<Vec<_>>(), [0b1000_0000_0001]);
+    }
+
+    #[test]
+    fn test_bit_set_raw_parts() {
+        let a = BitSet::from_blocks(vec![0u64, 1 << 63, 0]);
+        assert_eq!(a.iter().collect::<Vec<_>>(), [127]);
+        assert!(!a.contains(128));
+        assert_eq!(a.as_block_slice(), &[0, 1 << 63, 0]);
+
+        let mut b = a.clone();
+        assert!(b.insert(191));
+        assert!(b.insert(192));
+        assert_eq!(b.clone().into_raw_parts(), [0, 1 << 63, 1 << 63, 1]);
+        assert_eq!(BitSet::from_blocks(b.clone().into_raw_parts()), b);
+
+        let empty: BitSet = BitSet::from_blocks(vec![]);
+        assert!(empty.is_empty());
+    }
+
+    #[test]
+    fn test_bit_set_bytes() {
+        let a = BitSet::from_bytes(&[0b10100000, 0, 0b00000001]);
+        assert_eq!(a.iter().collect::<Vec<_>>(), [0, 2, 23]);
+        assert_eq!(a.to_bytes(), [0b10100000, 0, 0b00000001]);
+        assert_eq!(BitSet::from_bytes(&a.to_bytes()), a);
+
+        let mut b = BitSet::new();
+        b.insert(100);
+        let bytes = b.to_bytes();
+        assert_eq!(bytes.len(), 13);
+        assert_eq!(bytes[12], 0b00001000);
+        assert_eq!(BitSet::from_bytes(&bytes), b);
+
+        let c: BitSet<u64> = [3, 64].iter().cloned().collect();
+        assert_eq!(c.to_bytes(), [0b00010000, 0, 0, 0, 0, 0, 0, 0, 0b10000000]);
+    }
+
+    #[test]
+    fn test_bit_set_from_ranges() {
+        for &(start, end) in &[(0, 0), (5, 3), (0, 1), (3, 7), (0, 32), (31, 33),
+                               (10, 100), (32, 64), (1, 1000)] {
+            let a: BitSet = BitSet::from_range(start..end);
+            let b: BitSet = (start..end).collect();
+            assert_eq!(a, b);
+            assert_eq!(a.len(), end.saturating_sub(start));
+            assert!(!a.contains(end));
+        }
+
+        let a: BitSet<u8> = BitSet::from_ranges(vec![50..60, 3..5, 4..9, 0..0]);
+        let b: BitSet<u8> = (3..9).chain(50..60).collect();
+        assert_eq!(a, b);
+        assert_eq!(a.get_ref().len(), 60);
+    }
+
+    #[test]
+    fn test_bit_set_full() {
+        for &n in &[0, 1, 31, 32, 33, 100] {
+            let a: BitSet = BitSet::full(n);
+            assert_eq!(a.len(), n);
+            assert_eq!(a, (0..n).collect::<BitSet>());
+            assert!(!a.contains(n));
+
+            let b: BitSet<u64> = BitSet::full(n);
+            assert!(b.iter().eq(0..n));
+        }
+    }
+
+    #[test]
+    fn test_bit_set_macro() {
+        let a = bitset![1, 5, 9];
+        assert_eq!(a.iter().collect::<Vec<_>>(), [1, 5, 9]);
+        assert_eq!(a.get_ref().len(), 10);
+
+        let b = bitset![40..70, 3, 3..=5, 10..10,];
+        assert_eq!(b, (3..6).chain(40..70).collect::<BitSet>());
+        assert_eq!(b.get_ref().len(), 70);
+
+        let x = 2;
+        assert_eq!(bitset![x, x + 1], BitSet::from_range(2..4));
+        assert!(bitset![].is_empty());
+    }
+
+    #[test]
+    fn test_bit_set_fmt_bits() {
+        let empty = BitSet::new();
+        assert_eq!(format!("{:b}", empty), "");
+        assert_eq!(format!("{:#x}", empty), "0x");
+
+        let mut a = BitSet::new();
+        a.insert(0);
+        a.insert(5);
+        a.insert(31);
+        a.insert(36);
+        assert_eq!(format!("{:b}", a),
+                   "10000100000000000000000000000001\
+                    00001000000000000000000000000000");
+        assert_eq!(format!("{:x}", a), "1200000801000000");
+        assert_eq!(format!("{:#X}", a), "0x1200000801000000");
+
+        let b: BitSet<u64> = (60..64).collect();
+        assert_eq!(format!("{:#b}", b).len(), 66);
+        assert_eq!(format!("{:X}", b), "000000000000000F");
+    }
+
+    #[test]
+    fn test_bit_set_runs() {
+        let empty: BitSet = BitSet::new();
+        assert_eq!(empty.runs().next(), None);
+
+        let mut s: BitSet<u8> = BitSet::from_ranges(vec![3..5, 8..24, 30..32]);
+        assert_eq!(s.runs().collect::<Vec<_>>(), [3..5, 8..24, 30..32]);
+
+        s.insert(7);
+        s.insert(5);
+        s.insert(6);
+        assert_eq!(s.runs().collect::<Vec<_>>(), [3..24, 30..32]);
+
+        let full: BitSet<u64> = BitSet::full(200);
+        assert_eq!(full.runs().next(), Some(0..200));
+    }
+
+    #[test]
+    fn test_bit_set_gaps() {
+        let empty: BitSet = BitSet::new();
+        assert_eq!(empty.gaps(0).next(), None);
+        assert_eq!(empty.gaps(100).next(), Some(0..100));
+
+        let s: BitSet<u8> = BitSet::from_ranges(vec![0..3, 8..24, 30..32]);
+        assert_eq!(s.gaps(40).collect::<Vec<_>>(), [3..8, 24..30, 32..40]);
+        assert_eq!(s.gaps(26).collect::<Vec<_>>(), [3..8, 24..26]);
+        let mut gaps = s.gaps(24);
+        assert_eq!((gaps.next(), gaps.next()), (Some(3..8), None));
+        assert_eq!(s.gaps(2).next(), None);
+
+        let full: BitSet<u64> = BitSet::full(200);
+        assert_eq!(full.gaps(200).next(), None);
+        assert_eq!(full.gaps(300).next(), Some(200..300));
+    }
+
+    #[test]
+    fn test_bit_set_contiguous() {
+        let mut s: BitSet<u8> = BitSet::default();
+        assert!(s.is_contiguous());
+        assert_eq!(s.as_range(), Some(0..0));
+
+        s.insert(20);
+        assert_eq!(s.as_range(), Some(20..21));
+
+        s = BitSet::from_range(5..40);
+        assert_eq!(s.as_range(), Some(5..40));
+        s.insert(41);
+        assert!(!s.is_contiguous());
+        s.insert(40);
+        assert_eq!(s.as_range(), Some(5..42));
+    }
+
+    #[test]
+    fn test_bit_set_first_absent() {
+        let mut s: BitSet<u8> = BitSet::default();
+        assert_eq!(s.first_absent(), 0);
+
+        s = BitSet::from_range(0..16);
+        assert_eq!(s.first_absent(), 16);
+        s.insert(16);
+        assert_eq!(s.first_absent(), 17);
+        s.remove(9);
+        assert_eq!(s.first_absent(), 9);
+
+        let full: BitSet<u64> = BitSet::full(200);
+        assert_eq!(full.first_absent(), 200);
+    }
+
+    #[test]
+    fn test_bit_set_find_free_run() {
+        let mut s: BitSet<u8> = BitSet::default();
+        assert_eq!(s.find_free_run(0), 0);
+        assert_eq!(s.find_free_run(100), 0);
+
+        s = BitSet::from_ranges(vec![0..3, 5..8, 20..24]);
+        assert_eq!(s.find_free_run(0), 0);
+        assert_eq!(s.find_free_run(2), 3);
+        assert_eq!(s.find_free_run(3), 8);
+        assert_eq!(s.find_free_run(12), 8);
+        assert_eq!(s.find_free_run(13), 24);
+
+        s = BitSet::from_range(0..16);
+        assert_eq!(s.find_free_run(4), 16);
+    }
+
+    #[test]
+    fn test_try_insert() {
+        let mut s: BitSet<u8> = BitSet::default();
+        assert_eq!(s.try_insert(3), Ok(true));
+        assert_eq!(s.try_insert(100), Ok(true));
+        assert_eq!(s.try_insert(100), Ok(false));
+        assert_eq!(s.get_ref().len(), 101);
+        assert!(s.try_insert(usize::MAX - 1).is_err());
+        assert!(s.try_insert(usize::MAX).is_err());
+        assert_eq!(s.iter().collect::<Vec<_>>(), [3, 100]);
+    }
+
+    #[test]
+    fn test_try_grow_ops() {
+        let mut a: BitSet<u8> = [1, 2, 20].iter().cloned().collect();
+        let b: BitSet<u8> = [2, 50].iter().cloned().collect();
+
+        let mut union = a.clone();
+        assert_eq!(union.try_union_with(&b), Ok(()));
+        assert_eq!(union.iter().collect::<Vec<_>>(), [1, 2, 20, 50]);
+        assert_eq!(union.get_ref().len(), 51);
+
+        assert_eq!(a.try_symmetric_difference_with(&b), Ok(()));
+        assert_eq!(a.iter().collect::<Vec<_>>(), [1, 20, 50]);
+
+        let mut s: BitSet<u8> = BitSet::default();
+        assert_eq!(s.try_reserve_len(100), Ok(()));
+        assert!(s.capacity() >= 100);
+        assert_eq!(s.try_reserve_len_exact(10), Ok(()));
+        assert!(s.try_reserve_len(usize::MAX).is_err());
+        assert!(s.try_reserve_len_exact(usize::MAX).is_err());
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn test_with_capacity() {
+        let mut s: BitSet = BitSet::with_capacity(1000);
+        let capacity = s.capacity();
+        assert!(capacity >= 1000);
+        assert_eq!(s.get_ref().len(), 0);
+        assert_eq!(s.cmp(&BitSet::new()), Equal);
+
+        s.insert(999);
+        assert_eq!(s.capacity(), capacity);
+        s.reserve_len(500);
+        assert_eq!(s.capacity(), capacity);
+        s.reserve_len(2000);
+        assert!(s.capacity() >= 2000);
+    }
+
+    #[test]
+    fn test_union_intersect_all() {
+        let sets: Vec<BitSet<u8>> = (0..5).map(|i| (i..20).step_by(i + 1).collect()).collect();
+        let mut union = BitSet::default();
+        let mut intersection = sets[0].clone();
+        for set in &sets {
+            union.union_with(set);
+            intersection.intersect_with(set);
+        }
+        assert_eq!(BitSet::union_all(&sets), union);
+        assert_eq!(BitSet::intersect_all(&sets), intersection);
+        assert_eq!(BitSet::intersect_all(&sets).get_ref().len(), 18);
+
+        let mut mixed: Vec<BitSet<u8>> = (0..10).map(|i| [i, 7].iter().cloned().collect()).collect();
+        mixed.push([7, 5000].iter().cloned().collect());
+        let union = BitSet::union_all(&mixed);
+        assert_eq!(union.iter().collect::<Vec<_>>(), vec![0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 5000]);
+        assert_eq!(union.get_ref().len(), 5001);
+        let intersection = BitSet::intersect_all(&mixed);
+        assert_eq!(intersection.iter().collect::<Vec<_>>(), vec![7]);
+        assert_eq!(intersection.get_ref().len(), 8);
+
+        let none: Vec<BitSet> = Vec::new();
+        assert!(BitSet::union_all(&none).is_empty());
+        assert!(BitSet::intersect_all(&none).is_empty());
+    }
+
+    #[test]
+    fn test_threshold() {
+        let sets: Vec<BitSet<u8>> = (0..7).map(|i| (0..50).filter(|x| x % (i + 2) == 0).collect())
+                                          .collect();
+        for k in 1..9 {
+            let expected: BitSet<u8> = (0..50).filter(|&x| {
+                sets.iter().filter(|set| set.contains(x)).count() >= k
+            }).collect();
+            assert_eq!(BitSet::threshold(&sets, k), expected);
+        }
+        assert_eq!(BitSet::threshold(&sets, 1), BitSet::union_all(&sets));
+        assert_eq!(BitSet::threshold(&sets, 7), BitSet::intersect_all(&sets));
+        assert!(BitSet::threshold(&sets[..0], 1).is_empty());
+
+        // Lengths spanning several strips, most sets ending early
+        let sets: Vec<BitSet<u8>> = (0..6).map(|i| (0..(i + 1) * 700).filter(|x| x % (i + 1) == 0).collect())
+                                          .collect();
+        for k in 1..7 {
+            let expected: BitSet<u8> = (0..4200).filter(|&x| {
+                sets.iter().filter(|set| set.contains(x)).count() >= k
+            }).collect();
+            let threshold = BitSet::threshold(&sets, k);
+            assert!(threshold.eq_elements(&expected));
+            assert_eq!(threshold.get_ref().len(), sets[5].get_ref().len());
+        }
+    }
+
+    #[test]
+    fn test_ternary_ops() {
+        let a: BitSet<u8> = (0..30).filter(|x| x % 2 == 0).collect();
+        let b: BitSet<u8> = (0..20).filter(|x| x % 3 == 0).collect();
+        let c: BitSet<u8> = (0..40).filter(|x| x % 5 == 0).collect();
+
+        let mut fused = a.clone();
+        fused.union_with_intersection(&b, &c);
+        let mut expected = b.clone();
+        expected.intersect_with(&c);
+        expected.union_with(&a);
+        assert_eq!(fused, expected);
+
+        let mut fused = a.clone();
+        fused.intersect_with_union(&b, &c);
+        let mut expected = b.clone();
+        expected.union_with(&c);
+        expected.intersect_with(&a);
+        assert_eq!(fused, expected);
+
+        let mut fused = a.clone();
+        fused.difference_with_union(&b, &c);
+        let mut expected = a.clone();
+        expected.difference_with(&b);
+        expected.difference_with(&c);
+        assert_eq!(fused, expected);
+
+        let mut fused = a.clone();
+        fused.union_with_difference(&c, &b);
+        let mut expected = c.clone();
+        expected.difference_with(&b);
+        expected.union_with(&a);
+        assert_eq!(fused, expected);
+    }
+
+    #[test]
+    fn test_into_ops() {
+        let a: BitSet<u8> = (0..30).filter(|x| x % 2 == 0).collect();
+        let b: BitSet<u8> = (0..50).filter(|x| x % 3 == 0).collect();
+        let mut out: BitSet<u8> = (0..200).collect();
+        let ptr = out.get_ref().storage().as_ptr();
+
+        type IntoOp = fn(&BitSet<u8>, &BitSet<u8>, &mut BitSet<u8>);
+        type WithOp = fn(&mut BitSet<u8>, &BitSet<u8>);
+        let ops: [(IntoOp, WithOp); 4] = [
+            (BitSet::union_into, BitSet::union_with),
+            (BitSet::intersection_into, BitSet::intersect_with),
+            (BitSet::difference_into, BitSet::difference_with),
+            (BitSet::symmetric_difference_into, BitSet::symmetric_difference_with),
+        ];
+        for &(into, with) in ops.iter() {
+            for &(x, y) in [(&a, &b), (&b, &a)].iter() {
+                into(x, y, &mut out);
+                let mut expected = x.clone();
+                with(&mut expected, y);
+                assert_eq!(out, expected);
+                assert_eq!(out.get_ref().storage().as_ptr(), ptr);
+            }
+        }
+    }
+
+    #[test]
+    fn test_clone_from() {
+        let mut s: BitSet = (0..1000).collect();
+        let ptr = s.get_ref().storage().as_ptr();
+        let small: BitSet = [3, 70].iter().cloned().collect();
+        s.clone_from(&small);
+        assert_eq!(s, small);
+        assert_eq!(s.get_ref().len(), 71);
+        assert_eq!(s.get_ref().storage().as_ptr(), ptr);
+
+        let large: BitSet = (500..900).collect();
+        s.clone_from(&large);
+        assert_eq!(s, large);
+        assert_eq!(s.get_ref().storage().as_ptr(), ptr);
+    }
+
+    #[test]
+    fn test_intersect_with_truncates() {
+        let mut a: BitSet<u8> = (0..100).collect();
+        let b: BitSet<u8> = (10..20).filter(|x| x % 2 == 1).collect();
+        a.intersect_with(&b);
+        assert_eq!(a, b);
+        assert_eq!(a.get_ref().len(), 20);
+        assert_eq!(a.get_ref().storage().len(), 3);
+
+        // A longer operand leaves the length unchanged
+        let c: BitSet<u8> = (0..50).collect();
+        a.intersect_with(&c);
+        assert_eq!(a.get_ref().len(), 20);
+        a.difference_with(&c);
+        assert!(a.is_empty());
+        assert_eq!(a.get_ref().len(), 20);
+    }
+
+    #[test]
+    fn test_diff() {
+        let old: BitSet<u8> = [0, 7, 8, 20, 30].iter().cloned().collect();
+        let new: BitSet<u8> = [7, 9, 30, 45].iter().cloned().collect();
+        let (added, removed) = BitSet::diff(&old, &new);
+        assert_eq!(added.iter().collect::<Vec<_>>(), [9, 45]);
+        assert_eq!(removed.iter().collect::<Vec<_>>(), [0, 8, 20]);
+        assert_eq!(added.get_ref().len(), 46);
+        assert_eq!(removed.get_ref().len(), 31);
+
+        let (added, removed) = BitSet::diff(&new, &old);
+        assert_eq!(added.iter().collect::<Vec<_>>(), [0, 8, 20]);
+        assert_eq!(removed.iter().collect::<Vec<_>>(), [9, 45]);
+
+        let (added, removed) = BitSet::diff(&old, &old);
+        assert!(added.is_empty() && removed.is_empty());
+    }
+
+    #[test]
+    fn test_ops_with_shorter_operand() {
+        let huge: BitSet<u8> = (0..10_000).filter(|x| x % 3 == 0).collect();
+        let tiny: BitSet<u8> = (0..12).collect();
+        for &(x, y) in [(&huge, &tiny), (&tiny, &huge)].iter() {
+            let mut union = x.clone();
+            union.union_with(y);
+            assert_eq!(union, x.iter().chain(y.iter()).collect::<BitSet<u8>>());
+            let mut xor = x.clone();
+            xor.symmetric_difference_with(y);
+            assert_eq!(xor, x.symmetric_difference(y).collect::<BitSet<u8>>());
+            let mut diff = x.clone();
+            diff.difference_with(y);
+            assert_eq!(diff, x.difference(y).collect::<BitSet<u8>>());
+            let mut inter = x.clone();
+            inter.intersect_with(y);
+            assert_eq!(inter, x.intersection(y).collect::<BitSet<u8>>());
+        }
+    }
+
+    #[test]
+    fn test_truncate() {
+        let mut s: BitSet = (0..100).collect();
+        s.truncate(200);
+        assert_eq!(s.len(), 100);
+        s.truncate(70);
+        assert_eq!(s.len(), 70);
+        assert!(s.contains(69) && !s.contains(70));
+        assert_eq!(s.get_ref().storage().len(), 3);
+        // The cleared bits stay cleared when the set grows again
+        s.insert(95);
+        assert_eq!(s.len(), 71);
+        s.truncate(0);
+        assert!(s.is_empty());
+        assert_eq!(s.get_ref().storage().len(), 0);
+    }
+
+    #[test]
+    fn test_memory_usage() {
+        let mut s: BitSet<u8> = BitSet::default();
+        assert_eq!(s.memory_usage().universe_len, 0);
+        s.insert(20);
+        let usage = s.memory_usage();
+        assert_eq!(usage.universe_len, 21);
+        assert!(usage.heap_bytes >= 3);
+        assert_eq!(usage.heap_bytes, s.capacity() / 8);
+
+        s.reserve_len_exact(1000);
+        assert!(s.memory_usage().heap_bytes >= 125);
+        assert_eq!(s.memory_usage().universe_len, 21);
+    }
+
+    #[test]
+    fn test_lowest_bit() {
+        use super::lowest_bit;
+
+        for i in 0..8 {
+            assert_eq!(lowest_bit(0x80u8 >> (7 - i) | 0x80), i);
+        }
+        for i in 0..64 {
+            assert_eq!(lowest_bit(!0u64 << i), i);
+            assert_eq!(lowest_bit(1usize << (i % usize::BITS as usize)), i % usize::BITS as usize);
+        }
+        assert_eq!(lowest_bit(0x8000u16), 15);
+        assert_eq!(lowest_bit(0x0010_0000u32), 20);
+    }
+
+    #[test]
+    fn test_highest_bit() {
+        use super::highest_bit;
+
+        for i in 0..8 {
+            assert_eq!(highest_bit(1u8 << i | 1), i);
+        }
+        for i in 0..64 {
+            assert_eq!(highest_bit(!0u64 >> i), 63 - i);
+            assert_eq!(highest_bit(1usize << (i % usize::BITS as usize)), i % usize::BITS as usize);
+        }
+        assert_eq!(highest_bit(0x8001u16), 15);
+        assert_eq!(highest_bit(0x0010_0001u32), 20);
+    }
+
+    #[test]
+    fn test_lowest_highest_bit_generic() {
+        use super::{highest_bit, lowest_bit};
+        use primitive::tests::Wrapped;
+
+        for i in 0..32 {
+            for &low in &[0u32, 1, 0x8421] {
+                let block = 1u32 << i | low << i;
+                assert_eq!(lowest_bit(Wrapped(block)), lowest_bit(block));
+                assert_eq!(highest_bit(Wrapped(block)), highest_bit(block));
+                assert_eq!(highest_bit(Wrapped(1 << i | low)), highest_bit(1 << i | low));
+            }
+        }
+    }
+
     #[test]
     fn test_bit_vec_remove() {
         let mut a = BitSet::new();
@@ -1304,6 +3935,12 @@ mod tests {
         assert!(a.insert(1000));
         assert!(a.remove(1000));
         a.shrink_to_fit();
+        assert_eq!(a.get_ref().len(), 32);
+
+        let mut empty = BitSet::new();
+        empty.shrink_to_fit();
+        assert_eq!(empty.get_ref().len(), 0);
+        assert!(empty.insert(3));
     }
 
     #[test]
@@ -1393,7 +4030,8 @@ mod tests {
 mod bench {
     use super::BitSet;
     use bit_vec::BitVec;
-    use rand::{Rng, thread_rng, ThreadRng};
+    use rand::{RngCore, thread_rng};
+    use rand::rngs::ThreadRng;
 
     use test::{Bencher, black_box};
 
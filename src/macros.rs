@@ -0,0 +1,85 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+use core::ops::{Range, RangeInclusive};
+
+use BitSet;
+
+/// Creates a `BitSet` holding the given values and ranges of values.
+///
+/// The storage is allocated once for the largest value, and ranges are
+/// filled a whole block at a time.
+///
+/// # Examples
+///
+/// ```
+/// #[macro_use]
+/// extern crate bit_set;
+///
+/// fn main() {
+///     let s = bitset![1, 5, 9];
+///     assert_eq!(s.iter().collect::<Vec<_>>(), [1, 5, 9]);
+///
+///     let s = bitset![3..6, 42, 7..=8];
+///     assert_eq!(s.iter().collect::<Vec<_>>(), [3, 4, 5, 7, 8, 42]);
+///
+///     assert!(bitset![].is_empty());
+/// }
+/// ```
+#[macro_export]
+macro_rules! bitset {
+    () => { $crate::BitSet::new() };
+    ($($item:expr),+ $(,)*) => {
+        $crate::__from_items(&[$($crate::__BitSetItem::from($item)),+])
+    };
+}
+
+#[doc(hidden)]
+pub enum __BitSetItem {
+    Value(usize),
+    Range(Range<usize>),
+}
+
+impl From<usize> for __BitSetItem {
+    #[inline]
+    fn from(value: usize) -> Self {
+        __BitSetItem::Value(value)
+    }
+}
+
+impl From<Range<usize>> for __BitSetItem {
+    #[inline]
+    fn from(range: Range<usize>) -> Self {
+        __BitSetItem::Range(range)
+    }
+}
+
+impl From<RangeInclusive<usize>> for __BitSetItem {
+    #[inline]
+    fn from(range: RangeInclusive<usize>) -> Self {
+        let (start, end) = range.into_inner();
+        __BitSetItem::Range(start..end.checked_add(1).expect("capacity overflow"))
+    }
+}
+
+#[doc(hidden)]
+pub fn __from_items(items: &[__BitSetItem]) -> BitSet {
+    let len = items.iter().map(|item| match *item {
+        __BitSetItem::Value(value) => value.checked_add(1).expect("capacity overflow"),
+        __BitSetItem::Range(ref range) if range.start < range.end => range.end,
+        __BitSetItem::Range(_) => 0,
+    }).max().unwrap_or(0);
+
+    let mut set = BitSet::with_capacity(len);
+    set.bit_vec.grow(len, false);
+    for item in items {
+        match *item {
+            __BitSetItem::Value(value) => set.bit_vec.set(value, true),
+            __BitSetItem::Range(ref range) => set.fill_range(range.clone()),
+        }
+    }
+    set
+}
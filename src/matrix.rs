@@ -0,0 +1,434 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A matrix of bits stored row by row.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::Cloned;
+use core::slice;
+
+use bit_vec::BitBlock;
+
+use {blocks_for_bits, BitSet, BlockIter};
+
+/// A `rows × cols` matrix of bits, with each row stored as a contiguous
+/// range of blocks.
+///
+/// Each row reads like the `BitSet` of the columns set in it, and whole
+/// rows are combined a block at a time, which suits graph reachability and
+/// relation algebra.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BitMatrix;
+///
+/// // The edges of a small graph, then its reachability in two steps
+/// let mut m = BitMatrix::new(3, 3);
+/// m.insert(0, 1);
+/// m.insert(1, 2);
+/// m.union_rows(0, 1);
+///
+/// assert_eq!(m.row(0).iter().collect::<Vec<_>>(), [1, 2]);
+/// assert_eq!(m.column(2).collect::<Vec<_>>(), [0, 1]);
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct BitMatrix<B: BitBlock = u32> {
+    rows: usize,
+    cols: usize,
+    /// The blocks of each row, padded with zero bits.
+    row_blocks: usize,
+    blocks: Vec<B>,
+}
+
+impl BitMatrix<u32> {
+    /// Creates a new `rows × cols` matrix with all bits cleared.
+    #[inline]
+    pub fn new(rows: usize, cols: usize) -> Self {
+        BitMatrix::with_dimensions(rows, cols)
+    }
+}
+
+impl<B: BitBlock> BitMatrix<B> {
+    /// Creates a new `rows × cols` matrix with all bits cleared, for any
+    /// block type.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitMatrix;
+    ///
+    /// let m = BitMatrix::<u64>::with_dimensions(2, 100);
+    /// assert_eq!((m.rows(), m.cols()), (2, 100));
+    /// ```
+    pub fn with_dimensions(rows: usize, cols: usize) -> Self {
+        let row_blocks = blocks_for_bits::<B>(cols);
+        let len = rows.checked_mul(row_blocks).expect("capacity overflow");
+        BitMatrix { rows, cols, row_blocks, blocks: alloc::vec![B::zero(); len] }
+    }
+
+    /// Returns the number of rows.
+    #[inline]
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Returns the number of columns.
+    #[inline]
+    pub fn cols(&self) -> usize {
+        self.cols
+    }
+
+    fn row_blocks(&self, row: usize) -> &[B] {
+        assert!(row < self.rows, "row {} out of bounds", row);
+        &self.blocks[row * self.row_blocks..(row + 1) * self.row_blocks]
+    }
+
+    fn bit(&self, row: usize, col: usize) -> (usize, B) {
+        assert!(row < self.rows && col < self.cols,
+                "({}, {}) out of bounds of a {}x{} matrix", row, col, self.rows, self.cols);
+        (row * self.row_blocks + col / B::bits(), B::one() << (col % B::bits()))
+    }
+
+    /// Returns `true` if the bit at `(row, col)` is set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `(row, col)` is out of bounds.
+    #[inline]
+    pub fn contains(&self, row: usize, col: usize) -> bool {
+        let (i, mask) = self.bit(row, col);
+        self.blocks[i] & mask != B::zero()
+    }
+
+    /// Sets the bit at `(row, col)`. Returns `true` if it was not already
+    /// set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `(row, col)` is out of bounds.
+    #[inline]
+    pub fn insert(&mut self, row: usize, col: usize) -> bool {
+        let (i, mask) = self.bit(row, col);
+        let old = self.blocks[i];
+        self.blocks[i] = old | mask;
+        old & mask == B::zero()
+    }
+
+    /// Clears the bit at `(row, col)`. Returns `true` if it was set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `(row, col)` is out of bounds.
+    #[inline]
+    pub fn remove(&mut self, row: usize, col: usize) -> bool {
+        let (i, mask) = self.bit(row, col);
+        let old = self.blocks[i];
+        self.blocks[i] = old & !mask;
+        old & mask != B::zero()
+    }
+
+    /// Returns a view of the columns set in `row`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` is out of bounds.
+    #[inline]
+    pub fn row(&self, row: usize) -> Row<'_, B> {
+        Row { blocks: self.row_blocks(row) }
+    }
+
+    /// Iterator over the rows having the bit of column `col` set, in
+    /// ascending order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `col` is out of bounds.
+    pub fn column(&self, col: usize) -> Column<'_, B> {
+        assert!(col < self.cols, "column {} out of bounds", col);
+        Column { matrix: self, col, row: 0 }
+    }
+
+    /// Applies `f` to each block of `dst` and the block of `src` at the same
+    /// column.
+    fn merge_rows<F: Fn(B, B) -> B>(&mut self, dst: usize, src: usize, f: F) {
+        assert!(dst < self.rows && src < self.rows, "row out of bounds");
+        let n = self.row_blocks;
+        for i in 0..n {
+            let w = self.blocks[src * n + i];
+            let d = &mut self.blocks[dst * n + i];
+            *d = f(*d, w);
+        }
+    }
+
+    /// Sets the bits of row `dst` that are set in row `src`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if either row is out of bounds.
+    #[inline]
+    pub fn union_rows(&mut self, dst: usize, src: usize) {
+        self.merge_rows(dst, src, |a, b| a | b);
+    }
+
+    /// Clears the bits of row `dst` that are not set in row `src`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitMatrix;
+    ///
+    /// let mut m = BitMatrix::new(2, 4);
+    /// m.insert(0, 1);
+    /// m.insert(0, 3);
+    /// m.insert(1, 3);
+    /// m.intersect_rows(0, 1);
+    /// assert_eq!(m.row(0).iter().collect::<Vec<_>>(), [3]);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if either row is out of bounds.
+    #[inline]
+    pub fn intersect_rows(&mut self, dst: usize, src: usize) {
+        self.merge_rows(dst, src, |a, b| a & b);
+    }
+
+    /// Sets the bits of row `row` for the elements of `set`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::{BitMatrix, BitSet};
+    ///
+    /// let mut m = BitMatrix::new(2, 10);
+    /// let set: BitSet = [2, 7].iter().cloned().collect();
+    /// m.union_row_with(1, &set);
+    /// assert_eq!(m.row(1).to_bit_set(), set);
+    /// ```
+    ///
+    /// # Panics
+    ///
+    /// Panics if `row` is out of bounds or `set` holds a value not below
+    /// the number of columns.
+    pub fn union_row_with(&mut self, row: usize, set: &BitSet<B>) {
+        assert!(set.iter().all(|col| col < self.cols), "set element out of bounds");
+        assert!(row < self.rows, "row {} out of bounds", row);
+        let start = row * self.row_blocks;
+        for (d, &w) in self.blocks[start..start + self.row_blocks].iter_mut()
+                                                                   .zip(set.as_block_slice()) {
+            *d = *d | w;
+        }
+    }
+
+    /// Returns the transpose of the matrix, whose bit `(col, row)` is the
+    /// bit `(row, col)` of this one.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitMatrix;
+    ///
+    /// let mut m = BitMatrix::new(2, 3);
+    /// m.insert(0, 2);
+    /// m.insert(1, 0);
+    /// let t = m.transpose();
+    /// assert_eq!((t.rows(), t.cols()), (3, 2));
+    /// assert!(t.contains(2, 0) && t.contains(0, 1));
+    /// assert_eq!(t.transpose(), m);
+    /// ```
+    pub fn transpose(&self) -> Self {
+        let mut t = BitMatrix::with_dimensions(self.cols, self.rows);
+        for row in 0..self.rows {
+            for col in self.row(row).iter() {
+                t.insert(col, row);
+            }
+        }
+        t
+    }
+
+    /// Clears all the bits.
+    #[inline]
+    pub fn clear(&mut self) {
+        for block in &mut self.blocks {
+            *block = B::zero();
+        }
+    }
+}
+
+impl<B: BitBlock> fmt::Debug for BitMatrix<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_list().entries((0..self.rows).map(|row| self.row(row))).finish()
+    }
+}
+
+/// A view of a row of a `BitMatrix`, as the set of its columns with a set
+/// bit.
+#[derive(Clone, Copy)]
+pub struct Row<'a, B: 'a> {
+    blocks: &'a [B],
+}
+
+impl<'a, B: BitBlock> Row<'a, B> {
+    /// Returns `true` if the bit of column `col` is set.
+    #[inline]
+    pub fn contains(&self, col: usize) -> bool {
+        self.blocks.get(col / B::bits())
+                   .is_some_and(|&block| block & (B::one() << (col % B::bits())) != B::zero())
+    }
+
+    /// Returns the number of set bits in the row.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.blocks.iter().map(|block| block.count_ones()).sum()
+    }
+
+    /// Returns `true` if no bit is set in the row.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.blocks.iter().all(|&block| block == B::zero())
+    }
+
+    /// Returns the blocks of the row.
+    #[inline]
+    pub fn as_block_slice(&self) -> &'a [B] {
+        self.blocks
+    }
+
+    /// Iterator over the columns with a set bit, in ascending order.
+    #[inline]
+    pub fn iter(&self) -> RowIter<'a, B> {
+        RowIter(BlockIter::from_blocks(self.blocks.iter().cloned()))
+    }
+
+    /// Returns a `BitSet` holding the columns with a set bit.
+    #[inline]
+    pub fn to_bit_set(&self) -> BitSet<B> {
+        BitSet::from_blocks(self.blocks.to_vec())
+    }
+}
+
+impl<'a, B: BitBlock> IntoIterator for Row<'a, B> {
+    type Item = usize;
+    type IntoIter = RowIter<'a, B>;
+
+    #[inline]
+    fn into_iter(self) -> RowIter<'a, B> {
+        self.iter()
+    }
+}
+
+impl<'a, B: BitBlock> fmt::Debug for Row<'a, B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// An iterator over the columns of a `Row`.
+#[derive(Clone)]
+pub struct RowIter<'a, B: 'a>(BlockIter<Cloned<slice::Iter<'a, B>>, B>);
+
+impl<'a, B: BitBlock> Iterator for RowIter<'a, B> {
+    type Item = usize;
+
+    #[inline] fn next(&mut self) -> Option<usize> { self.0.next() }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+    #[inline] fn fold<Acc, F>(self, init: Acc, f: F) -> Acc where F: FnMut(Acc, usize) -> Acc { self.0.fold(init, f) }
+}
+
+/// An iterator over the rows of a `BitMatrix` with a bit set in a column.
+#[derive(Clone)]
+pub struct Column<'a, B: 'a + BitBlock> {
+    matrix: &'a BitMatrix<B>,
+    col: usize,
+    row: usize,
+}
+
+impl<'a, B: BitBlock> Iterator for Column<'a, B> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        while self.row < self.matrix.rows {
+            let row = self.row;
+            self.row += 1;
+            if self.matrix.contains(row, self.col) {
+                return Some(row);
+            }
+        }
+        None
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, Some(self.matrix.rows - self.row))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::BitMatrix;
+    use BitSet;
+
+    #[test]
+    fn test_bit_matrix() {
+        let mut m = BitMatrix::<u8>::with_dimensions(4, 11);
+        assert!(m.insert(0, 10));
+        assert!(!m.insert(0, 10));
+        assert!(m.insert(2, 0));
+        assert!(m.insert(2, 9));
+        assert!(m.insert(3, 9));
+        assert!(m.contains(2, 9) && !m.contains(1, 9));
+        assert_eq!(m.column(9).collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(m.row(2).len(), 2);
+        assert!(m.row(1).is_empty());
+        assert_eq!(format!("{:?}", m), "[{10}, {}, {0, 9}, {9}]");
+
+        m.union_rows(1, 2);
+        m.union_rows(1, 0);
+        assert_eq!(m.row(1).iter().collect::<Vec<_>>(), [0, 9, 10]);
+        m.intersect_rows(1, 3);
+        assert_eq!(m.row(1).to_bit_set(), [9].iter().cloned().collect::<BitSet<u8>>());
+        assert!(m.remove(1, 9));
+        assert!(!m.remove(1, 9));
+
+        let t = m.transpose();
+        assert_eq!((t.rows(), t.cols()), (11, 4));
+        assert_eq!(t.row(9).iter().collect::<Vec<_>>(), [2, 3]);
+        assert_eq!(t.transpose(), m);
+
+        m.clear();
+        assert!((0..4).all(|row| m.row(row).is_empty()));
+    }
+
+    #[test]
+    fn test_bit_matrix_transitive_closure() {
+        // A chain 0 -> 1 -> ... -> 9, closed with Warshall's algorithm
+        let n = 10;
+        let mut m = BitMatrix::new(n, n);
+        for i in 0..n - 1 {
+            m.insert(i, i + 1);
+        }
+        for k in 0..n {
+            for i in 0..n {
+                if m.contains(i, k) {
+                    m.union_rows(i, k);
+                }
+            }
+        }
+        for i in 0..n {
+            assert_eq!(m.row(i).iter().collect::<Vec<_>>(), (i + 1..n).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_bit_matrix_out_of_bounds() {
+        BitMatrix::new(2, 2).insert(0, 2);
+    }
+}
@@ -0,0 +1,151 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! An equivalence oracle checking `BitSet` against `BTreeSet<usize>`.
+//!
+//! [`check_ops`](fn.check_ops.html) decodes a byte string into a sequence
+//! of operations, applies each to a pair of `BitSet`s and to a pair of
+//! `BTreeSet`s, and cross-checks every query after each step. It is driven
+//! by random byte strings in the tests below and by the `ops` fuzz target
+//! under `fuzz/`, which builds the crate with `--cfg fuzzing`.
+
+use alloc::collections::BTreeSet;
+use alloc::vec::Vec;
+
+use {blocks_for_bits, BitSet};
+
+/// The values are kept below this, to span a few blocks.
+const UNIVERSE: usize = 300;
+
+/// Applies the operations encoded by `data` to both implementations,
+/// panicking at the first difference.
+pub fn check_ops(data: &[u8]) {
+    let mut sets = [BitSet::new(), BitSet::new()];
+    let mut models = [BTreeSet::new(), BTreeSet::new()];
+    let mut bytes = data.iter().cloned();
+    while let (Some(op), Some(arg)) = (bytes.next(), bytes.next()) {
+        // The low bit of the opcode picks the operand the step applies to
+        let (i, j) = if op & 1 == 0 { (0, 1) } else { (1, 0) };
+        let value = arg as usize * UNIVERSE / 256;
+        apply(op >> 1, value, i, j, &mut sets, &mut models);
+        check(&sets, &models, value);
+    }
+}
+
+fn apply(op: u8, value: usize, i: usize, j: usize,
+         sets: &mut [BitSet; 2], models: &mut [BTreeSet<usize>; 2]) {
+    let other = sets[j].clone();
+    let other_model = models[j].clone();
+    let (set, model) = (&mut sets[i], &mut models[i]);
+    match op % 14 {
+        0 | 1 => assert_eq!(set.insert(value), model.insert(value)),
+        2 => assert_eq!(set.remove(value), model.remove(&value)),
+        3 => {
+            set.union_with(&other);
+            model.extend(other_model);
+        }
+        4 => {
+            set.intersect_with(&other);
+            model.retain(|v| other_model.contains(v));
+        }
+        5 => {
+            set.difference_with(&other);
+            model.retain(|v| !other_model.contains(v));
+        }
+        6 => {
+            set.symmetric_difference_with(&other);
+            *model = model.symmetric_difference(&other_model).cloned().collect();
+        }
+        7 => {
+            set.truncate(value);
+            model.retain(|&v| v < value);
+        }
+        8 => {
+            let mut out = BitSet::new();
+            set.difference_into(&other, &mut out);
+            *set = out;
+            model.retain(|v| !other_model.contains(v));
+        }
+        9 => {
+            let shift = value % 70;
+            set.shift_up(shift);
+            *model = model.iter().map(|v| v + shift).collect();
+        }
+        10 => {
+            let shift = value % 70;
+            set.shift_down(shift);
+            *model = model.iter().filter(|&&v| v >= shift).map(|v| v - shift).collect();
+        }
+        11 => {
+            let mut out = BitSet::new();
+            set.symmetric_difference_into(&other, &mut out);
+            *set = out;
+            *model = model.symmetric_difference(&other_model).cloned().collect();
+        }
+        12 => set.shrink_to_fit(),
+        _ => {
+            set.clear();
+            model.clear();
+        }
+    }
+}
+
+fn check(sets: &[BitSet; 2], models: &[BTreeSet<usize>; 2], value: usize) {
+    let collect = |model: &BTreeSet<usize>| model.iter().cloned().collect::<Vec<_>>();
+    for (set, model) in sets.iter().zip(models) {
+        assert_eq!(set.iter().collect::<Vec<_>>(), collect(model));
+        assert_eq!(set.len(), model.len());
+        assert_eq!(set.is_empty(), model.is_empty());
+        assert_eq!(set.contains(value), model.contains(&value));
+
+        // The storage matches the length, and the bits past it are clear
+        let (blocks, nbits) = (set.as_block_slice(), set.get_ref().len());
+        assert_eq!(blocks.len(), blocks_for_bits::<u32>(nbits));
+        if nbits % 32 != 0 {
+            assert_eq!(blocks[blocks.len() - 1] >> (nbits % 32), 0);
+        }
+    }
+
+    let (a, b) = (&sets[0], &sets[1]);
+    let (ma, mb) = (&models[0], &models[1]);
+    assert_eq!(a.union(b).collect::<Vec<_>>(), ma.union(mb).cloned().collect::<Vec<_>>());
+    assert_eq!(a.intersection(b).collect::<Vec<_>>(),
+               ma.intersection(mb).cloned().collect::<Vec<_>>());
+    assert_eq!(a.difference(b).collect::<Vec<_>>(),
+               ma.difference(mb).cloned().collect::<Vec<_>>());
+    assert_eq!(a.symmetric_difference(b).collect::<Vec<_>>(),
+               ma.symmetric_difference(mb).cloned().collect::<Vec<_>>());
+    assert_eq!(a.is_subset(b), ma.is_subset(mb));
+    assert_eq!(a.is_superset(b), ma.is_superset(mb));
+    assert_eq!(a.is_disjoint(b), ma.is_disjoint(mb));
+    assert_eq!(a == b, ma == mb);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use rand::rngs::StdRng;
+    use rand::{RngCore, SeedableRng};
+
+    use super::check_ops;
+
+    #[test]
+    fn test_ops_oracle() {
+        let mut rng = StdRng::seed_from_u64(0);
+        for len in 0..300 {
+            let mut data = vec![0u8; len * 2];
+            rng.fill_bytes(&mut data);
+            check_ops(&data);
+        }
+    }
+
+    #[test]
+    fn test_ops_oracle_every_op() {
+        let data: Vec<u8> = (0..=255u8).flat_map(|op| vec![op, op.wrapping_mul(37)]).collect();
+        check_ops(&data);
+    }
+}
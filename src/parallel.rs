@@ -0,0 +1,169 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Building and combining sets on the rayon thread pool.
+
+use alloc::vec::Vec;
+
+use bit_vec::BitBlock;
+use rayon::prelude::*;
+
+use {blocks_for_bits, kernels, BitSet};
+
+/// Size of the strips of blocks each task combines, sized to stay in the L1
+/// cache while every operand is folded into it.
+const STRIP_BYTES: usize = 16 * 1024;
+
+impl<B: BitBlock + Send + Sync> BitSet<B> {
+    /// Creates a new `BitSet` holding the values of `values`, all below
+    /// `universe`, in parallel.
+    ///
+    /// The values are copied and sorted by the strip of blocks holding them,
+    /// then each strip of the result is filled by one task from its own run
+    /// of values, so the bitmap is allocated once and the tasks write to
+    /// disjoint parts of it. The values may come in any order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a value is not below `universe`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let values: Vec<usize> = (0..10_000).map(|i| i * 7 % 10_000).collect();
+    /// let s: BitSet = BitSet::from_par_slice(&values, 10_000);
+    /// assert_eq!(s.len(), 10_000);
+    /// assert_eq!(s.get_ref().len(), 10_000);
+    /// ```
+    pub fn from_par_slice(values: &[usize], universe: usize) -> Self {
+        if let Some(&value) = values.par_iter().find_any(|&&value| value >= universe) {
+            panic!("value {} is not below {}", value, universe);
+        }
+        let strip = STRIP_BYTES / B::bytes();
+        let strip_bits = strip * B::bits();
+        let mut values = values.to_vec();
+        values.par_sort_unstable_by_key(|&value| value / strip_bits);
+        let mut blocks = alloc::vec![B::zero(); blocks_for_bits::<B>(universe)];
+        blocks.par_chunks_mut(strip).enumerate().for_each(|(k, blocks)| {
+            let start = values.partition_point(|&value| value / strip_bits < k);
+            let end = values.partition_point(|&value| value / strip_bits <= k);
+            for &value in &values[start..end] {
+                kernels::insert(blocks, value - k * strip_bits);
+            }
+        });
+        let mut set = BitSet::from_blocks(blocks);
+        set.bit_vec.truncate(universe);
+        set
+    }
+
+    /// Returns a set of `nbits` bits, starting from `init` blocks and
+    /// applying `op` with the blocks of each of `sets` they have. The blocks
+    /// are split in strips combined on different threads, each visiting only
+    /// the operands reaching it.
+    fn par_merge_all(sets: &[&Self], nbits: usize, init: B, op: kernels::Op) -> Self {
+        let strip = STRIP_BYTES / B::bytes();
+        let mut blocks = alloc::vec![init; blocks_for_bits::<B>(nbits)];
+        blocks.par_chunks_mut(strip).enumerate().for_each(|(k, blocks)| {
+            for set in sets {
+                if let Some(other) = set.bit_vec.storage().get(k * strip..) {
+                    kernels::apply(blocks, other, op);
+                }
+            }
+        });
+        let mut set = BitSet::from_blocks(blocks);
+        set.bit_vec.truncate(nbits);
+        set
+    }
+
+    /// Returns the union of all the given sets, like
+    /// [`union_all`](#method.union_all), splitting the blocks of the result
+    /// in strips computed in parallel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let shards: Vec<BitSet> = (0..100).map(|i| BitSet::from_range(i * 1000..i * 1000 + 10)).collect();
+    /// let all = BitSet::par_union_all(&shards);
+    /// assert_eq!(all.len(), 1000);
+    /// assert_eq!(all, BitSet::union_all(&shards));
+    /// ```
+    pub fn par_union_all<'a, I>(sets: I) -> Self
+        where I: IntoIterator<Item = &'a Self>, B: 'a
+    {
+        let sets: Vec<&Self> = sets.into_iter().collect();
+        let nbits = sets.iter().map(|set| set.bit_vec.len()).max().unwrap_or(0);
+        Self::par_merge_all(&sets, nbits, B::zero(), kernels::Op::Or)
+    }
+
+    /// Returns the intersection of all the given sets, or an empty set if
+    /// there are none, like [`intersect_all`](#method.intersect_all),
+    /// splitting the blocks of the result in strips computed in parallel.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let sets: Vec<BitSet> = (0..50).map(|i| BitSet::from_range(i..100_000)).collect();
+    /// let common = BitSet::par_intersect_all(&sets);
+    /// assert_eq!(common, BitSet::<u32>::from_range(49..100_000));
+    /// ```
+    pub fn par_intersect_all<'a, I>(sets: I) -> Self
+        where I: IntoIterator<Item = &'a Self>, B: 'a
+    {
+        let sets: Vec<&Self> = sets.into_iter().collect();
+        let nbits = sets.iter().map(|set| set.bit_vec.len()).min().unwrap_or(0);
+        Self::par_merge_all(&sets, nbits, !B::zero(), kernels::Op::And)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use BitSet;
+
+    #[test]
+    fn test_from_par_slice() {
+        let values: Vec<usize> = (0..100_000).map(|i| i * 7919 % 65_537).collect();
+        let s: BitSet<u8> = BitSet::from_par_slice(&values, 70_000);
+        let expected: BitSet<u8> = values.iter().collect();
+        assert_eq!(s, expected);
+        assert_eq!(s.get_ref().len(), 70_000);
+
+        // Values spread over many strips, in descending order
+        let values: Vec<usize> = (0..50_000).rev().map(|i| i * 37).collect();
+        let s: BitSet<u16> = BitSet::from_par_slice(&values, 2_000_000);
+        assert!(s.iter().eq((0..50_000).map(|i| i * 37)));
+        assert_eq!(s.get_ref().len(), 2_000_000);
+
+        let empty: BitSet<u64> = BitSet::from_par_slice(&[], 100);
+        assert!(empty.is_empty() && empty.get_ref().len() == 100);
+    }
+
+    #[test]
+    fn test_par_union_intersect_all() {
+        let sets: Vec<BitSet<u8>> = (1..20)
+            .map(|k| (0..100_000).filter(|x| x % k == 0 || x % 7 == 3).collect())
+            .collect();
+        assert_eq!(BitSet::par_union_all(&sets), BitSet::union_all(&sets));
+        let common = BitSet::par_intersect_all(&sets);
+        assert_eq!(common, BitSet::intersect_all(&sets));
+        assert_eq!(common.get_ref().len(), 99_999);
+        assert!(BitSet::<u8>::par_union_all(&[]).get_ref().is_empty());
+        assert!(BitSet::<u8>::par_intersect_all(&[]).get_ref().is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_from_par_slice_out_of_range() {
+        let _: BitSet = BitSet::from_par_slice(&[1, 10], 10);
+    }
+}
@@ -0,0 +1,159 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Parsing sets from strings.
+
+use core::fmt;
+use core::num::ParseIntError;
+use core::str::FromStr;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use bit_vec::BitBlock;
+
+use BitSet;
+
+/// An error returned when parsing a `BitSet` from a string fails.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseBitSetError {
+    kind: ParseErrorKind,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum ParseErrorKind {
+    UnbalancedBraces,
+    Empty,
+    InvalidValue(ParseIntError),
+    InvalidRange,
+}
+
+impl ParseBitSetError {
+    fn new(kind: ParseErrorKind) -> Self {
+        ParseBitSetError { kind }
+    }
+}
+
+impl fmt::Display for ParseBitSetError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ParseErrorKind::UnbalancedBraces => fmt.write_str("unbalanced braces"),
+            ParseErrorKind::Empty => fmt.write_str("empty element"),
+            ParseErrorKind::InvalidValue(ref err) => write!(fmt, "invalid element: {}", err),
+            ParseErrorKind::InvalidRange => fmt.write_str("range start is greater than its end"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for ParseBitSetError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self.kind {
+            ParseErrorKind::InvalidValue(ref err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+fn parse_value(s: &str) -> Result<usize, ParseBitSetError> {
+    let s = s.trim();
+    if s.is_empty() {
+        return Err(ParseBitSetError::new(ParseErrorKind::Empty));
+    }
+    s.parse().map_err(|err| ParseBitSetError::new(ParseErrorKind::InvalidValue(err)))
+}
+
+impl<B: BitBlock> FromStr for BitSet<B> {
+    type Err = ParseBitSetError;
+
+    /// Parses a comma-separated list of values and inclusive ranges of
+    /// values, optionally surrounded by braces, such as `"{1, 3, 7}"` or
+    /// `"1,3,7-12"`. Whitespace around elements is ignored, and the empty
+    /// string or `"{}"` parse to an empty set.
+    ///
+    /// This accepts the output of the `Debug` implementation.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = "1,3,7-9".parse().unwrap();
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), [1, 3, 7, 8, 9]);
+    ///
+    /// let t: BitSet = format!("{:?}", s).parse().unwrap();
+    /// assert_eq!(s, t);
+    ///
+    /// assert!("1, x".parse::<BitSet>().is_err());
+    /// ```
+    fn from_str(s: &str) -> Result<Self, ParseBitSetError> {
+        let s = s.trim();
+        let s = match (s.starts_with('{'), s.ends_with('}')) {
+            (true, true) if s.len() >= 2 => &s[1..s.len() - 1],
+            (false, false) => s,
+            _ => return Err(ParseBitSetError::new(ParseErrorKind::UnbalancedBraces)),
+        };
+
+        let mut set = BitSet::default();
+        if s.trim().is_empty() {
+            return Ok(set);
+        }
+        for item in s.split(',') {
+            match item.find('-') {
+                Some(pos) => {
+                    let start = parse_value(&item[..pos])?;
+                    let end = parse_value(&item[pos + 1..])?;
+                    if start > end {
+                        return Err(ParseBitSetError::new(ParseErrorKind::InvalidRange));
+                    }
+                    let end = end.checked_add(1).expect("capacity overflow");
+                    set.fill_range(start..end);
+                }
+                None => {
+                    set.insert(parse_value(item)?);
+                }
+            }
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::string::ToString;
+    use std::vec::Vec;
+
+    use BitSet;
+
+    fn parse(s: &str) -> Vec<usize> {
+        s.parse::<BitSet>().unwrap().iter().collect()
+    }
+
+    #[test]
+    fn test_bit_set_from_str() {
+        assert_eq!(parse(""), []);
+        assert_eq!(parse(" {} "), []);
+        assert_eq!(parse("{ }"), []);
+        assert_eq!(parse("5"), [5]);
+        assert_eq!(parse("{1, 3, 7}"), [1, 3, 7]);
+        assert_eq!(parse("1,3,7-12"), [1, 3, 7, 8, 9, 10, 11, 12]);
+        assert_eq!(parse(" 40 - 41 , 3-3,2 "), [2, 3, 40, 41]);
+        assert_eq!(parse("{1, 1, 0-1}"), [0, 1]);
+    }
+
+    #[test]
+    fn test_bit_set_from_str_errors() {
+        let err = |s: &str| s.parse::<BitSet>().unwrap_err().to_string();
+        assert_eq!(err("{1, 2"), "unbalanced braces");
+        assert_eq!(err("}"), "unbalanced braces");
+        assert_eq!(err("1,,2"), "empty element");
+        assert_eq!(err("1,"), "empty element");
+        assert_eq!(err("-4"), "empty element");
+        assert_eq!(err("3-"), "empty element");
+        assert_eq!(err("a"), "invalid element: invalid digit found in string");
+        assert_eq!(err("1-2-3"), "invalid element: invalid digit found in string");
+        assert_eq!(err("5-4"), "range start is greater than its end");
+    }
+}
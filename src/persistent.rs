@@ -0,0 +1,626 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! An immutable set whose updates share storage with the original.
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cmp;
+use core::fmt;
+use core::iter::{self, FromIterator};
+use core::slice;
+
+use bit_vec::BitBlock;
+
+use {kernels, BitSet, BlockIter};
+
+/// The number of values covered by each chunk of blocks.
+const CHUNK_BITS: usize = 1024;
+
+/// The number of children of each branch of the tree, as a power of two.
+const BRANCH_SHIFT: usize = 5;
+const BRANCH: usize = 1 << BRANCH_SHIFT;
+
+/// A node of the tree of chunks. A leaf is a chunk, and a branch at height
+/// `h` covers `BRANCH^h` chunks, split evenly between its `BRANCH`
+/// children.
+enum Node<B> {
+    Leaf(Vec<B>),
+    Branch(Vec<Child<B>>),
+}
+
+/// A subtree, absent if it holds no value.
+type Child<B> = Option<Arc<Node<B>>>;
+
+fn chunk_len<B: BitBlock>() -> usize {
+    CHUNK_BITS / B::bits()
+}
+
+fn zero_chunk<B: BitBlock>() -> Vec<B> {
+    iter::repeat_n(B::zero(), chunk_len::<B>()).collect()
+}
+
+/// Returns `true` if a tree of height `height` covers the chunk at `index`.
+fn covers(height: usize, index: usize) -> bool {
+    index.checked_shr((BRANCH_SHIFT * height) as u32).unwrap_or(0) == 0
+}
+
+/// Returns the index among the children of a branch at `height` of the one
+/// covering the chunk at `index`.
+fn digit(index: usize, height: usize) -> usize {
+    (index >> (BRANCH_SHIFT * (height - 1))) & (BRANCH - 1)
+}
+
+impl<B: BitBlock> Node<B> {
+    fn leaf(blocks: Vec<B>) -> Child<B> {
+        if kernels::is_empty(&blocks) {
+            None
+        } else {
+            Some(Arc::new(Node::Leaf(blocks)))
+        }
+    }
+
+    fn branch(children: Vec<Child<B>>) -> Child<B> {
+        if children.iter().all(Option::is_none) {
+            None
+        } else {
+            Some(Arc::new(Node::Branch(children)))
+        }
+    }
+
+    fn blocks(&self) -> &[B] {
+        match *self {
+            Node::Leaf(ref blocks) => blocks,
+            Node::Branch(_) => unreachable!("not a leaf"),
+        }
+    }
+
+    fn children(&self) -> &[Child<B>] {
+        match *self {
+            Node::Branch(ref children) => children,
+            Node::Leaf(_) => unreachable!("not a branch"),
+        }
+    }
+}
+
+/// Returns `true` if both subtrees hold the same values, without looking
+/// into the subtrees they share.
+fn child_eq<B: BitBlock>(a: &Child<B>, b: &Child<B>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => Arc::ptr_eq(a, b) || match (&**a, &**b) {
+            (Node::Leaf(x), Node::Leaf(y)) => x == y,
+            (Node::Branch(x), Node::Branch(y)) => x.iter().zip(y).all(|(a, b)| child_eq(a, b)),
+            _ => false,
+        },
+        (None, None) => true,
+        _ => false,
+    }
+}
+
+/// Returns `true` if both lists hold the same subtrees at the same
+/// addresses.
+fn same_children<B>(a: &[Child<B>], b: &[Child<B>]) -> bool {
+    a.iter().zip(b).all(|pair| match pair {
+        (Some(a), Some(b)) => Arc::ptr_eq(a, b),
+        (None, None) => true,
+        _ => false,
+    })
+}
+
+/// An immutable set, where updates return a new set sharing every unchanged
+/// part of the storage with the original.
+///
+/// The blocks are split in chunks covering 1024 values each, held by a tree
+/// of branches of 32 children. Inserting or removing a value copies a single
+/// chunk and the branches on the path to it, and set operations reuse the
+/// subtrees of their operands whenever the result is equal to them, without
+/// looking into the subtrees both operands share. Chunks and subtrees holding
+/// no value aren't stored at all. This makes it cheap to keep many versions
+/// of a large set alive at once.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::PersistentBitSet;
+///
+/// let v1 = PersistentBitSet::new().insert(1).insert(5000);
+/// let v2 = v1.insert(2);
+/// let v3 = v2.remove(5000);
+///
+/// assert_eq!(v1.iter().collect::<Vec<_>>(), [1, 5000]);
+/// assert_eq!(v2.iter().collect::<Vec<_>>(), [1, 2, 5000]);
+/// assert_eq!(v3.iter().collect::<Vec<_>>(), [1, 2]);
+/// ```
+pub struct PersistentBitSet<B = u32> {
+    root: Child<B>,
+    /// The height of the root, kept as low as possible so equal sets have
+    /// trees of the same shape.
+    height: usize,
+}
+
+impl<B: BitBlock> Clone for PersistentBitSet<B> {
+    #[inline]
+    fn clone(&self) -> Self {
+        PersistentBitSet { root: self.root.clone(), height: self.height }
+    }
+}
+
+impl<B: BitBlock> Default for PersistentBitSet<B> {
+    #[inline]
+    fn default() -> Self {
+        PersistentBitSet { root: None, height: 0 }
+    }
+}
+
+impl PersistentBitSet<u32> {
+    /// Creates a new empty `PersistentBitSet`.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: BitBlock> PersistentBitSet<B> {
+    /// Returns the set with the tree at `root`, lowered while its root only
+    /// has a first child.
+    fn from_root(mut root: Child<B>, mut height: usize) -> Self {
+        loop {
+            let child = match root {
+                Some(ref node) if height > 0 && node.children()[1..].iter().all(Option::is_none) => {
+                    node.children()[0].clone()
+                }
+                _ => break,
+            };
+            root = child;
+            height -= 1;
+        }
+        if root.is_none() {
+            height = 0;
+        }
+        PersistentBitSet { root, height }
+    }
+
+    /// Returns the root of the tree, raised to `height`.
+    fn raised(&self, height: usize) -> Child<B> {
+        let mut root = self.root.clone();
+        for _ in self.height..height {
+            let mut children = alloc::vec![None; BRANCH];
+            children[0] = root;
+            root = Node::branch(children);
+        }
+        root
+    }
+
+    fn chunk(&self, index: usize) -> Option<&[B]> {
+        if !covers(self.height, index) {
+            return None;
+        }
+        let mut node = self.root.as_ref()?;
+        for height in (1..=self.height).rev() {
+            node = node.children()[digit(index, height)].as_ref()?;
+        }
+        Some(node.blocks())
+    }
+
+    /// Returns the subtree of height `height` at `node` where the chunk at
+    /// `index` is replaced with `leaf`, copying only the branches on the
+    /// path to it.
+    fn replace(node: Option<&Arc<Node<B>>>, height: usize, index: usize, leaf: Child<B>) -> Child<B> {
+        if height == 0 {
+            return leaf;
+        }
+        let mut children = match node {
+            Some(node) => node.children().to_vec(),
+            None => alloc::vec![None; BRANCH],
+        };
+        let digit = digit(index, height);
+        let child = Self::replace(children[digit].as_ref(), height - 1, index, leaf);
+        children[digit] = child;
+        Node::branch(children)
+    }
+
+    /// Returns a set where the chunk at `index` is replaced with `blocks`.
+    fn with_chunk(&self, index: usize, blocks: Vec<B>) -> Self {
+        let mut height = self.height;
+        while !covers(height, index) {
+            height += 1;
+        }
+        let root = self.raised(height);
+        PersistentBitSet::from_root(Self::replace(root.as_ref(), height, index, Node::leaf(blocks)), height)
+    }
+
+    /// Returns a copy of the blocks of the chunk at `index`.
+    fn chunk_blocks(&self, index: usize) -> Vec<B> {
+        match self.chunk(index) {
+            Some(blocks) => blocks.to_vec(),
+            None => zero_chunk(),
+        }
+    }
+
+    fn leaves(&self) -> Leaves<'_, B> {
+        Leaves { stack: alloc::vec![(slice::from_ref(&self.root).iter(), 0, self.height)] }
+    }
+
+    fn blocks(&self) -> ChunkBlocks<'_, B> {
+        ChunkBlocks { leaves: self.leaves(), current: [].iter(), zeros: 0, next_chunk: 0 }
+    }
+
+    /// Returns `true` if this set contains the specified integer.
+    pub fn contains(&self, value: usize) -> bool {
+        let bit = value % CHUNK_BITS;
+        self.chunk(value / CHUNK_BITS).is_some_and(|chunk| {
+            (chunk[bit / B::bits()] >> (bit % B::bits())) & B::one() != B::zero()
+        })
+    }
+
+    /// Returns a set with `value` added. If `value` is already present, the
+    /// set itself is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::PersistentBitSet;
+    ///
+    /// let a = PersistentBitSet::new();
+    /// let b = a.insert(3);
+    /// assert!(!a.contains(3));
+    /// assert!(b.contains(3));
+    /// ```
+    pub fn insert(&self, value: usize) -> Self {
+        if self.contains(value) {
+            return self.clone();
+        }
+        let (index, bit) = (value / CHUNK_BITS, value % CHUNK_BITS);
+        let mut blocks = self.chunk_blocks(index);
+        blocks[bit / B::bits()] = blocks[bit / B::bits()] | (B::one() << (bit % B::bits()));
+        self.with_chunk(index, blocks)
+    }
+
+    /// Returns a set with `value` removed. If `value` isn't present, the set
+    /// itself is returned.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::PersistentBitSet;
+    ///
+    /// let a = PersistentBitSet::new().insert(3);
+    /// let b = a.remove(3);
+    /// assert!(a.contains(3));
+    /// assert!(b.is_empty());
+    /// ```
+    pub fn remove(&self, value: usize) -> Self {
+        if !self.contains(value) {
+            return self.clone();
+        }
+        let (index, bit) = (value / CHUNK_BITS, value % CHUNK_BITS);
+        let mut blocks = self.chunk_blocks(index);
+        blocks[bit / B::bits()] = blocks[bit / B::bits()] & !(B::one() << (bit % B::bits()));
+        self.with_chunk(index, blocks)
+    }
+
+    /// Combines the subtrees `a` and `b` of height `height` with `op`,
+    /// reusing either of them, or any of their subtrees, when the result is
+    /// equal to it.
+    fn merge_nodes(a: Option<&Arc<Node<B>>>, b: Option<&Arc<Node<B>>>, height: usize, op: kernels::Op)
+        -> Child<B>
+    {
+        use kernels::Op;
+
+        let (a, b) = match (a, b) {
+            (Some(a), Some(b)) => (a, b),
+            (a, None) => return if op == Op::And { None } else { a.cloned() },
+            (None, b) => return if op == Op::Or || op == Op::Xor { b.cloned() } else { None },
+        };
+        if Arc::ptr_eq(a, b) {
+            return if op == Op::Or || op == Op::And { Some(a.clone()) } else { None };
+        }
+        if height > 0 {
+            let children: Vec<Child<B>> = a.children().iter().zip(b.children())
+                .map(|(x, y)| Self::merge_nodes(x.as_ref(), y.as_ref(), height - 1, op))
+                .collect();
+            return if same_children(&children, a.children()) {
+                Some(a.clone())
+            } else if same_children(&children, b.children()) {
+                Some(b.clone())
+            } else {
+                Node::branch(children)
+            };
+        }
+        let (x, y) = (a.blocks(), b.blocks());
+        match op {
+            Op::Or | Op::And if kernels::is_subset(x, y) => {
+                return Some(if op == Op::Or { b.clone() } else { a.clone() });
+            }
+            Op::Or | Op::And if kernels::is_subset(y, x) => {
+                return Some(if op == Op::Or { a.clone() } else { b.clone() });
+            }
+            Op::AndNot if kernels::is_disjoint(x, y) => return Some(a.clone()),
+            _ => {}
+        }
+        let mut blocks = x.to_vec();
+        kernels::apply(&mut blocks, y, op);
+        Node::leaf(blocks)
+    }
+
+    /// Combines both sets with `op`, visiting neither the subtrees they
+    /// share nor those only one of them has.
+    fn merge(&self, other: &Self, op: kernels::Op) -> Self {
+        let height = cmp::max(self.height, other.height);
+        let (a, b) = (self.raised(height), other.raised(height));
+        PersistentBitSet::from_root(Self::merge_nodes(a.as_ref(), b.as_ref(), height, op), height)
+    }
+
+    /// Returns the union of both sets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::PersistentBitSet;
+    ///
+    /// let a = PersistentBitSet::new().insert(1);
+    /// let b = PersistentBitSet::new().insert(2000);
+    /// assert_eq!(a.union(&b).iter().collect::<Vec<_>>(), [1, 2000]);
+    /// ```
+    pub fn union(&self, other: &Self) -> Self {
+        self.merge(other, kernels::Op::Or)
+    }
+
+    /// Returns the intersection of both sets.
+    pub fn intersection(&self, other: &Self) -> Self {
+        self.merge(other, kernels::Op::And)
+    }
+
+    /// Returns the values of `self` that aren't in `other`.
+    pub fn difference(&self, other: &Self) -> Self {
+        self.merge(other, kernels::Op::AndNot)
+    }
+
+    /// Returns the number of values in the set.
+    pub fn len(&self) -> usize {
+        self.leaves().map(|(_, leaf)| kernels::count_ones(leaf.blocks())).sum()
+    }
+
+    /// Returns `true` if the set holds no value.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        // Empty subtrees are never stored
+        self.root.is_none()
+    }
+
+    /// Returns the number of chunks stored by both sets at the same address.
+    pub fn shared_chunks(&self, other: &Self) -> usize {
+        let (mut a, mut b) = (self.leaves().peekable(), other.leaves().peekable());
+        let mut shared = 0;
+        while let (Some(&(i, x)), Some(&(j, y))) = (a.peek(), b.peek()) {
+            if i <= j {
+                a.next();
+            }
+            if j <= i {
+                b.next();
+            }
+            if i == j && Arc::ptr_eq(x, y) {
+                shared += 1;
+            }
+        }
+        shared
+    }
+
+    /// Iterator over each value in the set, in ascending order.
+    pub fn iter(&self) -> PersistentIter<'_, B> {
+        PersistentIter(BlockIter::from_blocks(self.blocks()))
+    }
+
+    /// Returns a `BitSet` holding the values of this set.
+    pub fn to_bit_set(&self) -> BitSet<B> {
+        BitSet::from_blocks(self.blocks().collect())
+    }
+}
+
+impl<'a, B: BitBlock> From<&'a BitSet<B>> for PersistentBitSet<B> {
+    fn from(set: &'a BitSet<B>) -> Self {
+        let mut level: Vec<Child<B>> = set.as_block_slice().chunks(chunk_len::<B>()).map(|blocks| {
+            let mut blocks = blocks.to_vec();
+            blocks.resize(chunk_len::<B>(), B::zero());
+            Node::leaf(blocks)
+        }).collect();
+        // Build the tree a level at a time, from the chunks up
+        let mut height = 0;
+        while level.len() > 1 {
+            level = level.chunks(BRANCH).map(|children| {
+                let mut children = children.to_vec();
+                children.resize(BRANCH, None);
+                Node::branch(children)
+            }).collect();
+            height += 1;
+        }
+        PersistentBitSet::from_root(level.pop().unwrap_or(None), height)
+    }
+}
+
+impl<B: BitBlock> FromIterator<usize> for PersistentBitSet<B> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        PersistentBitSet::from(&BitSet::from_iter(iter))
+    }
+}
+
+impl<B: BitBlock> PartialEq for PersistentBitSet<B> {
+    fn eq(&self, other: &Self) -> bool {
+        // Empty subtrees are never stored and the trees are as low as
+        // possible, so equal sets have trees of the same shape
+        self.height == other.height && child_eq(&self.root, &other.root)
+    }
+}
+
+impl<B: BitBlock> Eq for PersistentBitSet<B> {}
+
+impl<B: BitBlock> fmt::Debug for PersistentBitSet<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_set().entries(self.iter()).finish()
+    }
+}
+
+/// The chunks of a `PersistentBitSet` holding values, with their indices.
+#[derive(Clone)]
+struct Leaves<'a, B: 'a> {
+    /// For each branch on the path to the last chunk, the children left to
+    /// visit, the index of the first chunk under the next one, and their
+    /// height.
+    stack: Vec<(slice::Iter<'a, Child<B>>, usize, usize)>,
+}
+
+impl<'a, B: BitBlock> Iterator for Leaves<'a, B> {
+    type Item = (usize, &'a Arc<Node<B>>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let next = {
+                let &mut (ref mut children, ref mut index, height) = self.stack.last_mut()?;
+                children.next().map(|child| {
+                    let first = *index;
+                    *index += 1 << (BRANCH_SHIFT * height);
+                    (child, first, height)
+                })
+            };
+            match next {
+                Some((Some(node), index, 0)) => return Some((index, node)),
+                Some((Some(node), index, height)) => {
+                    self.stack.push((node.children().iter(), index, height - 1));
+                }
+                Some((None, _, _)) => {}
+                None => {
+                    self.stack.pop();
+                }
+            }
+        }
+    }
+}
+
+/// The blocks of a `PersistentBitSet`, with zeros for the missing chunks up
+/// to the last chunk holding values.
+#[derive(Clone)]
+struct ChunkBlocks<'a, B: 'a> {
+    leaves: Leaves<'a, B>,
+    current: slice::Iter<'a, B>,
+    zeros: usize,
+    next_chunk: usize,
+}
+
+impl<'a, B: BitBlock> Iterator for ChunkBlocks<'a, B> {
+    type Item = B;
+
+    fn next(&mut self) -> Option<B> {
+        loop {
+            if self.zeros > 0 {
+                self.zeros -= 1;
+                return Some(B::zero());
+            }
+            if let Some(&block) = self.current.next() {
+                return Some(block);
+            }
+            let (index, leaf) = self.leaves.next()?;
+            self.zeros = (index - self.next_chunk) * chunk_len::<B>();
+            self.current = leaf.blocks().iter();
+            self.next_chunk = index + 1;
+        }
+    }
+}
+
+/// An iterator for `PersistentBitSet`.
+#[derive(Clone)]
+pub struct PersistentIter<'a, B: 'a>(BlockIter<ChunkBlocks<'a, B>, B>);
+
+impl<'a, B: BitBlock> Iterator for PersistentIter<'a, B> {
+    type Item = usize;
+
+    #[inline] fn next(&mut self) -> Option<usize> { self.0.next() }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::PersistentBitSet;
+    use BitSet;
+
+    #[test]
+    fn test_persistent_bit_set_versions() {
+        let empty: PersistentBitSet<u8> = PersistentBitSet::default();
+        assert!(empty.is_empty());
+
+        let v1 = empty.insert(0).insert(1500).insert(4000);
+        let v2 = v1.insert(1);
+        assert_eq!(v1.len(), 3);
+        assert_eq!(v2.len(), 4);
+        assert!(!v1.contains(1));
+        // Only the first chunk was copied
+        assert_eq!(v1.shared_chunks(&v2), 2);
+
+        let v3 = v2.remove(4000).remove(7);
+        assert_eq!(v3.iter().collect::<Vec<_>>(), [0, 1, 1500]);
+        assert_eq!(v3.remove(0).remove(1).remove(1500), empty);
+        assert_eq!(v3.insert(4000).remove(4000), v3);
+    }
+
+    #[test]
+    fn test_persistent_bit_set_operations() {
+        let a: PersistentBitSet<u64> = (0..10).chain(2000..2010).collect();
+        let b: PersistentBitSet<u64> = (5..15).chain(5000..5001).collect();
+
+        let union = a.union(&b);
+        assert_eq!(union.iter().collect::<Vec<_>>(),
+                   (0..15).chain(2000..2010).chain(5000..5001).collect::<Vec<_>>());
+        // The chunks of 2000..2010 and 5000 are reused, 0..15 is new
+        assert_eq!(union.shared_chunks(&a), 1);
+        assert_eq!(union.shared_chunks(&b), 1);
+
+        assert_eq!(a.intersection(&b).iter().collect::<Vec<_>>(), (5..10).collect::<Vec<_>>());
+        assert_eq!(a.difference(&b).iter().collect::<Vec<_>>(),
+                   (0..5).chain(2000..2010).collect::<Vec<_>>());
+        assert!(a.difference(&a).is_empty());
+
+        let set: BitSet<u64> = union.to_bit_set();
+        assert_eq!(PersistentBitSet::from(&set), union);
+        assert_eq!(set.len(), union.len());
+    }
+
+    #[test]
+    fn test_persistent_bit_set_tree() {
+        // 200 chunks, so two levels of branches over the leaves
+        let base: BitSet<u32> = (0..200).map(|i| i * 1024 + i).collect();
+        let a = PersistentBitSet::from(&base);
+        assert_eq!(a.height, 2);
+        assert_eq!(a.to_bit_set(), base);
+
+        // An update copies one chunk and the branches above it
+        let b = a.insert(5000 * 1024);
+        assert_eq!(b.height, 3);
+        assert_eq!(a.shared_chunks(&b), 200);
+        let c = b.remove(5000 * 1024);
+        assert_eq!(c, a);
+        assert_eq!(c.height, 2);
+        let d = a.remove(0).insert(1);
+        assert_eq!(a.shared_chunks(&d), 199);
+        assert_eq!(d.len(), 200);
+
+        // Merging two versions only looks at the subtrees they don't share
+        let union = a.union(&d);
+        assert_eq!(union.len(), 201);
+        assert_eq!(union.shared_chunks(&a), 199);
+        assert!(a.difference(&c).is_empty());
+        assert_eq!(a.intersection(&b), a);
+        assert_eq!(b.difference(&a).iter().collect::<Vec<_>>(), [5000 * 1024]);
+        assert_eq!(a.difference(&d).iter().collect::<Vec<_>>(), [0]);
+
+        // Against `BitSet`, with sets of different heights
+        let small: PersistentBitSet<u32> = (0..3000).step_by(7).collect();
+        let (small_set, b_set) = (small.to_bit_set(), b.to_bit_set());
+        assert!(small.union(&b).iter().eq(small_set.union(&b_set)));
+        assert!(b.intersection(&small).iter().eq(b_set.intersection(&small_set)));
+        assert!(small.difference(&b).iter().eq(small_set.difference(&b_set)));
+        assert!(small.iter().eq(small_set.iter()));
+    }
+}
@@ -0,0 +1,171 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A pool of scratch sets reusing their allocations.
+
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+use core::mem;
+use core::ops::{Deref, DerefMut};
+
+use bit_vec::BitBlock;
+
+use BitSet;
+
+/// A pool handing out empty sets and taking them back with their storage,
+/// so that temporary sets don't allocate once the pool is warm.
+///
+/// [`get`](#method.get) returns a guard giving access to the set, which
+/// goes back to the pool when the guard is dropped.
+/// [`take`](#method.take) and [`put`](#method.put) move owned sets out of
+/// and into the pool.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BitSetPool;
+///
+/// let pool = BitSetPool::new();
+/// for frame in 0..3 {
+///     let mut visible = pool.get();
+///     let mut hidden = pool.get();
+///     visible.insert(frame);
+///     hidden.insert(1000);
+///     assert_eq!(visible.len(), 1);
+/// }
+/// // The two sets of the first frame were reused by the next ones
+/// assert_eq!(pool.available(), 2);
+/// ```
+pub struct BitSetPool<B: BitBlock = u32> {
+    free: RefCell<Vec<BitSet<B>>>,
+}
+
+impl BitSetPool<u32> {
+    /// Creates a new empty `BitSetPool`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B: BitBlock> Default for BitSetPool<B> {
+    #[inline]
+    fn default() -> Self {
+        BitSetPool { free: RefCell::new(Vec::new()) }
+    }
+}
+
+impl<B: BitBlock> BitSetPool<B> {
+    /// Returns the number of sets waiting in the pool.
+    #[inline]
+    pub fn available(&self) -> usize {
+        self.free.borrow().len()
+    }
+
+    /// Takes an empty set out of the pool, or creates one if the pool is
+    /// empty.
+    #[inline]
+    pub fn take(&self) -> BitSet<B> {
+        self.free.borrow_mut().pop().unwrap_or_default()
+    }
+
+    /// Clears `set` and keeps it in the pool, with its storage.
+    #[inline]
+    pub fn put(&self, mut set: BitSet<B>) {
+        set.truncate(0);
+        self.free.borrow_mut().push(set);
+    }
+
+    /// Returns an empty set from the pool, which goes back to it when the
+    /// returned guard is dropped.
+    #[inline]
+    pub fn get(&self) -> PooledBitSet<'_, B> {
+        PooledBitSet { set: self.take(), pool: self }
+    }
+}
+
+impl<B: BitBlock> fmt::Debug for BitSetPool<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("BitSetPool").field("available", &self.available()).finish()
+    }
+}
+
+/// A set borrowed from a [`BitSetPool`](struct.BitSetPool.html), returned
+/// to it when dropped.
+pub struct PooledBitSet<'a, B: BitBlock = u32> {
+    set: BitSet<B>,
+    pool: &'a BitSetPool<B>,
+}
+
+impl<'a, B: BitBlock> PooledBitSet<'a, B> {
+    /// Keeps the set instead of returning it to the pool.
+    #[inline]
+    pub fn into_inner(mut this: Self) -> BitSet<B> {
+        mem::take(&mut this.set)
+    }
+}
+
+impl<'a, B: BitBlock> Deref for PooledBitSet<'a, B> {
+    type Target = BitSet<B>;
+
+    #[inline]
+    fn deref(&self) -> &BitSet<B> {
+        &self.set
+    }
+}
+
+impl<'a, B: BitBlock> DerefMut for PooledBitSet<'a, B> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut BitSet<B> {
+        &mut self.set
+    }
+}
+
+impl<'a, B: BitBlock> Drop for PooledBitSet<'a, B> {
+    fn drop(&mut self) {
+        // A set taken by `into_inner` leaves an unallocated one behind,
+        // which isn't worth keeping
+        if self.set.capacity() > 0 {
+            self.pool.put(mem::take(&mut self.set));
+        }
+    }
+}
+
+impl<'a, B: BitBlock> fmt::Debug for PooledBitSet<'a, B> {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.set.fmt(fmt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BitSetPool, PooledBitSet};
+
+    #[test]
+    fn test_bit_set_pool() {
+        let pool: BitSetPool<u8> = BitSetPool::default();
+        {
+            let mut a = pool.get();
+            a.insert(500);
+            let b = pool.get();
+            assert!(b.is_empty());
+        }
+        // The unallocated set isn't kept
+        assert_eq!(pool.available(), 1);
+
+        let a = pool.get();
+        assert!(a.is_empty() && a.capacity() >= 500);
+        let mut kept = PooledBitSet::into_inner(a);
+        assert_eq!(pool.available(), 0);
+        kept.insert(3);
+        pool.put(kept);
+        let reused = pool.take();
+        assert!(reused.is_empty() && reused.capacity() >= 500);
+        assert_eq!(format!("{:?}", pool), "BitSetPool { available: 0 }");
+    }
+}
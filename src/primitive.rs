@@ -0,0 +1,302 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Fast paths for the blocks that are primitive integers.
+//!
+//! `BitBlock` can be implemented by any type, so nothing about the layout of
+//! a block or the meaning of its operators follows from the trait alone. The
+//! fast paths here only reinterpret a block once its type is known to be one
+//! of the primitive integers implementing `Primitive`, and return `None` for
+//! every other block type, whose callers fall back to the generic operations.
+
+use alloc::vec::Vec;
+use core::any::TypeId;
+use core::marker::PhantomData;
+use core::mem;
+use core::slice;
+
+use bit_vec::BitBlock;
+
+mod sealed {
+    pub trait Sealed {}
+}
+
+/// A primitive integer used as a block. Sealed, so it is only implemented
+/// for the types below.
+pub(crate) trait Primitive: BitBlock + sealed::Sealed + 'static {
+    fn trailing_zeros(self) -> usize;
+    fn leading_zeros(self) -> usize;
+    fn reverse_bits(self) -> Self;
+}
+
+macro_rules! primitive {
+    ($($t:ty)*) => {$(
+        impl sealed::Sealed for $t {}
+
+        impl Primitive for $t {
+            #[inline]
+            fn trailing_zeros(self) -> usize {
+                <$t>::trailing_zeros(self) as usize
+            }
+
+            #[inline]
+            fn leading_zeros(self) -> usize {
+                <$t>::leading_zeros(self) as usize
+            }
+
+            #[inline]
+            fn reverse_bits(self) -> Self {
+                <$t>::reverse_bits(self)
+            }
+        }
+    )*};
+}
+
+primitive!(u8 u16 u32 u64 usize);
+
+/// Returns the `TypeId` of `T`, which unlike `TypeId::of` needn't be
+/// `'static`. Types differing only in their lifetimes share an id, which
+/// doesn't matter here since the primitive integers have none.
+fn type_id<T: ?Sized>() -> TypeId {
+    trait NonStaticAny {
+        fn type_id(&self) -> TypeId where Self: 'static;
+    }
+
+    impl<T: ?Sized> NonStaticAny for PhantomData<T> {
+        fn type_id(&self) -> TypeId where Self: 'static {
+            TypeId::of::<T>()
+        }
+    }
+
+    let phantom = PhantomData::<T>;
+    // Only the lifetime of the trait object changes, and `type_id` neither
+    // keeps nor dereferences anything of type `T`.
+    let any = unsafe { mem::transmute::<&dyn NonStaticAny, &(dyn NonStaticAny + 'static)>(&phantom) };
+    any.type_id()
+}
+
+/// Returns `block` as a `P` if `B` is `P`.
+#[inline]
+fn cast<B: BitBlock, P: Primitive>(block: B) -> Option<P> {
+    if type_id::<B>() == TypeId::of::<P>() {
+        // `B` and `P` are the same type
+        Some(unsafe { mem::transmute_copy(&block) })
+    } else {
+        None
+    }
+}
+
+/// Returns `value` as a `B` if `B` is `P`, the reverse of `cast`.
+#[inline]
+fn uncast<P: Primitive, B: BitBlock>(value: P) -> Option<B> {
+    if type_id::<B>() == TypeId::of::<P>() {
+        Some(unsafe { mem::transmute_copy(&value) })
+    } else {
+        None
+    }
+}
+
+/// Returns `true` if `B` is one of the primitive integers.
+#[inline]
+pub(crate) fn is_primitive<B: BitBlock>() -> bool {
+    let id = type_id::<B>();
+    id == TypeId::of::<u8>() || id == TypeId::of::<u16>() || id == TypeId::of::<u32>()
+        || id == TypeId::of::<u64>() || id == TypeId::of::<usize>()
+}
+
+/// Returns the bytes of `blocks` in memory order, if they are primitive
+/// integers.
+#[inline]
+pub(crate) fn as_bytes<B: BitBlock>(blocks: &[B]) -> Option<&[u8]> {
+    if !is_primitive::<B>() {
+        return None;
+    }
+    // The primitive integers have no padding, so all their bytes are
+    // initialized.
+    Some(unsafe { slice::from_raw_parts(blocks.as_ptr() as *const u8, mem::size_of_val(blocks)) })
+}
+
+/// Returns the bytes of `blocks` mutably, if they are primitive integers.
+#[inline]
+pub(crate) fn as_bytes_mut<B: BitBlock>(blocks: &mut [B]) -> Option<&mut [u8]> {
+    if !is_primitive::<B>() {
+        return None;
+    }
+    // Any byte pattern is also a valid primitive integer
+    Some(unsafe { slice::from_raw_parts_mut(blocks.as_mut_ptr() as *mut u8, mem::size_of_val(blocks)) })
+}
+
+/// Turns the bytes of blocks of `size` bytes from memory order to
+/// little-endian order, or back.
+#[inline]
+fn swap_to_le(bytes: &mut [u8], size: usize) {
+    if cfg!(target_endian = "big") {
+        for block in bytes.chunks_mut(size) {
+            block.reverse();
+        }
+    }
+}
+
+/// Writes the little-endian bytes of `blocks` to the start of `buf`, if they
+/// are primitive integers. Returns the number of bytes written.
+///
+/// # Panics
+///
+/// Panics if `buf` is shorter than the bytes of `blocks`.
+#[cfg(feature = "std")]
+pub(crate) fn write_le_bytes<B: BitBlock>(blocks: &[B], buf: &mut [u8]) -> Option<usize> {
+    let bytes = as_bytes(blocks)?;
+    let buf = &mut buf[..bytes.len()];
+    buf.copy_from_slice(bytes);
+    swap_to_le(buf, B::bytes());
+    Some(bytes.len())
+}
+
+/// Returns the blocks of `blocks` repacked into blocks of type `C`, keeping
+/// the order of the bits, if both are primitive integers. The last block
+/// is padded with zeros.
+pub(crate) fn repack<B: BitBlock, C: BitBlock>(blocks: &[B]) -> Option<Vec<C>> {
+    if !is_primitive::<C>() {
+        return None;
+    }
+    let src = as_bytes(blocks)?;
+    let mut repacked = alloc::vec![C::zero(); src.len().div_ceil(C::bytes())];
+    {
+        let dst = as_bytes_mut(&mut repacked)?;
+        dst[..src.len()].copy_from_slice(src);
+        swap_to_le(&mut dst[..src.len()], B::bytes());
+        swap_to_le(dst, C::bytes());
+    }
+    Some(repacked)
+}
+
+/// Evaluates `$e` with `$p` bound to `$block` as its primitive type, or
+/// returns `None` if the block isn't a primitive integer.
+macro_rules! with_primitive {
+    ($block:expr, $p:ident => $e:expr) => {{
+        let block = $block;
+        if let Some($p) = cast::<_, u8>(block) {
+            Some($e)
+        } else if let Some($p) = cast::<_, u16>(block) {
+            Some($e)
+        } else if let Some($p) = cast::<_, u32>(block) {
+            Some($e)
+        } else if let Some($p) = cast::<_, u64>(block) {
+            Some($e)
+        } else if let Some($p) = cast::<_, usize>(block) {
+            Some($e)
+        } else {
+            None
+        }
+    }};
+}
+
+/// Returns the number of trailing zeros of `block`, if it is a primitive
+/// integer.
+#[inline]
+pub(crate) fn trailing_zeros<B: BitBlock>(block: B) -> Option<usize> {
+    with_primitive!(block, p => Primitive::trailing_zeros(p))
+}
+
+/// Returns the number of leading zeros of `block`, if it is a primitive
+/// integer.
+#[inline]
+pub(crate) fn leading_zeros<B: BitBlock>(block: B) -> Option<usize> {
+    with_primitive!(block, p => Primitive::leading_zeros(p))
+}
+
+/// Returns `block` with the order of its bits reversed, if it is a
+/// primitive integer.
+#[inline]
+pub(crate) fn reverse_bits<B: BitBlock>(block: B) -> Option<B> {
+    with_primitive!(block, p => uncast(Primitive::reverse_bits(p)).unwrap())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use core::ops::{Add, BitAnd, BitOr, BitXor, Not, Rem, Shl, Shr, Sub};
+
+    use bit_vec::BitBlock;
+
+    use super::{is_primitive, leading_zeros, repack, reverse_bits, trailing_zeros, type_id};
+    #[cfg(feature = "std")]
+    use super::write_le_bytes;
+
+    /// A block type laid out like a `u32`, which the fast paths must not
+    /// treat as one.
+    #[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
+    pub(crate) struct Wrapped(pub(crate) u32);
+
+    macro_rules! op {
+        ($tr:ident, $f:ident, $rhs:ty, $conv:expr) => {
+            impl $tr<$rhs> for Wrapped {
+                type Output = Wrapped;
+                fn $f(self, rhs: $rhs) -> Wrapped {
+                    Wrapped(self.0.$f($conv(rhs)))
+                }
+            }
+        };
+    }
+
+    op!(Add, add, Wrapped, |r: Wrapped| r.0);
+    op!(Sub, sub, Wrapped, |r: Wrapped| r.0);
+    op!(BitAnd, bitand, Wrapped, |r: Wrapped| r.0);
+    op!(BitOr, bitor, Wrapped, |r: Wrapped| r.0);
+    op!(BitXor, bitxor, Wrapped, |r: Wrapped| r.0);
+    op!(Rem, rem, Wrapped, |r: Wrapped| r.0);
+    op!(Shl, shl, usize, |r: usize| r as u32);
+    op!(Shr, shr, usize, |r: usize| r as u32);
+
+    impl Not for Wrapped {
+        type Output = Wrapped;
+        fn not(self) -> Wrapped {
+            Wrapped(!self.0)
+        }
+    }
+
+    impl BitBlock for Wrapped {
+        fn bits() -> usize { 32 }
+        fn from_byte(byte: u8) -> Self { Wrapped(byte as u32) }
+        fn count_ones(self) -> usize { self.0.count_ones() as usize }
+        fn zero() -> Self { Wrapped(0) }
+        fn one() -> Self { Wrapped(1) }
+    }
+
+    #[test]
+    fn test_primitive_dispatch() {
+        assert_eq!(trailing_zeros(8u8), Some(3));
+        assert_eq!(trailing_zeros(1u64 << 40), Some(40));
+        assert_eq!(leading_zeros(1u16), Some(15));
+        assert_eq!(leading_zeros(1usize), Some(usize::BITS as usize - 1));
+        assert_eq!(trailing_zeros(Wrapped(8)), None);
+        assert_eq!(leading_zeros(Wrapped(1)), None);
+        assert_eq!(reverse_bits(0b0000_0110u8), Some(0b0110_0000));
+        assert_eq!(reverse_bits(Wrapped(1)), None);
+
+        assert!(is_primitive::<u8>() && is_primitive::<usize>());
+        assert!(!is_primitive::<Wrapped>());
+        assert!(type_id::<u32>() != type_id::<Wrapped>());
+        assert!(type_id::<u64>() != type_id::<usize>());
+        assert!(type_id::<&'static u32>() == type_id::<&u32>());
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn test_primitive_write_le_bytes() {
+        let mut buf = [0u8; 8];
+        assert_eq!(write_le_bytes(&[0x0102u16, 0x0304], &mut buf), Some(4));
+        assert_eq!(buf[..4], [2, 1, 4, 3]);
+        assert_eq!(write_le_bytes(&[Wrapped(1)], &mut buf), None);
+    }
+
+    #[test]
+    fn test_primitive_repack() {
+        assert_eq!(repack::<u16, u32>(&[0x0102, 0x0304, 0x0506]), Some(vec![0x0304_0102, 0x0506]));
+        assert_eq!(repack::<u64, u8>(&[0x0102]), Some(vec![2, 1, 0, 0, 0, 0, 0, 0]));
+        assert_eq!(repack::<u32, Wrapped>(&[1]), None);
+        assert_eq!(repack::<Wrapped, u32>(&[Wrapped(1)]), None);
+    }
+}
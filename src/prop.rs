@@ -0,0 +1,169 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Strategies generating `BitSet`s for `proptest`, behind the `proptest`
+//! feature.
+//!
+//! # Examples
+//!
+//! ```
+//! # extern crate proptest;
+//! # extern crate bit_set;
+//! use bit_set::prop::bit_set;
+//! use proptest::test_runner::TestRunner;
+//!
+//! # fn main() {
+//! let sets = (bit_set::<u32>(0..200, 0..20), bit_set::<u32>(0..200, 0..20));
+//! TestRunner::default().run(&sets, |(a, b)| {
+//!     let mut union = a.clone();
+//!     union.union_with(&b);
+//!     assert!(union.is_superset(&a) && union.is_superset(&b));
+//!     Ok(())
+//! }).unwrap();
+//! # }
+//! ```
+
+use core::fmt;
+use core::marker::PhantomData;
+use core::ops::Range;
+
+use bit_vec::BitBlock;
+use proptest::arbitrary::Arbitrary;
+use proptest::collection::{btree_set, BTreeSetStrategy, BTreeSetValueTree, SizeRange};
+use proptest::strategy::{NewTree, Strategy, ValueTree};
+use proptest::test_runner::TestRunner;
+
+use BitSet;
+
+/// The values of the sets generated by `Arbitrary`.
+const ARBITRARY_UNIVERSE: usize = 1024;
+
+/// Creates a strategy generating sets of values in `element`, with a
+/// number of values in `size`.
+///
+/// Shrinking removes values and makes the remaining ones smaller.
+///
+/// # Panics
+///
+/// Panics if `element` has fewer values than the minimum of `size`.
+pub fn bit_set<B: BitBlock>(element: Range<usize>, size: impl Into<SizeRange>)
+    -> BitSetStrategy<B> {
+    BitSetStrategy { inner: btree_set(element, size), marker: PhantomData }
+}
+
+/// A strategy generating `BitSet`s, created by [`bit_set`](fn.bit_set.html).
+#[derive(Clone)]
+pub struct BitSetStrategy<B = u32> {
+    inner: BTreeSetStrategy<Range<usize>>,
+    marker: PhantomData<fn() -> B>,
+}
+
+impl<B> fmt::Debug for BitSetStrategy<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_tuple("BitSetStrategy").field(&self.inner).finish()
+    }
+}
+
+impl<B: BitBlock> Strategy for BitSetStrategy<B> {
+    type Tree = BitSetValueTree<B>;
+    type Value = BitSet<B>;
+
+    fn new_tree(&self, runner: &mut TestRunner) -> NewTree<Self> {
+        let inner = self.inner.new_tree(runner)?;
+        Ok(BitSetValueTree { inner, marker: PhantomData })
+    }
+}
+
+/// The `ValueTree` of a [`BitSetStrategy`](struct.BitSetStrategy.html).
+#[derive(Clone)]
+pub struct BitSetValueTree<B = u32> {
+    inner: BTreeSetValueTree<<Range<usize> as Strategy>::Tree>,
+    marker: PhantomData<fn() -> B>,
+}
+
+impl<B> fmt::Debug for BitSetValueTree<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_tuple("BitSetValueTree").field(&self.inner).finish()
+    }
+}
+
+impl<B: BitBlock> ValueTree for BitSetValueTree<B> {
+    type Value = BitSet<B>;
+
+    fn current(&self) -> BitSet<B> {
+        let mut set = BitSet::default();
+        set.extend(self.inner.current());
+        set
+    }
+
+    #[inline]
+    fn simplify(&mut self) -> bool {
+        self.inner.simplify()
+    }
+
+    #[inline]
+    fn complicate(&mut self) -> bool {
+        self.inner.complicate()
+    }
+}
+
+/// Generates sets of values below 1024, with a number of values in the
+/// given range.
+impl<B: BitBlock> Arbitrary for BitSet<B> {
+    type Parameters = SizeRange;
+    type Strategy = BitSetStrategy<B>;
+
+    fn arbitrary_with(size: SizeRange) -> BitSetStrategy<B> {
+        bit_set(0..ARBITRARY_UNIVERSE, size)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use proptest::arbitrary::any;
+    use proptest::strategy::{Strategy, ValueTree};
+    use proptest::test_runner::TestRunner;
+
+    use BitSet;
+    use super::bit_set;
+
+    #[test]
+    fn test_bit_set_strategy() {
+        let mut runner = TestRunner::deterministic();
+        let strategy = bit_set::<u8>(10..50, 3..8);
+        for _ in 0..100 {
+            let set = strategy.new_tree(&mut runner).unwrap().current();
+            assert!((3..8).contains(&set.len()));
+            assert!(set.iter().all(|value| (10..50).contains(&value)));
+        }
+    }
+
+    #[test]
+    fn test_bit_set_shrinking() {
+        let mut runner = TestRunner::deterministic();
+        let mut tree = bit_set::<u32>(0..1000, 10..20).new_tree(&mut runner).unwrap();
+        // Shrink as `proptest` does, towards a minimal set failing a test
+        // that rejects the values of 100 or more
+        let fails = |set: BitSet| set.iter().any(|value| value >= 100);
+        assert!(fails(tree.current()));
+        while tree.simplify() {
+            while !fails(tree.current()) && tree.complicate() {}
+        }
+        let set = tree.current();
+        assert_eq!(set.len(), 10);
+        assert_eq!(set.iter().filter(|&value| value >= 100).collect::<Vec<_>>(), [100]);
+    }
+
+    #[test]
+    fn test_arbitrary() {
+        let mut runner = TestRunner::deterministic();
+        let set: BitSet = any::<BitSet>().new_tree(&mut runner).unwrap().current();
+        assert!(set.len() < 100);
+        assert!(set.iter().all(|value| value < 1024));
+    }
+}
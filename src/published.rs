@@ -0,0 +1,274 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A set updated by one writer and read through published snapshots.
+
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut};
+use core::sync::atomic::{AtomicPtr, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use bit_vec::BitBlock;
+
+use BitSet;
+
+/// The state shared by the writer and the readers.
+///
+/// `current` owns one strong count of the published snapshot. A reader
+/// announces itself in the counter of the epoch it started in, loads the
+/// pointer and takes its own strong count. The writer swaps the pointer,
+/// moves to the next epoch, and releases the count of the old snapshot only
+/// once no reader is left in the previous epoch, since those may have loaded
+/// the old pointer without counting it yet.
+struct Shared<B: BitBlock> {
+    current: AtomicPtr<BitSet<B>>,
+    epoch: AtomicUsize,
+    readers: [AtomicUsize; 2],
+    _marker: PhantomData<Arc<BitSet<B>>>,
+}
+
+impl<B: BitBlock> Shared<B> {
+    fn new(snapshot: Arc<BitSet<B>>) -> Self {
+        Shared {
+            current: AtomicPtr::new(Arc::into_raw(snapshot) as *mut _),
+            epoch: AtomicUsize::new(0),
+            readers: [AtomicUsize::new(0), AtomicUsize::new(0)],
+            _marker: PhantomData,
+        }
+    }
+
+    fn load(&self) -> Arc<BitSet<B>> {
+        let readers = loop {
+            let epoch = self.epoch.load(Ordering::SeqCst);
+            let readers = &self.readers[epoch % 2];
+            readers.fetch_add(1, Ordering::SeqCst);
+            if self.epoch.load(Ordering::SeqCst) == epoch {
+                break readers;
+            }
+            // The writer may already be past waiting for this counter
+            readers.fetch_sub(1, Ordering::SeqCst);
+        };
+        let snapshot = self.current.load(Ordering::SeqCst);
+        // The writer keeps the strong count of `current` until the reader
+        // leaves its epoch
+        unsafe { Arc::increment_strong_count(snapshot) };
+        readers.fetch_sub(1, Ordering::Release);
+        unsafe { Arc::from_raw(snapshot) }
+    }
+
+    fn store(&self, snapshot: Arc<BitSet<B>>) -> Arc<BitSet<B>> {
+        let old = self.current.swap(Arc::into_raw(snapshot) as *mut _, Ordering::SeqCst);
+        let epoch = self.epoch.fetch_add(1, Ordering::SeqCst);
+        // The readers only stay in an epoch for a few instructions
+        while self.readers[epoch % 2].load(Ordering::Acquire) != 0 {
+            std::thread::yield_now();
+        }
+        unsafe { Arc::from_raw(old) }
+    }
+}
+
+impl<B: BitBlock> Drop for Shared<B> {
+    fn drop(&mut self) {
+        drop(unsafe { Arc::from_raw(*self.current.get_mut()) });
+    }
+}
+
+/// A set changed by a single writer, whose readers see the snapshots it
+/// publishes.
+///
+/// The writer mutates a private copy, through `Deref` and `DerefMut` to
+/// [`BitSet`](struct.BitSet.html), and makes it visible with
+/// [`publish`](#method.publish). A [`PublishedReader`](struct.PublishedReader.html)
+/// takes a reference to the latest snapshot without locking, and then reads
+/// it for as long as it needs without blocking the writer. Publishing swaps
+/// a pointer, then waits only for the readers caught in the middle of taking
+/// their reference.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::PublishedBitSet;
+///
+/// let mut set = PublishedBitSet::new();
+/// let reader = set.reader();
+///
+/// set.insert(1);
+/// set.insert(2);
+/// assert!(reader.load().is_empty());
+///
+/// set.publish();
+/// let snapshot = reader.load();
+/// set.insert(3);
+/// set.publish();
+/// assert_eq!(snapshot.iter().collect::<Vec<_>>(), [1, 2]);
+/// assert_eq!(reader.load().len(), 3);
+/// ```
+pub struct PublishedBitSet<B: BitBlock = u32> {
+    working: BitSet<B>,
+    published: Arc<Shared<B>>,
+    /// The previously published snapshot, reused by the next publication
+    /// once no reader holds it anymore.
+    spare: Option<Arc<BitSet<B>>>,
+}
+
+/// A handle reading the snapshots published by a
+/// [`PublishedBitSet`](struct.PublishedBitSet.html).
+pub struct PublishedReader<B: BitBlock = u32> {
+    published: Arc<Shared<B>>,
+}
+
+impl PublishedBitSet<u32> {
+    /// Creates a new empty `PublishedBitSet`, with an empty snapshot
+    /// published.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B: BitBlock> Default for PublishedBitSet<B> {
+    #[inline]
+    fn default() -> Self {
+        PublishedBitSet::from_bit_set(BitSet::default())
+    }
+}
+
+impl<B: BitBlock> PublishedBitSet<B> {
+    /// Creates a `PublishedBitSet` starting from the values of `set`, which
+    /// are published.
+    pub fn from_bit_set(set: BitSet<B>) -> Self {
+        PublishedBitSet {
+            published: Arc::new(Shared::new(Arc::new(set.clone()))),
+            working: set,
+            spare: None,
+        }
+    }
+
+    /// Returns a new reader of the published snapshots.
+    #[inline]
+    pub fn reader(&self) -> PublishedReader<B> {
+        PublishedReader { published: self.published.clone() }
+    }
+
+    /// Publishes the current values of the set, which readers see from
+    /// their next [`load`](struct.PublishedReader.html#method.load).
+    ///
+    /// The snapshot previously published is recycled if no reader still
+    /// holds it, so a writer publishing regularly doesn't allocate.
+    pub fn publish(&mut self) {
+        let snapshot = match self.spare.take() {
+            Some(mut spare) => match Arc::get_mut(&mut spare) {
+                Some(set) => {
+                    set.clone_from(&self.working);
+                    spare
+                }
+                None => Arc::new(self.working.clone()),
+            },
+            None => Arc::new(self.working.clone()),
+        };
+        self.spare = Some(self.published.store(snapshot));
+    }
+
+    /// Returns the private copy of the writer, dropping the snapshots.
+    #[inline]
+    pub fn into_inner(self) -> BitSet<B> {
+        self.working
+    }
+}
+
+impl<B: BitBlock> Deref for PublishedBitSet<B> {
+    type Target = BitSet<B>;
+
+    #[inline]
+    fn deref(&self) -> &BitSet<B> {
+        &self.working
+    }
+}
+
+impl<B: BitBlock> DerefMut for PublishedBitSet<B> {
+    #[inline]
+    fn deref_mut(&mut self) -> &mut BitSet<B> {
+        &mut self.working
+    }
+}
+
+impl<B: BitBlock> PublishedReader<B> {
+    /// Returns the latest published snapshot, which stays unchanged however
+    /// long it is held. This never waits for the writer.
+    #[inline]
+    pub fn load(&self) -> Arc<BitSet<B>> {
+        self.published.load()
+    }
+}
+
+impl<B: BitBlock> Clone for PublishedReader<B> {
+    #[inline]
+    fn clone(&self) -> Self {
+        PublishedReader { published: self.published.clone() }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::vec::Vec;
+
+    use BitSet;
+    use super::PublishedBitSet;
+
+    #[test]
+    fn test_published_bit_set() {
+        let start: BitSet<u8> = [5].iter().collect();
+        let mut set = PublishedBitSet::from_bit_set(start);
+        let reader = set.reader();
+        assert_eq!(reader.load().iter().collect::<Vec<_>>(), [5]);
+
+        set.insert(6);
+        set.publish();
+        let held = reader.load();
+        set.insert(7);
+        set.publish();
+        set.remove(5);
+        set.publish();
+        assert_eq!(held.iter().collect::<Vec<_>>(), [5, 6]);
+        drop(held);
+
+        // Without readers holding them, two snapshots are reused in turn
+        let first = Arc::as_ptr(&reader.load());
+        set.publish();
+        set.publish();
+        assert_eq!(Arc::as_ptr(&reader.load()), first);
+        assert_eq!(reader.clone().load().iter().collect::<Vec<_>>(), [6, 7]);
+        assert_eq!(set.into_inner().len(), 2);
+    }
+
+    #[test]
+    fn test_published_bit_set_threads() {
+        let mut set = PublishedBitSet::new();
+        let reader = set.reader();
+        std::thread::scope(|scope| {
+            for _ in 0..4 {
+                let reader = reader.clone();
+                scope.spawn(move || {
+                    for _ in 0..1000 {
+                        // Each snapshot holds a prefix of the values
+                        let snapshot = reader.load();
+                        assert_eq!(snapshot.iter().collect::<Vec<_>>(), (0..snapshot.len()).collect::<Vec<_>>());
+                    }
+                });
+            }
+            for v in 0..1000 {
+                set.insert(v);
+                set.publish();
+            }
+        });
+
+        // Only the writer's spare and the published snapshot are left
+        let snapshot = reader.load();
+        assert_eq!(snapshot.len(), 1000);
+        assert_eq!(Arc::strong_count(&snapshot), 2);
+    }
+}
@@ -0,0 +1,175 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Random sampling, behind the `rand` feature.
+
+use bit_vec::BitBlock;
+use rand::Rng;
+
+use {blocks_for_bits, lowest_bit, BitSet};
+
+/// The number of bits of precision of the density of `BitSet::random`.
+const DENSITY_BITS: u32 = 32;
+
+/// Returns a block of independent, uniformly random bits.
+fn random_block<B: BitBlock, R: Rng + ?Sized>(rng: &mut R) -> B {
+    let mut block = B::zero();
+    let mut bits = 0;
+    while bits < B::bits() {
+        let word = rng.next_u64();
+        for byte in word.to_le_bytes().iter().take((B::bits() - bits) / 8) {
+            block = block | (B::from_byte(*byte) << bits);
+            bits += 8;
+        }
+    }
+    block
+}
+
+impl<B: BitBlock> BitSet<B> {
+    /// Creates a set of universe `universe` where each value is present
+    /// independently with probability `density`.
+    ///
+    /// The blocks are generated directly rather than bit by bit: the
+    /// density is rounded to 32 binary digits, and each digit costs one
+    /// random block, combined with `&` or `|` depending on the digit.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `density` isn't between 0 and 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate bit_set;
+    /// # extern crate rand;
+    /// use bit_set::BitSet;
+    ///
+    /// # fn main() {
+    /// let s: BitSet = BitSet::random(1000, 0.25, &mut rand::thread_rng());
+    /// assert_eq!(s.get_ref().len(), 1000);
+    /// assert!(s.iter().all(|value| value < 1000));
+    /// # }
+    /// ```
+    pub fn random<R: Rng + ?Sized>(universe: usize, density: f64, rng: &mut R) -> Self {
+        assert!((0.0..=1.0).contains(&density), "density {} is not between 0 and 1", density);
+        let scale = (1u64 << DENSITY_BITS) as f64;
+        let p = (density * scale).round() as u64;
+        let blocks = (0..blocks_for_bits::<B>(universe)).map(|_| {
+            if p == 0 {
+                return B::zero();
+            }
+            if p >> DENSITY_BITS != 0 {
+                return !B::zero();
+            }
+            // From the least significant digit up, a set digit halves the
+            // probability of a zero bit and a clear one that of a one bit
+            (p.trailing_zeros()..DENSITY_BITS).fold(B::zero(), |acc, digit| {
+                let block = random_block::<B, R>(rng);
+                if p >> digit & 1 == 1 { acc | block } else { acc & block }
+            })
+        }).collect();
+        let mut set = BitSet::from_blocks(blocks);
+        set.truncate(universe);
+        set
+    }
+
+    /// Returns a uniformly random value of the set, or `None` if it is
+    /// empty.
+    ///
+    /// The value is found by skipping whole blocks by their number of values,
+    /// without collecting the values.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # extern crate bit_set;
+    /// # extern crate rand;
+    /// use bit_set::BitSet;
+    ///
+    /// # fn main() {
+    /// let s: BitSet = [2, 40, 100].iter().cloned().collect();
+    /// let value = s.choose(&mut rand::thread_rng()).unwrap();
+    /// assert!(s.contains(value));
+    /// assert_eq!(BitSet::new().choose(&mut rand::thread_rng()), None);
+    /// # }
+    /// ```
+    pub fn choose<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<usize> {
+        let len = self.len();
+        if len == 0 {
+            return None;
+        }
+        let mut n = rng.gen_range(0..len);
+        for (i, block) in self.bit_vec.blocks().enumerate() {
+            let ones = block.count_ones();
+            if n < ones {
+                // Clear the `n` lowest bits to land on the chosen one
+                let block = (0..n).fold(block, |b, _| b & (b - B::one()));
+                return Some(i * B::bits() + lowest_bit(block));
+            }
+            n -= ones;
+        }
+        unreachable!("the set has fewer values than its length")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+
+    use BitSet;
+    use super::random_block;
+
+    #[test]
+    fn test_choose() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let s: BitSet<u8> = [0, 7, 8, 33, 100].iter().cloned().collect();
+        let mut counts = [0; 101];
+        for _ in 0..5000 {
+            counts[s.choose(&mut rng).unwrap()] += 1;
+        }
+        for (value, &count) in counts.iter().enumerate() {
+            if s.contains(value) {
+                assert!(count > 800, "{} chosen {} times", value, count);
+            } else {
+                assert_eq!(count, 0);
+            }
+        }
+        let mut empty = s.clone();
+        empty.clear();
+        assert_eq!(empty.choose(&mut rng), None);
+    }
+
+    #[test]
+    fn test_random_block() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut seen = 0u32;
+        for _ in 0..100 {
+            seen |= random_block::<u32, _>(&mut rng);
+        }
+        assert_eq!(seen, !0);
+    }
+
+    #[test]
+    fn test_random() {
+        let mut rng = StdRng::seed_from_u64(7);
+        for &density in &[0.0, 0.01, 0.25, 0.5, 0.9, 1.0] {
+            let s = BitSet::<u8>::random(100_003, density, &mut rng);
+            assert_eq!(s.get_ref().len(), 100_003);
+            let expected = density * 100_003.0;
+            assert!((s.len() as f64 - expected).abs() <= 1000.0,
+                    "density {}: {} values", density, s.len());
+        }
+        assert_eq!(BitSet::<u32>::random(100, 1.0, &mut rng).len(), 100);
+        assert!(BitSet::<u32>::random(0, 0.5, &mut rng).is_empty());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_random_bad_density() {
+        BitSet::<u32>::random(10, 1.5, &mut StdRng::seed_from_u64(7));
+    }
+}
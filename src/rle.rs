@@ -0,0 +1,311 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A set stored as a sorted list of runs of consecutive values.
+
+use alloc::vec::Vec;
+use core::cmp;
+use core::fmt;
+use core::iter::{Cloned, Flatten, FromIterator};
+use core::ops::Range;
+use core::slice;
+
+use bit_vec::BitBlock;
+
+use BitSet;
+
+/// A set stored as the sorted list of its maximal runs of consecutive
+/// values, taking two `usize`s per run whatever its length.
+///
+/// This suits data made of long runs of present and absent values, such as
+/// interval masks, where it can be orders of magnitude smaller than a
+/// `BitSet`. Lookups take a binary search over the runs.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::RleBitSet;
+///
+/// let mut s = RleBitSet::from_ranges(vec![0..1_000_000, 5_000_000..6_000_000]);
+/// assert_eq!(s.len(), 2_000_000);
+///
+/// s.remove(500);
+/// assert_eq!(s.runs(), [0..500, 501..1_000_000, 5_000_000..6_000_000]);
+/// ```
+#[derive(Clone, Default, PartialEq, Eq, Hash)]
+pub struct RleBitSet {
+    /// Sorted, non-empty runs separated by at least one absent value.
+    runs: Vec<Range<usize>>,
+}
+
+impl RleBitSet {
+    /// Creates a new empty `RleBitSet`.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Creates a set holding every value of the given ranges, which may
+    /// overlap and come in any order.
+    pub fn from_ranges<I: IntoIterator<Item = Range<usize>>>(ranges: I) -> Self {
+        let mut ranges: Vec<_> = ranges.into_iter().filter(|r| r.start < r.end).collect();
+        ranges.sort_unstable_by_key(|r| r.start);
+        let mut runs: Vec<Range<usize>> = Vec::with_capacity(ranges.len());
+        for range in ranges {
+            match runs.last_mut() {
+                Some(last) if range.start <= last.end => last.end = cmp::max(last.end, range.end),
+                _ => runs.push(range),
+            }
+        }
+        RleBitSet { runs }
+    }
+
+    /// Returns the runs of consecutive values of the set, in ascending
+    /// order.
+    #[inline]
+    pub fn runs(&self) -> &[Range<usize>] {
+        &self.runs
+    }
+
+    /// Returns the number of values in the set.
+    pub fn len(&self) -> usize {
+        self.runs.iter().map(|r| r.len()).sum()
+    }
+
+    /// Returns `true` if the set holds no value.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.runs.is_empty()
+    }
+
+    /// Returns `true` if this set contains the specified integer.
+    pub fn contains(&self, value: usize) -> bool {
+        let i = self.runs.partition_point(|r| r.end <= value);
+        self.runs.get(i).is_some_and(|r| r.start <= value)
+    }
+
+    /// Adds a value to the set. Returns `true` if the value was not already
+    /// present in the set.
+    pub fn insert(&mut self, value: usize) -> bool {
+        // The first run ending at or after `value`
+        let i = self.runs.partition_point(|r| r.end < value);
+        match self.runs.get(i).cloned() {
+            Some(run) if run.start <= value && value < run.end => return false,
+            Some(run) if run.end == value => {
+                self.runs[i].end += 1;
+                if self.runs.get(i + 1).is_some_and(|next| next.start == value + 1) {
+                    self.runs[i].end = self.runs.remove(i + 1).end;
+                }
+            }
+            Some(run) if run.start == value + 1 => self.runs[i].start = value,
+            _ => self.runs.insert(i, value..value + 1),
+        }
+        true
+    }
+
+    /// Removes a value from the set. Returns `true` if the value was
+    /// present in the set.
+    pub fn remove(&mut self, value: usize) -> bool {
+        let i = self.runs.partition_point(|r| r.end <= value);
+        let run = match self.runs.get(i) {
+            Some(run) if run.start <= value => run.clone(),
+            _ => return false,
+        };
+        if run.len() == 1 {
+            self.runs.remove(i);
+        } else if run.start == value {
+            self.runs[i].start += 1;
+        } else if run.end == value + 1 {
+            self.runs[i].end -= 1;
+        } else {
+            self.runs[i].end = value;
+            self.runs.insert(i + 1, value + 1..run.end);
+        }
+        true
+    }
+
+    /// Returns the union of both sets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::RleBitSet;
+    ///
+    /// let a = RleBitSet::from_ranges(vec![0..10]);
+    /// let b = RleBitSet::from_ranges(vec![10..20, 30..40]);
+    /// assert_eq!(a.union(&b).runs(), [0..20, 30..40]);
+    /// ```
+    pub fn union(&self, other: &Self) -> Self {
+        RleBitSet::from_ranges(self.runs.iter().chain(other.runs.iter()).cloned())
+    }
+
+    /// Returns the intersection of both sets.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::RleBitSet;
+    ///
+    /// let a = RleBitSet::from_ranges(vec![0..10, 20..30]);
+    /// let b = RleBitSet::from_ranges(vec![5..25]);
+    /// assert_eq!(a.intersection(&b).runs(), [5..10, 20..25]);
+    /// ```
+    pub fn intersection(&self, other: &Self) -> Self {
+        let mut runs = Vec::new();
+        let (mut i, mut j) = (0, 0);
+        while i < self.runs.len() && j < other.runs.len() {
+            let (a, b) = (&self.runs[i], &other.runs[j]);
+            let start = cmp::max(a.start, b.start);
+            let end = cmp::min(a.end, b.end);
+            if start < end {
+                runs.push(start..end);
+            }
+            if a.end <= b.end { i += 1 } else { j += 1 }
+        }
+        RleBitSet { runs }
+    }
+
+    /// Returns the values of `self` that aren't in `other`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::RleBitSet;
+    ///
+    /// let a = RleBitSet::from_ranges(vec![0..10, 20..30]);
+    /// let b = RleBitSet::from_ranges(vec![5..25]);
+    /// assert_eq!(a.difference(&b).runs(), [0..5, 25..30]);
+    /// ```
+    pub fn difference(&self, other: &Self) -> Self {
+        let mut runs = Vec::new();
+        let mut j = 0;
+        for run in &self.runs {
+            let mut start = run.start;
+            // Skip the runs of `other` ending before this one
+            while j < other.runs.len() && other.runs[j].end <= start {
+                j += 1;
+            }
+            let mut k = j;
+            while k < other.runs.len() && other.runs[k].start < run.end {
+                if start < other.runs[k].start {
+                    runs.push(start..other.runs[k].start);
+                }
+                start = cmp::max(start, other.runs[k].end);
+                k += 1;
+            }
+            if start < run.end {
+                runs.push(start..run.end);
+            }
+        }
+        RleBitSet { runs }
+    }
+
+    /// Iterator over each value in the set, in ascending order.
+    pub fn iter(&self) -> RleIter<'_> {
+        RleIter(self.runs.iter().cloned().flatten())
+    }
+
+    /// Returns a `BitSet` holding the values of this set.
+    pub fn to_bit_set<B: BitBlock>(&self) -> BitSet<B> {
+        BitSet::from_ranges(self.runs.iter().cloned())
+    }
+}
+
+impl<'a, B: BitBlock> From<&'a BitSet<B>> for RleBitSet {
+    fn from(set: &'a BitSet<B>) -> Self {
+        RleBitSet { runs: set.runs().collect() }
+    }
+}
+
+impl FromIterator<usize> for RleBitSet {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        RleBitSet::from_ranges(iter.into_iter().map(|value| value..value + 1))
+    }
+}
+
+impl<'a> IntoIterator for &'a RleBitSet {
+    type Item = usize;
+    type IntoIter = RleIter<'a>;
+
+    fn into_iter(self) -> RleIter<'a> {
+        self.iter()
+    }
+}
+
+impl fmt::Debug for RleBitSet {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_set().entries(self.runs.iter()).finish()
+    }
+}
+
+/// An iterator for `RleBitSet`.
+#[derive(Clone)]
+pub struct RleIter<'a>(Flatten<Cloned<slice::Iter<'a, Range<usize>>>>);
+
+impl<'a> Iterator for RleIter<'a> {
+    type Item = usize;
+
+    #[inline] fn next(&mut self) -> Option<usize> { self.0.next() }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::RleBitSet;
+    use BitSet;
+
+    #[test]
+    fn test_rle_bit_set_insert_remove() {
+        let mut s = RleBitSet::new();
+        assert!(s.insert(5));
+        assert!(s.insert(7));
+        assert!(!s.insert(7));
+        assert_eq!(s.runs(), [5..6, 7..8]);
+        assert!(s.insert(6));
+        assert_eq!(s.runs().to_vec(), vec![5..8]);
+        assert!(s.insert(4));
+        assert!(s.insert(10));
+        assert_eq!(s.runs(), [4..8, 10..11]);
+
+        assert!(s.remove(6));
+        assert!(!s.remove(6));
+        assert!(!s.remove(100));
+        assert_eq!(s.runs(), [4..6, 7..8, 10..11]);
+        assert!(s.remove(4));
+        assert!(s.remove(7));
+        assert!(s.remove(10));
+        assert_eq!(s.runs().to_vec(), vec![5..6]);
+        assert!(s.contains(5));
+        assert!(!s.contains(4) && !s.contains(6));
+        assert_eq!(s.len(), 1);
+    }
+
+    #[test]
+    fn test_rle_bit_set_operations() {
+        let a_values: Vec<usize> = (0..300).filter(|x| (x / 7) % 3 != 0).collect();
+        let b_values: Vec<usize> = (50..400).filter(|x| (x / 11) % 2 == 0).collect();
+        let a: RleBitSet = a_values.iter().cloned().collect();
+        let b: RleBitSet = b_values.iter().cloned().collect();
+        let (da, db): (BitSet, BitSet) = (a.to_bit_set(), b.to_bit_set());
+
+        assert_eq!(a.iter().collect::<Vec<_>>(), a_values);
+        assert_eq!(RleBitSet::from(&da), a);
+
+        let mut expected = da.clone();
+        expected.union_with(&db);
+        assert_eq!(a.union(&b).to_bit_set::<u32>(), expected);
+
+        let mut expected = da.clone();
+        expected.intersect_with(&db);
+        assert_eq!(a.intersection(&b).to_bit_set::<u32>(), expected);
+
+        let mut expected = da.clone();
+        expected.difference_with(&db);
+        assert_eq!(a.difference(&b).to_bit_set::<u32>(), expected);
+        assert!(b.difference(&b).is_empty());
+    }
+}
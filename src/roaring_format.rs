@@ -0,0 +1,278 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Reading and writing the portable serialization format of Roaring bitmaps.
+//!
+//! See <https://github.com/RoaringBitmap/RoaringFormatSpec> for the format.
+
+use core::ops::Range;
+use std::io::{self, Read, Write};
+use std::vec::Vec;
+
+use bit_vec::BitBlock;
+
+use super::BitSet;
+
+const SERIAL_COOKIE_NO_RUNCONTAINER: u32 = 12346;
+const SERIAL_COOKIE: u16 = 12347;
+const NO_OFFSET_THRESHOLD: usize = 4;
+/// Containers with at most this many elements are stored as sorted arrays.
+const ARRAY_MAX_LEN: usize = 4096;
+const BITMAP_WORDS: usize = 1024;
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+fn read_u16<R: Read>(reader: &mut R) -> io::Result<u16> {
+    let mut bytes = [0u8; 2];
+    reader.read_exact(&mut bytes)?;
+    Ok(u16::from_le_bytes(bytes))
+}
+
+fn read_u32<R: Read>(reader: &mut R) -> io::Result<u32> {
+    let mut bytes = [0u8; 4];
+    reader.read_exact(&mut bytes)?;
+    Ok(u32::from_le_bytes(bytes))
+}
+
+/// Reads the payload of a container, returning the ranges of offsets from
+/// its base it holds.
+fn read_container<R: Read>(reader: &mut R, is_run: bool, card: usize) -> io::Result<Vec<Range<usize>>> {
+    let mut ranges = Vec::new();
+    if is_run {
+        let runs = read_u16(reader)?;
+        for _ in 0..runs {
+            let start = read_u16(reader)? as usize;
+            let run_len = read_u16(reader)? as usize + 1;
+            if start + run_len > 1 << 16 {
+                return Err(invalid_data("run overflows its container"));
+            }
+            ranges.push(start..start + run_len);
+        }
+    } else if card <= ARRAY_MAX_LEN {
+        for _ in 0..card {
+            let value = read_u16(reader)? as usize;
+            ranges.push(value..value + 1);
+        }
+    } else {
+        let mut word = [0u8; 8];
+        for w in 0..BITMAP_WORDS {
+            reader.read_exact(&mut word)?;
+            let mut word = u64::from_le_bytes(word);
+            while word != 0 {
+                let value = 64 * w + word.trailing_zeros() as usize;
+                ranges.push(value..value + 1);
+                word &= word - 1;
+            }
+        }
+    }
+    Ok(ranges)
+}
+
+impl<B: BitBlock> BitSet<B> {
+    /// Writes this set to `writer` in the portable serialization format of
+    /// Roaring bitmaps, as used by the Java, Go, C and Rust implementations.
+    ///
+    /// Each range of 2^16 elements holding at least one element becomes an
+    /// array container if it holds up to 4096 elements, and a bitmap
+    /// container otherwise. Run containers are never written.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `InvalidInput` if the set holds an element
+    /// that doesn't fit in a `u32`, and forwards any error from `writer`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = [1, 5, 70000].iter().cloned().collect();
+    ///
+    /// let mut buf = Vec::new();
+    /// s.write_roaring(&mut buf).unwrap();
+    ///
+    /// assert_eq!(BitSet::read_roaring(&buf[..]).unwrap(), s);
+    /// ```
+    pub fn write_roaring<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        // Gather the key and cardinality of each container
+        let mut containers: Vec<(u16, usize)> = Vec::new();
+        for value in self.iter() {
+            if value > u32::MAX as usize {
+                return Err(io::Error::new(io::ErrorKind::InvalidInput,
+                                          "element doesn't fit in a u32"));
+            }
+            let key = (value >> 16) as u16;
+            match containers.last_mut() {
+                Some(&mut (k, ref mut len)) if k == key => *len += 1,
+                _ => containers.push((key, 1)),
+            }
+        }
+
+        writer.write_all(&SERIAL_COOKIE_NO_RUNCONTAINER.to_le_bytes())?;
+        writer.write_all(&(containers.len() as u32).to_le_bytes())?;
+        for &(key, len) in &containers {
+            writer.write_all(&key.to_le_bytes())?;
+            writer.write_all(&((len - 1) as u16).to_le_bytes())?;
+        }
+        let header_len = 8 + 8 * containers.len();
+        let mut offset = header_len;
+        for &(_, len) in &containers {
+            writer.write_all(&(offset as u32).to_le_bytes())?;
+            offset += if len <= ARRAY_MAX_LEN { 2 * len } else { 8 * BITMAP_WORDS };
+        }
+
+        let mut values = self.iter();
+        for &(key, len) in &containers {
+            let base = (key as usize) << 16;
+            if len <= ARRAY_MAX_LEN {
+                for value in values.by_ref().take(len) {
+                    writer.write_all(&((value - base) as u16).to_le_bytes())?;
+                }
+            } else {
+                let mut words = [0u64; BITMAP_WORDS];
+                for value in values.by_ref().take(len) {
+                    let low = value - base;
+                    words[low / 64] |= 1 << (low % 64);
+                }
+                for word in words.iter() {
+                    writer.write_all(&word.to_le_bytes())?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a set written in the portable serialization format of Roaring
+    /// bitmaps from `reader`.
+    ///
+    /// All container kinds are supported, including run containers. The
+    /// result is a regular dense `BitSet`, so its storage is proportional to
+    /// the largest element of the bitmap. It only grows for a container once
+    /// the payload of the container has been read, so a truncated input
+    /// doesn't allocate the space announced by its header.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error of kind `InvalidData` if the data isn't a valid
+    /// Roaring bitmap, and forwards any error from `reader`, including
+    /// `UnexpectedEof` if the data is truncated.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// // A bitmap holding 1, 2 and 3 written by another implementation
+    /// let buf = [0x3a, 0x30, 0, 0, 1, 0, 0, 0, 0, 0, 2, 0, 16, 0, 0, 0,
+    ///            1, 0, 2, 0, 3, 0];
+    /// let s: BitSet = BitSet::read_roaring(&buf[..]).unwrap();
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), [1, 2, 3]);
+    /// ```
+    pub fn read_roaring<R: Read>(mut reader: R) -> io::Result<Self> {
+        let cookie = read_u32(&mut reader)?;
+        let (len, run_flags) = if cookie == SERIAL_COOKIE_NO_RUNCONTAINER {
+            (read_u32(&mut reader)? as usize, None)
+        } else if cookie as u16 == SERIAL_COOKIE {
+            let len = (cookie >> 16) as usize + 1;
+            let mut flags = vec![0u8; len.div_ceil(8)];
+            reader.read_exact(&mut flags)?;
+            (len, Some(flags))
+        } else {
+            return Err(invalid_data("not a serialized Roaring bitmap"));
+        };
+        if len > 1 << 16 {
+            return Err(invalid_data("too many containers"));
+        }
+
+        let mut descriptions = Vec::with_capacity(len);
+        for _ in 0..len {
+            let key = read_u16(&mut reader)?;
+            let card = read_u16(&mut reader)? as usize + 1;
+            descriptions.push((key, card));
+        }
+        if run_flags.is_none() || len >= NO_OFFSET_THRESHOLD {
+            // The offsets are only useful for random access, skip them
+            for _ in 0..len {
+                read_u32(&mut reader)?;
+            }
+        }
+
+        let mut set = BitSet::default();
+        for (i, &(key, card)) in descriptions.iter().enumerate() {
+            let is_run = run_flags.as_ref()
+                .is_some_and(|flags| flags[i / 8] & (1 << (i % 8)) != 0);
+            let ranges = read_container(&mut reader, is_run, card)?;
+            // Only grow once the payload is known to be there
+            let base = (key as usize).checked_mul(1 << 16)
+                .ok_or_else(|| invalid_data("container key out of range"))?;
+            let end = base.checked_add(1 << 16)
+                .ok_or_else(|| invalid_data("container key out of range"))?;
+            set.reserve_len(end);
+            for range in ranges {
+                set.fill_range(base + range.start..base + range.end);
+            }
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::super::BitSet;
+
+    #[test]
+    fn test_bit_set_roaring_round_trip() {
+        let empty = BitSet::new();
+        let mut buf = Vec::new();
+        empty.write_roaring(&mut buf).unwrap();
+        assert_eq!(buf, [0x3a, 0x30, 0, 0, 0, 0, 0, 0]);
+        assert!(BitSet::<u32>::read_roaring(&buf[..]).unwrap().is_empty());
+
+        // One sparse array container, one dense bitmap container
+        let mut a: BitSet<u64> = (0..10_000).map(|x| x * 3).collect();
+        a.insert(1 << 20);
+        buf.clear();
+        a.write_roaring(&mut buf).unwrap();
+        assert_eq!(&buf[4..8], [2, 0, 0, 0]);
+        assert_eq!(BitSet::<u64>::read_roaring(&buf[..]).unwrap(), a);
+
+        let mut too_big = BitSet::new();
+        too_big.insert(1 << 32);
+        assert!(too_big.write_roaring(&mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn test_bit_set_roaring_run_containers() {
+        // Cookie with one container, a run flag, then key 1 holding the runs
+        // [2, 5) and [65534, 65536).
+        let buf = [0x3b, 0x30, 0, 0, 0b1, 1, 0, 4, 0,
+                   2, 0, 2, 0, 2, 0, 0xfe, 0xff, 1, 0];
+        let s: BitSet = BitSet::read_roaring(&buf[..]).unwrap();
+        let expected: Vec<_> = (0x10002..0x10005).chain(0x1fffe..0x20000).collect();
+        assert_eq!(s.iter().collect::<Vec<_>>(), expected);
+
+        assert!(BitSet::<u32>::read_roaring(&buf[..buf.len() - 1]).is_err());
+        assert!(BitSet::<u32>::read_roaring(&[1, 2, 3, 4][..]).is_err());
+    }
+
+    #[test]
+    fn test_bit_set_roaring_truncated_payload() {
+        // One array container with the last key and 4 elements, of which
+        // only the first is there
+        let mut buf = vec![0x3a, 0x30, 0, 0, 1, 0, 0, 0, 0xff, 0xff, 3, 0, 16, 0, 0, 0, 7, 0];
+        let err = BitSet::<u32>::read_roaring(&buf[..]).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::UnexpectedEof);
+
+        buf.extend_from_slice(&[8, 0, 9, 0, 10, 0]);
+        buf[8..10].copy_from_slice(&[0x10, 0]);
+        let s = BitSet::<u32>::read_roaring(&buf[..]).unwrap();
+        assert_eq!(s.iter().collect::<Vec<_>>(), (0x10_0007..0x10_000b).collect::<Vec<_>>());
+    }
+}
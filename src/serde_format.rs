@@ -0,0 +1,142 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Serde support, behind the `serde` feature.
+//!
+//! Human-readable formats such as JSON get the list of values, in
+//! ascending order, so that serialized sets stay reviewable. Binary formats
+//! get a pair of the length in bits, as a `u64`, and the sequence of
+//! blocks, which is compact for dense sets.
+
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use core::fmt;
+use core::marker::PhantomData;
+
+use bit_vec::BitBlock;
+use serde::de::{self, Deserialize, Deserializer, SeqAccess, Visitor};
+use serde::ser::{Serialize, SerializeTuple, Serializer};
+
+use {blocks_for_bits, BitSet};
+
+impl<B: BitBlock + Serialize> Serialize for BitSet<B> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        if serializer.is_human_readable() {
+            serializer.collect_seq(self.iter())
+        } else {
+            let mut tuple = serializer.serialize_tuple(2)?;
+            tuple.serialize_element(&(self.bit_vec.len() as u64))?;
+            tuple.serialize_element(self.as_block_slice())?;
+            tuple.end()
+        }
+    }
+}
+
+impl<'de, B: BitBlock + Deserialize<'de>> Deserialize<'de> for BitSet<B> {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        if deserializer.is_human_readable() {
+            deserializer.deserialize_seq(ValuesVisitor(PhantomData))
+        } else {
+            deserializer.deserialize_tuple(2, BlocksVisitor(PhantomData))
+        }
+    }
+}
+
+/// Reads the list of values of the human-readable representation.
+struct ValuesVisitor<B>(PhantomData<B>);
+
+impl<'de, B: BitBlock> Visitor<'de> for ValuesVisitor<B> {
+    type Value = BitSet<B>;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("a sequence of set values")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<BitSet<B>, A::Error> {
+        let mut set = BitSet::default();
+        while let Some(value) = seq.next_element::<usize>()? {
+            set.insert(value);
+        }
+        Ok(set)
+    }
+}
+
+/// Reads the length and blocks of the binary representation.
+struct BlocksVisitor<B>(PhantomData<B>);
+
+impl<'de, B: BitBlock + Deserialize<'de>> Visitor<'de> for BlocksVisitor<B> {
+    type Value = BitSet<B>;
+
+    fn expecting(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.write_str("a length in bits and a sequence of blocks")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<BitSet<B>, A::Error> {
+        let nbits: u64 = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(0, &self))?;
+        let blocks: Vec<B> = seq.next_element()?.ok_or_else(|| de::Error::invalid_length(1, &self))?;
+        let nbits = usize::try_from(nbits)
+            .map_err(|_| de::Error::custom("bit set length overflows usize"))?;
+        if blocks.len() != blocks_for_bits::<B>(nbits) {
+            return Err(de::Error::custom("bit set blocks don't match its length"));
+        }
+        let used = nbits % B::bits();
+        if used != 0 && blocks[blocks.len() - 1] >> used != B::zero() {
+            return Err(de::Error::custom("bit set has bits set past its length"));
+        }
+        let mut set = BitSet::from_blocks(blocks);
+        set.truncate(nbits);
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_test::{assert_de_tokens_error, assert_tokens, Compact, Configure, Token};
+
+    use BitSet;
+
+    #[test]
+    fn test_serde_readable() {
+        let s: BitSet<u8> = [1, 9].iter().cloned().collect();
+        assert_tokens(&s.readable(), &[
+            Token::Seq { len: None },
+            Token::U64(1),
+            Token::U64(9),
+            Token::SeqEnd,
+        ]);
+    }
+
+    #[test]
+    fn test_serde_compact() {
+        let s: BitSet<u8> = [1, 9].iter().cloned().collect();
+        assert_tokens(&s.compact(), &[
+            Token::Tuple { len: 2 },
+            Token::U64(10),
+            Token::Seq { len: Some(2) },
+            Token::U8(0b10),
+            Token::U8(0b10),
+            Token::SeqEnd,
+            Token::TupleEnd,
+        ]);
+
+        assert_de_tokens_error::<Compact<BitSet<u8>>>(&[
+            Token::Tuple { len: 2 },
+            Token::U64(4),
+            Token::Seq { len: Some(1) },
+            Token::U8(0x10),
+            Token::SeqEnd,
+            Token::TupleEnd,
+        ], "bit set has bits set past its length");
+        assert_de_tokens_error::<Compact<BitSet<u8>>>(&[
+            Token::Tuple { len: 2 },
+            Token::U64(20),
+            Token::Seq { len: Some(1) },
+            Token::U8(1),
+            Token::SeqEnd,
+            Token::TupleEnd,
+        ], "bit set blocks don't match its length");
+    }
+}
@@ -0,0 +1,229 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A growable set split in independently locked shards.
+
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::vec::Vec;
+
+use bit_vec::BitBlock;
+
+use BitSet;
+
+/// A growable set that can be updated from many threads, split in shards
+/// each behind its own lock.
+///
+/// The values are cut in consecutive chunks of `span` values, dealt to the
+/// shards in turn: writers working on different regions of the universe
+/// lock different shards, and each shard grows independently as values are
+/// added. The merged contents are read with [`contains`](#method.contains),
+/// [`len`](#method.len) or [`to_bit_set`](#method.to_bit_set).
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::ShardedBitSet;
+///
+/// let set = ShardedBitSet::new();
+///
+/// std::thread::scope(|scope| {
+///     for t in 0..4 {
+///         let set = &set;
+///         scope.spawn(move || {
+///             for v in t * 100_000..(t + 1) * 100_000 {
+///                 set.insert(v);
+///             }
+///         });
+///     }
+/// });
+///
+/// assert_eq!(set.len(), 400_000);
+/// assert!(set.to_bit_set().iter().eq(0..400_000));
+/// ```
+#[derive(Debug)]
+pub struct ShardedBitSet<B: BitBlock = u32> {
+    shards: Vec<Mutex<BitSet<B>>>,
+    /// The number of values of each chunk, a multiple of the block size.
+    span: usize,
+}
+
+impl ShardedBitSet<u32> {
+    /// Creates a new empty `ShardedBitSet` with 16 shards and chunks of 4096
+    /// values.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B: BitBlock> Default for ShardedBitSet<B> {
+    #[inline]
+    fn default() -> Self {
+        ShardedBitSet::with_shards(16, 4096)
+    }
+}
+
+impl<B: BitBlock> ShardedBitSet<B> {
+    /// Creates a new empty set with `shards` shards, dealt chunks of `span`
+    /// values in turn. The span is rounded up to a multiple of the block
+    /// size.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `shards` or `span` is zero.
+    pub fn with_shards(shards: usize, span: usize) -> Self {
+        assert!(shards > 0 && span > 0, "the shards and the span must not be empty");
+        ShardedBitSet {
+            shards: (0..shards).map(|_| Mutex::new(BitSet::default())).collect(),
+            span: span.div_ceil(B::bits()) * B::bits(),
+        }
+    }
+
+    /// Returns the number of shards.
+    #[inline]
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Returns the shard holding `value`, and the value within it.
+    fn locate(&self, value: usize) -> (usize, usize) {
+        let (chunk, offset) = (value / self.span, value % self.span);
+        let n = self.shards.len();
+        (chunk % n, chunk / n * self.span + offset)
+    }
+
+    fn lock(&self, shard: usize) -> MutexGuard<'_, BitSet<B>> {
+        // A panic while holding the lock can't break the invariants of the
+        // set, so its contents are still usable
+        self.shards[shard].lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Adds a value to the set. Returns `true` if the value was not already
+    /// present in the set.
+    pub fn insert(&self, value: usize) -> bool {
+        let (shard, local) = self.locate(value);
+        self.lock(shard).insert(local)
+    }
+
+    /// Removes a value from the set. Returns `true` if the value was
+    /// present in the set.
+    pub fn remove(&self, value: usize) -> bool {
+        let (shard, local) = self.locate(value);
+        self.lock(shard).remove(local)
+    }
+
+    /// Returns `true` if the set contains the specified value.
+    pub fn contains(&self, value: usize) -> bool {
+        let (shard, local) = self.locate(value);
+        self.lock(shard).contains(local)
+    }
+
+    /// Returns the number of values in the set, locking the shards in turn.
+    pub fn len(&self) -> usize {
+        (0..self.shards.len()).map(|shard| self.lock(shard).len()).sum()
+    }
+
+    /// Returns `true` if the set holds no value, locking the shards in turn.
+    pub fn is_empty(&self) -> bool {
+        (0..self.shards.len()).all(|shard| self.lock(shard).is_empty())
+    }
+
+    /// Removes all values from the set, locking the shards in turn.
+    pub fn clear(&self) {
+        for shard in 0..self.shards.len() {
+            self.lock(shard).clear();
+        }
+    }
+
+    /// Copies the blocks of each shard into a set of the whole universe.
+    fn merge<'a, I>(&self, shards: I) -> BitSet<B> where I: Iterator<Item = &'a BitSet<B>>, B: 'a {
+        let n = self.shards.len();
+        let span_blocks = self.span / B::bits();
+        let mut blocks = Vec::new();
+        for (shard, set) in shards.enumerate() {
+            for (i, &block) in set.as_block_slice().iter().enumerate() {
+                if block == B::zero() {
+                    continue;
+                }
+                let chunk = i / span_blocks * n + shard;
+                let index = chunk * span_blocks + i % span_blocks;
+                if index >= blocks.len() {
+                    blocks.resize(index + 1, B::zero());
+                }
+                blocks[index] = block;
+            }
+        }
+        BitSet::from_blocks(blocks)
+    }
+
+    /// Returns the values of the set as a `BitSet`, copying the shards a
+    /// block at a time. The shards are locked in turn, so values added to
+    /// or removed from a shard while another is copied may or may not be
+    /// seen.
+    pub fn to_bit_set(&self) -> BitSet<B> {
+        let shards: Vec<_> = (0..self.shards.len()).map(|shard| self.lock(shard).clone()).collect();
+        self.merge(shards.iter())
+    }
+
+    /// Returns the values of the set as a `BitSet`, consuming the shards.
+    pub fn into_bit_set(self) -> BitSet<B> {
+        let shards: Vec<_> = self.shards.iter()
+            .map(|shard| {
+                let mut shard = shard.lock().unwrap_or_else(PoisonError::into_inner);
+                std::mem::take(&mut *shard)
+            })
+            .collect();
+        self.merge(shards.iter())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use BitSet;
+    use super::ShardedBitSet;
+
+    #[test]
+    fn test_sharded_bit_set() {
+        let set: ShardedBitSet<u8> = ShardedBitSet::with_shards(3, 10);
+        assert_eq!(set.span, 16);
+        let values = [0, 15, 16, 47, 48, 100, 1000, 5000];
+        for &v in &values {
+            assert!(set.insert(v));
+        }
+        assert!(!set.insert(48));
+        assert_eq!(set.len(), values.len());
+        assert!(set.contains(1000) && !set.contains(999));
+        let merged = set.to_bit_set();
+        assert_eq!(merged.iter().collect::<Vec<_>>(), values);
+        assert_eq!(merged.get_ref().len(), 5008);
+
+        assert!(set.remove(5000) && !set.remove(5000));
+        let merged = set.into_bit_set();
+        assert_eq!(merged.iter().collect::<Vec<_>>(), &values[..7]);
+        assert_eq!(merged.get_ref().len(), 1008);
+    }
+
+    #[test]
+    fn test_sharded_bit_set_threads() {
+        let set = ShardedBitSet::new();
+        std::thread::scope(|scope| {
+            for t in 0..8 {
+                let set = &set;
+                scope.spawn(move || {
+                    for v in (t..200_000).step_by(8) {
+                        set.insert(v * 3);
+                    }
+                });
+            }
+        });
+        let expected: BitSet = (0..200_000).map(|v| v * 3).collect();
+        assert_eq!(set.to_bit_set(), expected);
+        set.clear();
+        assert!(set.is_empty());
+    }
+}
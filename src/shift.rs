@@ -0,0 +1,293 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Moving all the elements of a set at once.
+
+use core::cmp;
+use core::ops::{Shl, ShlAssign, Shr, ShrAssign};
+
+use bit_vec::BitBlock;
+
+use {blocks_for_bits, primitive, BitSet};
+
+/// Reverses the order of the bits of `block`.
+fn reverse_bits<B: BitBlock>(block: B) -> B {
+    // The primitive integers use their own `reverse_bits`
+    if let Some(reversed) = primitive::reverse_bits(block) {
+        return reversed;
+    }
+    (0..B::bits()).fold(B::zero(), |acc, i| {
+        if (block >> i) & B::one() == B::one() { acc | (B::one() << (B::bits() - 1 - i)) } else { acc }
+    })
+}
+
+impl<B: BitBlock> BitSet<B> {
+    /// Adds `n` to every element of the set, shifting whole blocks.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an element would overflow.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s: BitSet = [0, 5, 31].iter().cloned().collect();
+    /// s.shift_up(3);
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), [3, 8, 34]);
+    /// ```
+    pub fn shift_up(&mut self, n: usize) {
+        let len = self.bit_vec.len();
+        if n == 0 || len == 0 {
+            return;
+        }
+        let nbits = len.checked_add(n).expect("capacity overflow");
+        let (q, r) = (n / B::bits(), n % B::bits());
+        let old_blocks = self.bit_vec.storage().len();
+        unsafe {
+            let blocks = self.bit_vec.storage_mut();
+            blocks.resize(blocks_for_bits::<B>(nbits), B::zero());
+            for i in (0..blocks.len()).rev() {
+                // The source blocks holding the bits moving to block `i`
+                let load = |j: Option<usize>| match j {
+                    Some(j) if j < old_blocks => blocks[j],
+                    _ => B::zero(),
+                };
+                let hi = load(i.checked_sub(q));
+                blocks[i] = if r == 0 {
+                    hi
+                } else {
+                    (hi << r) | (load(i.checked_sub(q + 1)) >> (B::bits() - r))
+                };
+            }
+            self.bit_vec.set_len(nbits);
+        }
+    }
+
+    /// Subtracts `n` from every element of the set, dropping the elements
+    /// smaller than `n`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s: BitSet = [0, 5, 40].iter().cloned().collect();
+    /// s.shift_down(3);
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), [2, 37]);
+    /// ```
+    pub fn shift_down(&mut self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        let nbits = self.bit_vec.len().saturating_sub(n);
+        let (q, r) = (n / B::bits(), n % B::bits());
+        let new_blocks = blocks_for_bits::<B>(nbits);
+        unsafe {
+            let blocks = self.bit_vec.storage_mut();
+            for i in 0..new_blocks {
+                let load = |j: usize| blocks.get(j).cloned().unwrap_or(B::zero());
+                let lo = load(i + q);
+                blocks[i] = if r == 0 {
+                    lo
+                } else {
+                    (lo >> r) | (load(i + q + 1) << (B::bits() - r))
+                };
+            }
+            // The bits past the old length are zero, so those past the new
+            // one are too
+            blocks.truncate(new_blocks);
+            self.bit_vec.set_len(nbits);
+        }
+    }
+
+    /// Removes the elements smaller than `universe` from the set and returns
+    /// them as a new set.
+    fn split_below(&mut self, universe: usize) -> BitSet<B> {
+        let nblocks = cmp::min(blocks_for_bits::<B>(universe), self.bit_vec.storage().len());
+        let mut low = BitSet::from_blocks(self.bit_vec.storage()[..nblocks].to_vec());
+        low.truncate(universe);
+        let (q, r) = (universe / B::bits(), universe % B::bits());
+        unsafe {
+            for (i, block) in self.bit_vec.storage_mut()[..nblocks].iter_mut().enumerate() {
+                // The block holding `universe` keeps the bits from it onwards
+                *block = if i < q { B::zero() } else { (*block >> r) << r };
+            }
+        }
+        low
+    }
+
+    /// Rotates the elements smaller than `universe` by `n` within
+    /// `0..universe`: element `i` becomes `(i + n) % universe`. The elements
+    /// at or past `universe` are left as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut wheel: BitSet = [0, 3, 9, 12].iter().cloned().collect();
+    /// wheel.rotate_within(10, 2);
+    /// assert_eq!(wheel.iter().collect::<Vec<_>>(), [1, 2, 5, 12]);
+    /// ```
+    pub fn rotate_within(&mut self, universe: usize, n: usize) {
+        if universe == 0 || n.is_multiple_of(universe) {
+            return;
+        }
+        let n = n % universe;
+        let mut low = self.split_below(universe);
+        let mut wrapped = low.clone();
+        low.shift_up(n);
+        low.truncate(universe);
+        wrapped.shift_down(universe - n);
+        low.union_with(&wrapped);
+        self.union_with(&low);
+    }
+
+    /// Mirrors the elements smaller than `universe` within `0..universe`:
+    /// element `i` becomes `universe - 1 - i`. The elements at or past
+    /// `universe` are left as they are.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let mut s: BitSet = [0, 1, 5, 12].iter().cloned().collect();
+    /// s.reverse_within(8);
+    /// assert_eq!(s.iter().collect::<Vec<_>>(), [2, 6, 7, 12]);
+    /// ```
+    pub fn reverse_within(&mut self, universe: usize) {
+        let nblocks = blocks_for_bits::<B>(universe);
+        let low = self.split_below(universe);
+        // Reversing the order of the blocks and of the bits of each mirrors
+        // `0..nblocks * bits`, which is then brought down to `0..universe`
+        let storage = low.bit_vec.storage();
+        let blocks = (0..nblocks).rev()
+            .map(|i| reverse_bits(storage.get(i).cloned().unwrap_or(B::zero())))
+            .collect();
+        let mut reversed = BitSet::from_blocks(blocks);
+        reversed.shift_down(nblocks * B::bits() - universe);
+        self.union_with(&reversed);
+    }
+}
+
+impl<B: BitBlock> Shl<usize> for BitSet<B> {
+    type Output = Self;
+
+    /// Adds `n` to every element, like [`shift_up`](#method.shift_up).
+    #[inline]
+    fn shl(mut self, n: usize) -> Self {
+        self.shift_up(n);
+        self
+    }
+}
+
+impl<B: BitBlock> ShlAssign<usize> for BitSet<B> {
+    #[inline]
+    fn shl_assign(&mut self, n: usize) {
+        self.shift_up(n);
+    }
+}
+
+impl<B: BitBlock> Shr<usize> for BitSet<B> {
+    type Output = Self;
+
+    /// Subtracts `n` from every element, like
+    /// [`shift_down`](#method.shift_down).
+    #[inline]
+    fn shr(mut self, n: usize) -> Self {
+        self.shift_down(n);
+        self
+    }
+}
+
+impl<B: BitBlock> ShrAssign<usize> for BitSet<B> {
+    #[inline]
+    fn shr_assign(&mut self, n: usize) {
+        self.shift_down(n);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use primitive::tests::Wrapped;
+    use BitSet;
+    use super::reverse_bits;
+
+    #[test]
+    fn test_shift() {
+        let values = [0, 1, 7, 8, 9, 30, 31, 32, 63, 64, 100];
+        let s: BitSet<u8> = values.iter().cloned().collect();
+        for n in 0..80 {
+            let up = s.clone() << n;
+            let expected: Vec<_> = values.iter().map(|v| v + n).collect();
+            assert_eq!(up.iter().collect::<Vec<_>>(), expected);
+            assert_eq!(up.get_ref().len(), 101 + n);
+
+            let mut down = up.clone();
+            down >>= n;
+            assert_eq!(down, s);
+
+            let down = s.clone() >> n;
+            let expected: Vec<_> = values.iter().filter(|&&v| v >= n).map(|v| v - n).collect();
+            assert_eq!(down.iter().collect::<Vec<_>>(), expected);
+            assert_eq!(down.get_ref().len(), 101 - n);
+        }
+        assert!((s.clone() >> 1000).is_empty());
+
+        let mut empty: BitSet = BitSet::new();
+        empty <<= 10;
+        assert_eq!(empty.get_ref().len(), 0);
+    }
+
+    #[test]
+    fn test_rotate_within() {
+        let values = [0, 1, 7, 8, 9, 30, 31, 32, 63, 64, 100];
+        let s: BitSet<u8> = values.iter().cloned().collect();
+        for universe in 0..110 {
+            for n in [0, 1, 7, 8, 9, 15, 16, 17, 64, universe, 3 * universe + 5] {
+                let mut rotated = s.clone();
+                rotated.rotate_within(universe, n);
+                let expected: BitSet<u8> = values.iter()
+                    .map(|&v| if v < universe { (v + n) % universe } else { v })
+                    .collect();
+                assert_eq!(rotated.iter().collect::<Vec<_>>(),
+                           expected.iter().collect::<Vec<_>>(),
+                           "universe {} n {}", universe, n);
+            }
+        }
+    }
+
+    #[test]
+    fn test_reverse_within() {
+        let values = [0, 1, 7, 8, 9, 30, 31, 32, 63, 64, 100];
+        let s: BitSet<u8> = values.iter().cloned().collect();
+        for universe in 0..110 {
+            let mut reversed = s.clone();
+            reversed.reverse_within(universe);
+            let expected: BitSet<u8> = values.iter()
+                .map(|&v| if v < universe { universe - 1 - v } else { v })
+                .collect();
+            assert_eq!(reversed.iter().collect::<Vec<_>>(),
+                       expected.iter().collect::<Vec<_>>(),
+                       "universe {}", universe);
+            reversed.reverse_within(universe);
+            assert_eq!(reversed.iter().collect::<Vec<_>>(), values);
+        }
+    }
+
+    #[test]
+    fn test_reverse_bits() {
+        assert_eq!(reverse_bits(0b0000_0110u8), 0b0110_0000);
+        assert_eq!(reverse_bits(1u32), 1 << 31);
+        assert_eq!(reverse_bits(0x0123_4567_89ab_cdefu64), 0x0123_4567_89ab_cdefu64.reverse_bits());
+        assert_eq!(reverse_bits(Wrapped(0x0001_8421)), Wrapped(0x0001_8421u32.reverse_bits()));
+    }
+}
@@ -0,0 +1,392 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Vectorized kernels for the bulk operations, enabled by the `simd`
+//! feature.
+//!
+//! The kernels work on the bytes of the blocks, which is only valid because
+//! the bitwise operations and the population count don't depend on how the
+//! bytes are grouped into blocks. They are only used for the primitive
+//! integers; other block types may have padding bytes, or operators that
+//! aren't bitwise, and go through the generic code.
+//!
+//! On `x86_64` the widest of AVX-512, AVX2 and SSE2 supported by the CPU is
+//! used. With the `std` feature it is detected at runtime, so a single binary
+//! picks the fastest kernels on each machine; without it, only the target
+//! features enabled at compile time are used. On `aarch64` NEON is always
+//! available. Other targets use a loop over `u64` words that the compiler can
+//! vectorize.
+
+use bit_vec::BitBlock;
+
+use kernels::Op;
+use primitive;
+
+impl Op {
+    #[inline]
+    fn scalar(self, a: u64, b: u64) -> u64 {
+        match self {
+            Op::Or => a | b,
+            Op::And => a & b,
+            Op::AndNot => a & !b,
+            Op::Xor => a ^ b,
+        }
+    }
+}
+
+/// Returns `true` if the blocks of type `B` can be processed as bytes,
+/// which only holds for the primitive integers implementing `BitBlock`.
+#[inline]
+pub(crate) fn is_supported<B: BitBlock>() -> bool {
+    primitive::is_primitive::<B>()
+}
+
+fn as_bytes<B: BitBlock>(blocks: &[B]) -> &[u8] {
+    primitive::as_bytes(blocks).expect("blocks are not primitive integers")
+}
+
+fn as_bytes_mut<B: BitBlock>(blocks: &mut [B]) -> &mut [u8] {
+    primitive::as_bytes_mut(blocks).expect("blocks are not primitive integers")
+}
+
+/// Sets each block of `dst` to the result of `op` on it and the block of
+/// `src` at the same index. Both slices must have the same length.
+pub(crate) fn apply<B: BitBlock>(dst: &mut [B], src: &[B], op: Op) {
+    assert_eq!(dst.len(), src.len());
+    imp::apply(as_bytes_mut(dst), as_bytes(src), op);
+}
+
+/// Returns the number of set bits in `blocks`.
+pub(crate) fn count_ones<B: BitBlock>(blocks: &[B]) -> usize {
+    imp::count_ones(as_bytes(blocks))
+}
+
+/// Kernels processing 8 bytes at a time, used on their own on targets
+/// without explicit kernels and for the remainders otherwise.
+mod portable {
+    use super::Op;
+
+    fn word(bytes: &[u8]) -> u64 {
+        let mut word = [0; 8];
+        word[..bytes.len()].copy_from_slice(bytes);
+        u64::from_ne_bytes(word)
+    }
+
+    pub fn apply(dst: &mut [u8], src: &[u8], op: Op) {
+        let mut dst_words = dst.chunks_exact_mut(8);
+        let mut src_words = src.chunks_exact(8);
+        for (d, s) in (&mut dst_words).zip(&mut src_words) {
+            d.copy_from_slice(&op.scalar(word(d), word(s)).to_ne_bytes());
+        }
+        for (d, &s) in dst_words.into_remainder().iter_mut().zip(src_words.remainder()) {
+            *d = op.scalar(*d as u64, s as u64) as u8;
+        }
+    }
+
+    pub fn count_ones(bytes: &[u8]) -> usize {
+        let words = bytes.chunks_exact(8);
+        let tail = word(words.remainder()).count_ones() as usize;
+        words.map(|w| word(w).count_ones() as usize).sum::<usize>() + tail
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use core::arch::x86_64::*;
+
+    use super::{portable, Op};
+
+    /// Applies `op` 16 bytes at a time with SSE2, which every `x86_64` CPU
+    /// supports.
+    pub fn apply_sse2(dst: &mut [u8], src: &[u8], op: Op) {
+        let n = dst.len() / 16 * 16;
+        for i in (0..n).step_by(16) {
+            unsafe {
+                let d = dst.as_mut_ptr().add(i) as *mut __m128i;
+                let a = _mm_loadu_si128(d);
+                let b = _mm_loadu_si128(src.as_ptr().add(i) as *const __m128i);
+                let r = match op {
+                    Op::Or => _mm_or_si128(a, b),
+                    Op::And => _mm_and_si128(a, b),
+                    Op::AndNot => _mm_andnot_si128(b, a),
+                    Op::Xor => _mm_xor_si128(a, b),
+                };
+                _mm_storeu_si128(d, r);
+            }
+        }
+        portable::apply(&mut dst[n..], &src[n..], op);
+    }
+
+    /// Applies `op` 32 bytes at a time.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn apply_avx2(dst: &mut [u8], src: &[u8], op: Op) {
+        let n = dst.len() / 32 * 32;
+        for i in (0..n).step_by(32) {
+            let d = dst.as_mut_ptr().add(i) as *mut __m256i;
+            let a = _mm256_loadu_si256(d);
+            let b = _mm256_loadu_si256(src.as_ptr().add(i) as *const __m256i);
+            let r = match op {
+                Op::Or => _mm256_or_si256(a, b),
+                Op::And => _mm256_and_si256(a, b),
+                Op::AndNot => _mm256_andnot_si256(b, a),
+                Op::Xor => _mm256_xor_si256(a, b),
+            };
+            _mm256_storeu_si256(d, r);
+        }
+        portable::apply(&mut dst[n..], &src[n..], op);
+    }
+
+    /// Counts the set bits 32 bytes at a time, looking up the count of each
+    /// nibble with a shuffle.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn count_ones_avx2(bytes: &[u8]) -> usize {
+        let lookup = _mm256_setr_epi8(
+            0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4,
+            0, 1, 1, 2, 1, 2, 2, 3, 1, 2, 2, 3, 2, 3, 3, 4);
+        let low_nibbles = _mm256_set1_epi8(0x0f);
+        let mut total = _mm256_setzero_si256();
+        let n = bytes.len() / 32 * 32;
+        for i in (0..n).step_by(32) {
+            let v = _mm256_loadu_si256(bytes.as_ptr().add(i) as *const __m256i);
+            let lo = _mm256_and_si256(v, low_nibbles);
+            let hi = _mm256_and_si256(_mm256_srli_epi16(v, 4), low_nibbles);
+            let counts = _mm256_add_epi8(_mm256_shuffle_epi8(lookup, lo),
+                                         _mm256_shuffle_epi8(lookup, hi));
+            // Sum the byte counts into the four 64-bit lanes
+            total = _mm256_add_epi64(total, _mm256_sad_epu8(counts, _mm256_setzero_si256()));
+        }
+        let mut lanes = [0u64; 4];
+        _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, total);
+        lanes.iter().sum::<u64>() as usize + portable::count_ones(&bytes[n..])
+    }
+
+    /// Applies `op` 64 bytes at a time.
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX-512F.
+    #[target_feature(enable = "avx512f")]
+    pub unsafe fn apply_avx512(dst: &mut [u8], src: &[u8], op: Op) {
+        let n = dst.len() / 64 * 64;
+        for i in (0..n).step_by(64) {
+            let d = dst.as_mut_ptr().add(i) as *mut __m512i;
+            let a = _mm512_loadu_si512(d);
+            let b = _mm512_loadu_si512(src.as_ptr().add(i) as *const __m512i);
+            let r = match op {
+                Op::Or => _mm512_or_si512(a, b),
+                Op::And => _mm512_and_si512(a, b),
+                Op::AndNot => _mm512_andnot_si512(b, a),
+                Op::Xor => _mm512_xor_si512(a, b),
+            };
+            _mm512_storeu_si512(d, r);
+        }
+        apply_avx2(&mut dst[n..], &src[n..], op);
+    }
+
+    /// Counts the set bits 64 bytes at a time, like
+    /// [count_ones_avx2](fn.count_ones_avx2.html).
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX-512F and AVX-512BW.
+    #[target_feature(enable = "avx512f,avx512bw")]
+    pub unsafe fn count_ones_avx512(bytes: &[u8]) -> usize {
+        let lookup = _mm512_set4_epi32(0x0403_0302, 0x0302_0201, 0x0302_0201, 0x0201_0100);
+        let low_nibbles = _mm512_set1_epi8(0x0f);
+        let mut total = _mm512_setzero_si512();
+        let n = bytes.len() / 64 * 64;
+        for i in (0..n).step_by(64) {
+            let v = _mm512_loadu_si512(bytes.as_ptr().add(i) as *const __m512i);
+            let lo = _mm512_and_si512(v, low_nibbles);
+            let hi = _mm512_and_si512(_mm512_srli_epi16(v, 4), low_nibbles);
+            let counts = _mm512_add_epi8(_mm512_shuffle_epi8(lookup, lo),
+                                         _mm512_shuffle_epi8(lookup, hi));
+            total = _mm512_add_epi64(total, _mm512_sad_epu8(counts, _mm512_setzero_si512()));
+        }
+        _mm512_reduce_add_epi64(total) as usize + count_ones_avx2(&bytes[n..])
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod imp {
+    use super::{x86, Op};
+
+    /// The widest instruction set available for the kernels.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Level {
+        Sse2,
+        Avx2,
+        Avx512,
+    }
+
+    #[cfg(feature = "std")]
+    pub fn level() -> Level {
+        // The detection macros cache their result, so this is cheap
+        if is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("avx512bw") {
+            Level::Avx512
+        } else if is_x86_feature_detected!("avx2") {
+            Level::Avx2
+        } else {
+            Level::Sse2
+        }
+    }
+
+    #[cfg(not(feature = "std"))]
+    pub fn level() -> Level {
+        if cfg!(all(target_feature = "avx512f", target_feature = "avx512bw")) {
+            Level::Avx512
+        } else if cfg!(target_feature = "avx2") {
+            Level::Avx2
+        } else {
+            Level::Sse2
+        }
+    }
+
+    pub fn apply(dst: &mut [u8], src: &[u8], op: Op) {
+        match level() {
+            Level::Avx512 => unsafe { x86::apply_avx512(dst, src, op) },
+            Level::Avx2 => unsafe { x86::apply_avx2(dst, src, op) },
+            Level::Sse2 => x86::apply_sse2(dst, src, op),
+        }
+    }
+
+    pub fn count_ones(bytes: &[u8]) -> usize {
+        match level() {
+            Level::Avx512 => unsafe { x86::count_ones_avx512(bytes) },
+            Level::Avx2 => unsafe { x86::count_ones_avx2(bytes) },
+            Level::Sse2 => super::portable::count_ones(bytes),
+        }
+    }
+}
+
+#[cfg(target_arch = "aarch64")]
+mod imp {
+    use core::arch::aarch64::*;
+
+    use super::{portable, Op};
+
+    pub fn apply(dst: &mut [u8], src: &[u8], op: Op) {
+        let n = dst.len() / 16 * 16;
+        for i in (0..n).step_by(16) {
+            unsafe {
+                let d = dst.as_mut_ptr().add(i);
+                let a = vld1q_u8(d);
+                let b = vld1q_u8(src.as_ptr().add(i));
+                let r = match op {
+                    Op::Or => vorrq_u8(a, b),
+                    Op::And => vandq_u8(a, b),
+                    Op::AndNot => vbicq_u8(a, b),
+                    Op::Xor => veorq_u8(a, b),
+                };
+                vst1q_u8(d, r);
+            }
+        }
+        portable::apply(&mut dst[n..], &src[n..], op);
+    }
+
+    pub fn count_ones(bytes: &[u8]) -> usize {
+        let n = bytes.len() / 16 * 16;
+        let mut total = 0;
+        for i in (0..n).step_by(16) {
+            total += unsafe { vaddlvq_u8(vcntq_u8(vld1q_u8(bytes.as_ptr().add(i)))) } as usize;
+        }
+        total + portable::count_ones(&bytes[n..])
+    }
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+use self::portable as imp;
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::{apply, count_ones, is_supported, portable, Op};
+
+    fn pattern(len: usize, seed: u64) -> Vec<u64> {
+        (0..len as u64).map(|i| (i + seed).wrapping_mul(0x9e37_79b9_7f4a_7c15).rotate_left(17)).collect()
+    }
+
+    #[test]
+    fn test_simd_kernels_match_scalar() {
+        assert!(is_supported::<u8>() && is_supported::<u64>());
+        for &op in [Op::Or, Op::And, Op::AndNot, Op::Xor].iter() {
+            for len in 0..40 {
+                let (a, b) = (pattern(len, 1), pattern(len, 2));
+                let expected: Vec<u64> = a.iter().zip(&b).map(|(&x, &y)| op.scalar(x, y)).collect();
+                let mut dst = a.clone();
+                apply(&mut dst, &b, op);
+                assert_eq!(dst, expected);
+            }
+        }
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn test_simd_x86_kernels() {
+        use super::x86;
+
+        let (a, b) = (pattern(37, 4), pattern(37, 5));
+        let (a, b) = (super::as_bytes(&a), super::as_bytes(&b));
+        let mut expected = a.to_vec();
+        portable::apply(&mut expected, b, Op::Xor);
+        let count = portable::count_ones(a);
+
+        let mut dst = a.to_vec();
+        x86::apply_sse2(&mut dst, b, Op::Xor);
+        assert_eq!(dst, expected);
+        if is_x86_feature_detected!("avx2") {
+            let mut dst = a.to_vec();
+            unsafe { x86::apply_avx2(&mut dst, b, Op::Xor) };
+            assert_eq!(dst, expected);
+            assert_eq!(unsafe { x86::count_ones_avx2(a) }, count);
+        }
+        if is_x86_feature_detected!("avx512f") && is_x86_feature_detected!("avx512bw") {
+            let mut dst = a.to_vec();
+            unsafe { x86::apply_avx512(&mut dst, b, Op::Xor) };
+            assert_eq!(dst, expected);
+            assert_eq!(unsafe { x86::count_ones_avx512(a) }, count);
+        }
+    }
+
+    #[test]
+    fn test_simd_skips_non_primitive_blocks() {
+        use primitive::tests::Wrapped;
+        use BitSet;
+
+        assert!(!is_supported::<Wrapped>());
+        let mut a: BitSet<Wrapped> = BitSet::default();
+        let mut b: BitSet<Wrapped> = BitSet::default();
+        a.extend([1, 40, 100]);
+        b.extend([40, 70]);
+        a.union_with(&b);
+        assert_eq!(a.len(), 4);
+        a.intersect_with(&b);
+        assert_eq!(a.iter().collect::<Vec<_>>(), [40, 70]);
+    }
+
+    #[test]
+    fn test_simd_count_ones() {
+        for len in 0..40 {
+            let words = pattern(len, 3);
+            let expected: usize = words.iter().map(|w| w.count_ones() as usize).sum();
+            assert_eq!(count_ones(&words), expected);
+
+            let bytes: Vec<u8> = words.iter().map(|&w| w as u8).collect();
+            let expected: usize = bytes.iter().map(|b| b.count_ones() as usize).sum();
+            assert_eq!(count_ones(&bytes), expected);
+            assert_eq!(portable::count_ones(&bytes), expected);
+        }
+    }
+}
@@ -0,0 +1,95 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Similarity coefficients between two sets.
+
+use core::cmp;
+
+use bit_vec::BitBlock;
+
+use BitSet;
+
+/// Returns `numerator / denominator`, or 1 if the denominator is zero.
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 { 1.0 } else { numerator as f64 / denominator as f64 }
+}
+
+impl<B: BitBlock> BitSet<B> {
+    /// Counts the values of the intersection, of `self` and of `other` in
+    /// one pass over the blocks of both sets.
+    fn overlap_counts(&self, other: &Self) -> (usize, usize, usize) {
+        let (a, b) = (self.bit_vec.storage(), other.bit_vec.storage());
+        let mut counts = (0, 0, 0);
+        for i in 0..cmp::max(a.len(), b.len()) {
+            let x = a.get(i).cloned().unwrap_or(B::zero());
+            let y = b.get(i).cloned().unwrap_or(B::zero());
+            counts.0 += (x & y).count_ones();
+            counts.1 += x.count_ones();
+            counts.2 += y.count_ones();
+        }
+        counts
+    }
+
+    /// Returns the Jaccard index of the two sets, the size of their
+    /// intersection over the size of their union. Two empty sets have an
+    /// index of 1.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let a: BitSet = [1, 2, 3, 4].iter().cloned().collect();
+    /// let b: BitSet = [3, 4, 5, 6].iter().cloned().collect();
+    /// assert_eq!(a.jaccard(&b), 2.0 / 6.0);
+    /// assert_eq!(a.dice(&b), 0.5);
+    /// assert_eq!(a.overlap_coefficient(&b), 0.5);
+    /// ```
+    pub fn jaccard(&self, other: &Self) -> f64 {
+        let (both, a, b) = self.overlap_counts(other);
+        ratio(both, a + b - both)
+    }
+
+    /// Returns the Sørensen–Dice coefficient of the two sets, twice the size
+    /// of their intersection over the sum of their sizes. Two empty sets
+    /// have a coefficient of 1.
+    pub fn dice(&self, other: &Self) -> f64 {
+        let (both, a, b) = self.overlap_counts(other);
+        ratio(2 * both, a + b)
+    }
+
+    /// Returns the overlap coefficient of the two sets, the size of their
+    /// intersection over the size of the smaller one. It is 1 when one set
+    /// is a subset of the other, including when one is empty.
+    pub fn overlap_coefficient(&self, other: &Self) -> f64 {
+        let (both, a, b) = self.overlap_counts(other);
+        ratio(both, cmp::min(a, b))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use BitSet;
+
+    #[test]
+    fn test_similarity() {
+        let a: BitSet<u8> = (0..30).collect();
+        let b: BitSet<u8> = (20..100).collect();
+        assert_eq!(a.overlap_counts(&b), (10, 30, 80));
+        assert_eq!(a.jaccard(&b), 10.0 / 100.0);
+        assert_eq!(b.jaccard(&a), 10.0 / 100.0);
+        assert_eq!(a.dice(&b), 20.0 / 110.0);
+        assert_eq!(a.overlap_coefficient(&b), 10.0 / 30.0);
+        assert_eq!(a.jaccard(&a), 1.0);
+
+        let empty = BitSet::default();
+        assert_eq!(empty.jaccard(&empty), 1.0);
+        assert_eq!(empty.dice(&empty), 1.0);
+        assert_eq!(a.jaccard(&empty), 0.0);
+        assert_eq!(a.dice(&empty), 0.0);
+        assert_eq!(a.overlap_coefficient(&empty), 1.0);
+    }
+}
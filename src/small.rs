@@ -0,0 +1,284 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Sets keeping their first blocks inline.
+
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::FromIterator;
+use core::ops::Deref;
+
+use bit_vec::BitBlock;
+
+use {BlockStorage, BlockStorageMut, GenericBitSet};
+
+/// Block storage holding up to `N` blocks inline, and moving them to the
+/// heap once more are needed.
+#[derive(Clone)]
+pub enum SmallBlocks<B, const N: usize> {
+    /// The blocks, inline.
+    Inline([B; N]),
+    /// The blocks, on the heap.
+    Heap(Vec<B>),
+}
+
+impl<B: BitBlock, const N: usize> SmallBlocks<B, N> {
+    /// Returns `true` if the blocks have moved to the heap.
+    #[inline]
+    pub fn is_spilled(&self) -> bool {
+        match *self {
+            SmallBlocks::Inline(_) => false,
+            SmallBlocks::Heap(_) => true,
+        }
+    }
+}
+
+impl<B: BitBlock, const N: usize> Default for SmallBlocks<B, N> {
+    #[inline]
+    fn default() -> Self {
+        SmallBlocks::Inline([B::zero(); N])
+    }
+}
+
+impl<B: BitBlock, const N: usize> BlockStorage for SmallBlocks<B, N> {
+    type Block = B;
+
+    #[inline]
+    fn blocks(&self) -> &[B] {
+        match *self {
+            SmallBlocks::Inline(ref blocks) => blocks,
+            SmallBlocks::Heap(ref blocks) => blocks,
+        }
+    }
+}
+
+impl<B: BitBlock, const N: usize> BlockStorageMut for SmallBlocks<B, N> {
+    #[inline]
+    fn blocks_mut(&mut self) -> &mut [B] {
+        match *self {
+            SmallBlocks::Inline(ref mut blocks) => blocks,
+            SmallBlocks::Heap(ref mut blocks) => blocks,
+        }
+    }
+
+    fn grow(&mut self, len: usize) -> bool {
+        if len <= self.blocks().len() {
+            return true;
+        }
+        let spilled = match *self {
+            // Resizing in place keeps the growth amortized
+            SmallBlocks::Heap(ref mut blocks) => {
+                blocks.resize(len, B::zero());
+                return true;
+            }
+            SmallBlocks::Inline(ref blocks) => {
+                let mut spilled = Vec::with_capacity(len);
+                spilled.extend_from_slice(blocks);
+                spilled.resize(len, B::zero());
+                spilled
+            }
+        };
+        *self = SmallBlocks::Heap(spilled);
+        true
+    }
+}
+
+/// A set storing values below `N * B::bits()` inline, without allocating,
+/// and moving to the heap transparently when a larger value is added.
+///
+/// The default of four `u32` blocks holds values below 128 inline. The read
+/// methods are those of [`GenericBitSet`](struct.GenericBitSet.html).
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::SmallBitSet;
+///
+/// let mut s: SmallBitSet = SmallBitSet::new();
+/// s.insert(3);
+/// s.insert(127);
+/// assert!(!s.is_spilled());
+///
+/// s.insert(1000);
+/// assert!(s.is_spilled());
+/// assert_eq!(s.iter().collect::<Vec<_>>(), [3, 127, 1000]);
+/// ```
+#[derive(Clone, Default)]
+pub struct SmallBitSet<B: BitBlock = u32, const N: usize = 4> {
+    set: GenericBitSet<SmallBlocks<B, N>>,
+}
+
+impl<B: BitBlock, const N: usize> SmallBitSet<B, N> {
+    /// Creates a new empty `SmallBitSet`, without allocating.
+    #[inline]
+    pub fn new() -> Self {
+        SmallBitSet { set: GenericBitSet::new(SmallBlocks::default()) }
+    }
+
+    /// Returns `true` if the blocks have moved to the heap.
+    #[inline]
+    pub fn is_spilled(&self) -> bool {
+        self.set.storage().is_spilled()
+    }
+
+    /// Adds a value to the set. Returns `true` if the value was not already
+    /// present in the set.
+    #[inline]
+    pub fn insert(&mut self, value: usize) -> bool {
+        self.set.insert(value).expect("spilling storage always grows")
+    }
+
+    /// Removes a value from the set. Returns `true` if the value was
+    /// present in the set.
+    #[inline]
+    pub fn remove(&mut self, value: usize) -> bool {
+        self.set.remove(value)
+    }
+
+    /// Removes all values from the set, keeping the storage.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.set.clear()
+    }
+
+    /// Unions in-place with `other`.
+    #[inline]
+    pub fn union_with<T>(&mut self, other: &T) where T: BlockStorage<Block = B> + ?Sized {
+        self.set.union_with(other).expect("spilling storage always grows")
+    }
+
+    /// Intersects in-place with `other`.
+    #[inline]
+    pub fn intersect_with<T>(&mut self, other: &T) where T: BlockStorage<Block = B> + ?Sized {
+        self.set.intersect_with(other)
+    }
+
+    /// Removes the values of `other` from the set.
+    #[inline]
+    pub fn difference_with<T>(&mut self, other: &T) where T: BlockStorage<Block = B> + ?Sized {
+        self.set.difference_with(other)
+    }
+
+    /// Makes the set the symmetric difference with `other`.
+    #[inline]
+    pub fn symmetric_difference_with<T>(&mut self, other: &T)
+        where T: BlockStorage<Block = B> + ?Sized
+    {
+        self.set.symmetric_difference_with(other).expect("spilling storage always grows")
+    }
+
+    /// Returns the underlying `GenericBitSet`.
+    #[inline]
+    pub fn into_inner(self) -> GenericBitSet<SmallBlocks<B, N>> {
+        self.set
+    }
+}
+
+impl<B: BitBlock, const N: usize> Deref for SmallBitSet<B, N> {
+    type Target = GenericBitSet<SmallBlocks<B, N>>;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.set
+    }
+}
+
+impl<B: BitBlock, const N: usize> BlockStorage for SmallBitSet<B, N> {
+    type Block = B;
+
+    #[inline]
+    fn blocks(&self) -> &[B] {
+        self.set.blocks()
+    }
+}
+
+impl<B: BitBlock, const N: usize> PartialEq for SmallBitSet<B, N> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.iter().eq(other.iter())
+    }
+}
+
+impl<B: BitBlock, const N: usize> Eq for SmallBitSet<B, N> {}
+
+impl<B: BitBlock, const N: usize> Extend<usize> for SmallBitSet<B, N> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<B: BitBlock, const N: usize> FromIterator<usize> for SmallBitSet<B, N> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = SmallBitSet::new();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<B: BitBlock, const N: usize> fmt::Debug for SmallBitSet<B, N> {
+    #[inline]
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        self.set.fmt(fmt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use BitSet;
+    use super::{SmallBitSet, SmallBlocks};
+
+    #[test]
+    fn test_small_bit_set() {
+        let mut s: SmallBitSet<u8, 2> = SmallBitSet::new();
+        assert_eq!(s.capacity(), 16);
+        s.extend(vec![0, 7, 15]);
+        assert!(!s.is_spilled());
+        assert_eq!(s.len(), 3);
+
+        let other: BitSet<u8> = [7, 40].iter().cloned().collect();
+        s.union_with(&other);
+        assert!(s.is_spilled());
+        assert_eq!(s.iter().collect::<Vec<_>>(), [0, 7, 15, 40]);
+        assert_eq!(s.capacity(), 48);
+
+        s.symmetric_difference_with(&other);
+        assert_eq!(format!("{:?}", s), "{0, 15}");
+        assert!(s.remove(15) && !s.remove(15));
+        s.clear();
+        assert!(s.is_empty() && s.is_spilled());
+
+        let a: SmallBitSet = [1, 2, 3].iter().cloned().collect();
+        let mut b: SmallBitSet = [1, 2, 3, 500].iter().cloned().collect();
+        assert!(a != b && a.is_subset(&b));
+        b.intersect_with(&*a);
+        assert_eq!(a, b);
+        b.difference_with(&[0b10u32][..]);
+        assert_eq!(b.iter().collect::<Vec<_>>(), [2, 3]);
+    }
+
+    #[test]
+    fn test_small_bit_set_grows_in_place() {
+        let mut s: SmallBitSet<u8, 2> = SmallBitSet::new();
+        let mut reallocations = 0;
+        let mut capacity = 0;
+        for value in 0..4096 {
+            s.insert(value);
+            if let SmallBlocks::Heap(ref blocks) = *s.set.storage() {
+                if blocks.capacity() != capacity {
+                    capacity = blocks.capacity();
+                    reallocations += 1;
+                }
+            }
+        }
+        assert_eq!(s.len(), 4096);
+        assert!(reallocations < 16, "{} reallocations", reallocations);
+    }
+}
@@ -0,0 +1,414 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A set storing only the chunks of blocks that hold values.
+
+use alloc::collections::btree_map::{self, BTreeMap, Entry};
+use alloc::vec::Vec;
+use core::fmt;
+use core::iter::{self, Cloned, FromIterator};
+use core::slice;
+
+use bit_vec::BitBlock;
+
+use {BitSet, BlockIter};
+
+/// The number of values covered by each chunk of blocks.
+const CHUNK_BITS: usize = 512;
+
+fn chunk_len<B: BitBlock>() -> usize {
+    CHUNK_BITS / B::bits()
+}
+
+fn is_zero<B: BitBlock>(blocks: &[B]) -> bool {
+    blocks.iter().all(|&b| b == B::zero())
+}
+
+/// A set storing its values in chunks of 512 bits, keeping only the chunks
+/// that hold at least one value in a `BTreeMap`.
+///
+/// Memory use is proportional to the number of non-empty chunks rather than
+/// to the largest value, so values can be spread over the whole `usize`
+/// range. Operations touch the chunks of the operands only.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::SparseBitSet;
+///
+/// let mut s = SparseBitSet::new();
+/// s.insert(4_000_000_000);
+/// s.insert(7);
+///
+/// assert!(s.contains(4_000_000_000));
+/// assert_eq!(s.iter().collect::<Vec<_>>(), [7, 4_000_000_000]);
+/// ```
+#[derive(Clone, PartialEq, Eq, Hash)]
+pub struct SparseBitSet<B = u32> {
+    /// Non-zero chunks, keyed by the index of their first value divided by
+    /// `CHUNK_BITS`.
+    chunks: BTreeMap<usize, Vec<B>>,
+}
+
+impl<B: BitBlock> Default for SparseBitSet<B> {
+    #[inline]
+    fn default() -> Self {
+        SparseBitSet { chunks: BTreeMap::new() }
+    }
+}
+
+impl SparseBitSet<u32> {
+    /// Creates a new empty `SparseBitSet`.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<B: BitBlock> SparseBitSet<B> {
+    /// Returns the chunk, block index and mask of `value`.
+    fn locate(value: usize) -> (usize, usize, B) {
+        let bit = value % CHUNK_BITS;
+        (value / CHUNK_BITS, bit / B::bits(), B::one() << (bit % B::bits()))
+    }
+
+    /// Returns `true` if this set contains the specified integer.
+    pub fn contains(&self, value: usize) -> bool {
+        let (chunk, block, mask) = Self::locate(value);
+        self.chunks.get(&chunk).is_some_and(|blocks| blocks[block] & mask != B::zero())
+    }
+
+    /// Adds a value to the set. Returns `true` if the value was not already
+    /// present in the set.
+    pub fn insert(&mut self, value: usize) -> bool {
+        let (chunk, block, mask) = Self::locate(value);
+        let blocks = self.chunks.entry(chunk)
+            .or_insert_with(|| iter::repeat_n(B::zero(), chunk_len::<B>()).collect());
+        let absent = blocks[block] & mask == B::zero();
+        blocks[block] = blocks[block] | mask;
+        absent
+    }
+
+    /// Removes a value from the set. Returns `true` if the value was
+    /// present in the set.
+    pub fn remove(&mut self, value: usize) -> bool {
+        let (chunk, block, mask) = Self::locate(value);
+        let mut entry = match self.chunks.entry(chunk) {
+            Entry::Occupied(entry) => entry,
+            Entry::Vacant(_) => return false,
+        };
+        let blocks = entry.get_mut();
+        let present = blocks[block] & mask != B::zero();
+        blocks[block] = blocks[block] & !mask;
+        if is_zero(blocks) {
+            entry.remove();
+        }
+        present
+    }
+
+    /// Returns the number of values in the set.
+    pub fn len(&self) -> usize {
+        self.chunks.values().flat_map(|blocks| blocks.iter()).map(|b| b.count_ones()).sum()
+    }
+
+    /// Returns `true` if the set holds no value.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    /// Removes all values from the set.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.chunks.clear();
+    }
+
+    /// Returns the number of chunks of 512 values holding at least one
+    /// value, each taking `512 / 8` bytes of storage.
+    #[inline]
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.len()
+    }
+
+    /// Iterator over each value in the set, in ascending order.
+    pub fn iter(&self) -> SparseIter<'_, B> {
+        SparseIter {
+            chunks: self.chunks.iter(),
+            base: 0,
+            values: BlockIter::from_blocks([].iter().cloned()),
+        }
+    }
+
+    /// Unions in-place with the specified other set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::SparseBitSet;
+    ///
+    /// let mut a: SparseBitSet = [1, 1 << 40].iter().cloned().collect();
+    /// let b: SparseBitSet = [2, 1 << 50].iter().cloned().collect();
+    /// a.union_with(&b);
+    /// assert_eq!(a.iter().collect::<Vec<_>>(), [1, 2, 1 << 40, 1 << 50]);
+    /// ```
+    pub fn union_with(&mut self, other: &Self) {
+        for (&key, theirs) in &other.chunks {
+            match self.chunks.entry(key) {
+                Entry::Occupied(mut entry) => {
+                    for (a, &b) in entry.get_mut().iter_mut().zip(theirs) {
+                        *a = *a | b;
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(theirs.clone());
+                }
+            }
+        }
+    }
+
+    /// Intersects in-place with the specified other set.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::SparseBitSet;
+    ///
+    /// let mut a: SparseBitSet = [1, 2, 1 << 40].iter().cloned().collect();
+    /// let b: SparseBitSet = [2, 1 << 40, 1 << 50].iter().cloned().collect();
+    /// a.intersect_with(&b);
+    /// assert_eq!(a.iter().collect::<Vec<_>>(), [2, 1 << 40]);
+    /// ```
+    pub fn intersect_with(&mut self, other: &Self) {
+        self.chunks.retain(|key, blocks| match other.chunks.get(key) {
+            Some(theirs) => {
+                for (a, &b) in blocks.iter_mut().zip(theirs) {
+                    *a = *a & b;
+                }
+                !is_zero(blocks)
+            }
+            None => false,
+        });
+    }
+
+    /// Makes this set the difference with the specified other set in-place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::SparseBitSet;
+    ///
+    /// let mut a: SparseBitSet = [1, 2, 1 << 40].iter().cloned().collect();
+    /// let b: SparseBitSet = [2, 1 << 40].iter().cloned().collect();
+    /// a.difference_with(&b);
+    /// assert_eq!(a.iter().collect::<Vec<_>>(), [1]);
+    /// ```
+    pub fn difference_with(&mut self, other: &Self) {
+        for (key, theirs) in &other.chunks {
+            if let Entry::Occupied(mut entry) = self.chunks.entry(*key) {
+                for (a, &b) in entry.get_mut().iter_mut().zip(theirs) {
+                    *a = *a & !b;
+                }
+                if is_zero(entry.get()) {
+                    entry.remove();
+                }
+            }
+        }
+    }
+
+    /// Makes this set the symmetric difference with the specified other set
+    /// in-place.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::SparseBitSet;
+    ///
+    /// let mut a: SparseBitSet = [1, 2].iter().cloned().collect();
+    /// let b: SparseBitSet = [2, 1 << 40].iter().cloned().collect();
+    /// a.symmetric_difference_with(&b);
+    /// assert_eq!(a.iter().collect::<Vec<_>>(), [1, 1 << 40]);
+    /// ```
+    pub fn symmetric_difference_with(&mut self, other: &Self) {
+        for (&key, theirs) in &other.chunks {
+            match self.chunks.entry(key) {
+                Entry::Occupied(mut entry) => {
+                    for (a, &b) in entry.get_mut().iter_mut().zip(theirs) {
+                        *a = *a ^ b;
+                    }
+                    if is_zero(entry.get()) {
+                        entry.remove();
+                    }
+                }
+                Entry::Vacant(entry) => {
+                    entry.insert(theirs.clone());
+                }
+            }
+        }
+    }
+
+    /// Returns `true` if the set is a subset of another.
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.chunks.iter().all(|(key, blocks)| match other.chunks.get(key) {
+            Some(theirs) => blocks.iter().zip(theirs).all(|(&a, &b)| a & b == a),
+            None => false,
+        })
+    }
+
+    /// Returns `true` if the set has no elements in common with `other`.
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.chunks.iter().all(|(key, blocks)| match other.chunks.get(key) {
+            Some(theirs) => blocks.iter().zip(theirs).all(|(&a, &b)| a & b == B::zero()),
+            None => true,
+        })
+    }
+
+    /// Returns a `BitSet` holding the values of this set.
+    pub fn to_bit_set(&self) -> BitSet<B> {
+        let mut blocks = Vec::new();
+        for (&key, chunk) in &self.chunks {
+            blocks.resize(key * chunk_len::<B>(), B::zero());
+            blocks.extend_from_slice(chunk);
+        }
+        BitSet::from_blocks(blocks)
+    }
+}
+
+impl<'a, B: BitBlock> From<&'a BitSet<B>> for SparseBitSet<B> {
+    fn from(set: &'a BitSet<B>) -> Self {
+        let chunks = set.as_block_slice().chunks(chunk_len::<B>()).enumerate()
+            .filter(|&(_, blocks)| !is_zero(blocks))
+            .map(|(key, blocks)| {
+                let mut blocks = blocks.to_vec();
+                blocks.resize(chunk_len::<B>(), B::zero());
+                (key, blocks)
+            })
+            .collect();
+        SparseBitSet { chunks }
+    }
+}
+
+impl<B: BitBlock> FromIterator<usize> for SparseBitSet<B> {
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        let mut set = Self::default();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<B: BitBlock> Extend<usize> for SparseBitSet<B> {
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<'a, B: BitBlock> IntoIterator for &'a SparseBitSet<B> {
+    type Item = usize;
+    type IntoIter = SparseIter<'a, B>;
+
+    fn into_iter(self) -> SparseIter<'a, B> {
+        self.iter()
+    }
+}
+
+impl<B: BitBlock> fmt::Debug for SparseBitSet<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_set().entries(self).finish()
+    }
+}
+
+/// An iterator for `SparseBitSet`.
+#[derive(Clone)]
+pub struct SparseIter<'a, B: 'a> {
+    chunks: btree_map::Iter<'a, usize, Vec<B>>,
+    /// The first value of the current chunk.
+    base: usize,
+    values: BlockIter<Cloned<slice::Iter<'a, B>>, B>,
+}
+
+impl<'a, B: BitBlock> Iterator for SparseIter<'a, B> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        loop {
+            if let Some(value) = self.values.next() {
+                return Some(self.base + value);
+            }
+            let (&key, blocks) = self.chunks.next()?;
+            self.base = key * CHUNK_BITS;
+            self.values = BlockIter::from_blocks(blocks.iter().cloned());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::SparseBitSet;
+    use BitSet;
+
+    #[test]
+    fn test_sparse_bit_set_insert_remove() {
+        let mut s: SparseBitSet<u8> = SparseBitSet::default();
+        assert!(s.insert(usize::MAX));
+        assert!(s.insert(0));
+        assert!(!s.insert(0));
+        assert_eq!(s.chunk_count(), 2);
+        assert_eq!(s.len(), 2);
+        assert_eq!(s.iter().collect::<Vec<_>>(), [0, usize::MAX]);
+
+        assert!(s.remove(usize::MAX));
+        assert!(!s.remove(usize::MAX));
+        assert_eq!(s.chunk_count(), 1);
+        assert!(s.remove(0));
+        assert!(s.is_empty());
+        assert_eq!(s, SparseBitSet::default());
+    }
+
+    #[test]
+    fn test_sparse_bit_set_operations() {
+        let a_values: Vec<usize> = (0..3000).filter(|x| x % 3 == 0).collect();
+        let b_values: Vec<usize> = (1000..5000).filter(|x| x % 5 == 0).collect();
+        let (a, b): (SparseBitSet<u64>, SparseBitSet<u64>) =
+            (a_values.iter().cloned().collect(), b_values.iter().cloned().collect());
+        let (da, db) = (a.to_bit_set(), b.to_bit_set());
+        assert_eq!(SparseBitSet::from(&da), a);
+        assert_eq!(da.iter().collect::<Vec<_>>(), a_values);
+
+        let mut x = a.clone();
+        x.union_with(&b);
+        let mut expected = da.clone();
+        expected.union_with(&db);
+        assert_eq!(x.to_bit_set(), expected);
+
+        let mut x = a.clone();
+        x.intersect_with(&b);
+        let mut expected = da.clone();
+        expected.intersect_with(&db);
+        assert_eq!(x.to_bit_set(), expected);
+        assert!(x.is_subset(&a) && x.is_subset(&b));
+
+        let mut x = a.clone();
+        x.difference_with(&b);
+        let mut expected = da.clone();
+        expected.difference_with(&db);
+        assert_eq!(x.to_bit_set(), expected);
+        assert!(x.is_disjoint(&b));
+
+        let mut x = a.clone();
+        x.symmetric_difference_with(&b);
+        let mut expected = da.clone();
+        expected.symmetric_difference_with(&db);
+        assert_eq!(x.to_bit_set(), expected);
+
+        x.symmetric_difference_with(&x.clone());
+        assert!(x.is_empty());
+        let empty: BitSet<u64> = BitSet::default();
+        assert_eq!(SparseBitSet::from(&empty), x);
+    }
+}
@@ -0,0 +1,438 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Sets over pluggable block storage.
+
+use alloc::vec::Vec;
+use core::fmt;
+
+use bit_vec::BitBlock;
+
+use {find_bit, kernels, BitSet, BitSetView, LimitError, ViewIter};
+
+/// Storage holding the blocks of a [`GenericBitSet`](struct.GenericBitSet.html).
+///
+/// The element `i * B::bits() + j` is in the set if bit `j` of block `i` is
+/// set.
+pub trait BlockStorage {
+    /// The type of the blocks.
+    type Block: BitBlock;
+
+    /// Returns the blocks.
+    fn blocks(&self) -> &[Self::Block];
+}
+
+/// Storage whose blocks can be changed, and possibly grown.
+pub trait BlockStorageMut: BlockStorage {
+    /// Returns the blocks, mutably.
+    fn blocks_mut(&mut self) -> &mut [Self::Block];
+
+    /// Grows the storage to at least `len` blocks, the new ones being zero.
+    /// Returns `false`, leaving the storage unchanged, if it can't hold that
+    /// many blocks.
+    fn grow(&mut self, len: usize) -> bool;
+}
+
+impl<B: BitBlock> BlockStorage for Vec<B> {
+    type Block = B;
+
+    #[inline]
+    fn blocks(&self) -> &[B] {
+        self
+    }
+}
+
+impl<B: BitBlock> BlockStorageMut for Vec<B> {
+    #[inline]
+    fn blocks_mut(&mut self) -> &mut [B] {
+        self
+    }
+
+    fn grow(&mut self, len: usize) -> bool {
+        if len > self.len() {
+            self.resize(len, B::zero());
+        }
+        true
+    }
+}
+
+impl<B: BitBlock, const N: usize> BlockStorage for [B; N] {
+    type Block = B;
+
+    #[inline]
+    fn blocks(&self) -> &[B] {
+        self
+    }
+}
+
+impl<B: BitBlock, const N: usize> BlockStorageMut for [B; N] {
+    #[inline]
+    fn blocks_mut(&mut self) -> &mut [B] {
+        self
+    }
+
+    #[inline]
+    fn grow(&mut self, len: usize) -> bool {
+        len <= N
+    }
+}
+
+impl<B: BitBlock> BlockStorage for [B] {
+    type Block = B;
+
+    #[inline]
+    fn blocks(&self) -> &[B] {
+        self
+    }
+}
+
+impl<B: BitBlock> BlockStorage for &[B] {
+    type Block = B;
+
+    #[inline]
+    fn blocks(&self) -> &[B] {
+        self
+    }
+}
+
+impl<B: BitBlock> BlockStorage for &mut [B] {
+    type Block = B;
+
+    #[inline]
+    fn blocks(&self) -> &[B] {
+        self
+    }
+}
+
+impl<B: BitBlock> BlockStorageMut for &mut [B] {
+    #[inline]
+    fn blocks_mut(&mut self) -> &mut [B] {
+        self
+    }
+
+    #[inline]
+    fn grow(&mut self, len: usize) -> bool {
+        len <= self.len()
+    }
+}
+
+impl<B: BitBlock> BlockStorage for BitSet<B> {
+    type Block = B;
+
+    #[inline]
+    fn blocks(&self) -> &[B] {
+        self.as_block_slice()
+    }
+}
+
+impl<'a, B: BitBlock> BlockStorage for BitSetView<'a, B> {
+    type Block = B;
+
+    #[inline]
+    fn blocks(&self) -> &[B] {
+        self.as_block_slice()
+    }
+}
+
+/// Returns the number of blocks up to the last non-zero one.
+fn used_len<B: BitBlock>(blocks: &[B]) -> usize {
+    blocks.iter().rposition(|&block| block != B::zero()).map_or(0, |i| i + 1)
+}
+
+/// A set over any [`BlockStorage`](trait.BlockStorage.html), such as a
+/// `Vec`, an array or a borrowed slice, running the same algorithms on all
+/// of them.
+///
+/// The set can hold values up to the bits of its blocks, and grows its
+/// storage when it can. Operations needing more room than fixed-size
+/// storage offers fail with a [`LimitError`](struct.LimitError.html) and
+/// leave the set unchanged. The set operations accept any storage for the
+/// other operand.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::{BitSet, GenericBitSet};
+///
+/// let mut fixed = GenericBitSet::new([0u32; 2]);
+/// assert_eq!(fixed.insert(10), Ok(true));
+/// assert!(fixed.insert(64).is_err());
+///
+/// let heap: BitSet = [1, 10, 40].iter().cloned().collect();
+/// fixed.union_with(&heap).unwrap();
+/// assert_eq!(fixed.iter().collect::<Vec<_>>(), [1, 10, 40]);
+///
+/// let mut growing = GenericBitSet::new(Vec::<u32>::new());
+/// growing.insert(1000).unwrap();
+/// assert!(growing.is_superset(&GenericBitSet::new([0u32; 0])));
+/// ```
+#[derive(Clone, Default)]
+pub struct GenericBitSet<S> {
+    storage: S,
+}
+
+/// A set over caller-provided blocks, which never allocates.
+///
+/// Inserting a value past the blocks, or a set op needing more of them,
+/// fails with a [`LimitError`](struct.LimitError.html) instead of growing.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BitSetMut;
+///
+/// let mut buffer = [0u32; 2];
+/// let mut s = BitSetMut::new(&mut buffer[..]);
+/// assert_eq!(s.insert(3), Ok(true));
+/// assert!(s.insert(64).is_err());
+/// s.union_with(&[0b11u32][..]).unwrap();
+/// assert_eq!(s.iter().collect::<Vec<_>>(), [0, 1, 3]);
+/// assert_eq!(buffer, [0b1011, 0]);
+/// ```
+pub type BitSetMut<'a, B = u32> = GenericBitSet<&'a mut [B]>;
+
+impl<S: BlockStorage> GenericBitSet<S> {
+    /// Creates a set holding the values whose bits are set in `storage`.
+    #[inline]
+    pub fn new(storage: S) -> Self {
+        GenericBitSet { storage }
+    }
+
+    /// Returns the storage.
+    #[inline]
+    pub fn storage(&self) -> &S {
+        &self.storage
+    }
+
+    /// Returns the storage, dropping the set.
+    #[inline]
+    pub fn into_storage(self) -> S {
+        self.storage
+    }
+
+    /// Returns the number of values the storage can currently hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.storage.blocks().len() * S::Block::bits()
+    }
+
+    /// Returns a read-only view of the set.
+    #[inline]
+    pub fn view(&self) -> BitSetView<'_, S::Block> {
+        BitSetView::from_blocks(self.storage.blocks())
+    }
+
+    /// Returns `true` if the set contains the specified value.
+    #[inline]
+    pub fn contains(&self, value: usize) -> bool {
+        self.view().contains(value)
+    }
+
+    /// Returns the number of values in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.view().len()
+    }
+
+    /// Returns `true` if the set holds no value.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.view().is_empty()
+    }
+
+    /// Iterator over each value of the set, in ascending order.
+    #[inline]
+    pub fn iter(&self) -> ViewIter<'_, S::Block> {
+        self.view().iter()
+    }
+
+    /// Returns `true` if the set has no value in common with `other`.
+    pub fn is_disjoint<T>(&self, other: &GenericBitSet<T>) -> bool
+        where T: BlockStorage<Block = S::Block>
+    {
+        kernels::is_disjoint(self.storage.blocks(), other.storage.blocks())
+    }
+
+    /// Returns `true` if every value of the set is in `other`.
+    pub fn is_subset<T>(&self, other: &GenericBitSet<T>) -> bool
+        where T: BlockStorage<Block = S::Block>
+    {
+        kernels::is_subset(self.storage.blocks(), other.storage.blocks())
+    }
+
+    /// Returns `true` if every value of `other` is in the set.
+    #[inline]
+    pub fn is_superset<T>(&self, other: &GenericBitSet<T>) -> bool
+        where T: BlockStorage<Block = S::Block>
+    {
+        other.is_subset(self)
+    }
+}
+
+impl<S: BlockStorageMut> GenericBitSet<S> {
+    /// Grows the storage to `len` blocks, or returns the error for the
+    /// first value of `blocks` past the capacity.
+    fn reserve_blocks(&mut self, len: usize, blocks: &[S::Block]) -> Result<(), LimitError> {
+        if len <= self.storage.blocks().len() || self.storage.grow(len) {
+            return Ok(());
+        }
+        let capacity = self.capacity();
+        let value = find_bit(blocks, capacity, true).unwrap_or(capacity);
+        Err(LimitError::new(value, capacity))
+    }
+
+    /// Adds a value to the set. Returns `true` if the value was not already
+    /// present in the set, or an error if the storage can't hold it.
+    pub fn insert(&mut self, value: usize) -> Result<bool, LimitError> {
+        let bits = S::Block::bits();
+        if value >= self.capacity() && !self.storage.grow(value / bits + 1) {
+            return Err(LimitError::new(value, self.capacity()));
+        }
+        Ok(kernels::insert(self.storage.blocks_mut(), value))
+    }
+
+    /// Removes a value from the set. Returns `true` if the value was
+    /// present in the set.
+    pub fn remove(&mut self, value: usize) -> bool {
+        kernels::remove(self.storage.blocks_mut(), value)
+    }
+
+    /// Removes all values from the set, keeping the storage.
+    pub fn clear(&mut self) {
+        for block in self.storage.blocks_mut() {
+            *block = S::Block::zero();
+        }
+    }
+
+    /// Unions in-place with `other`, or fails if the storage can't hold its
+    /// values.
+    pub fn union_with<T>(&mut self, other: &T) -> Result<(), LimitError>
+        where T: BlockStorage<Block = S::Block> + ?Sized
+    {
+        let b = other.blocks();
+        self.reserve_blocks(used_len(b), b)?;
+        kernels::apply(self.storage.blocks_mut(), b, kernels::Op::Or);
+        Ok(())
+    }
+
+    /// Intersects in-place with `other`.
+    pub fn intersect_with<T>(&mut self, other: &T) where T: BlockStorage<Block = S::Block> + ?Sized {
+        kernels::intersect(self.storage.blocks_mut(), other.blocks());
+    }
+
+    /// Removes the values of `other` from the set.
+    pub fn difference_with<T>(&mut self, other: &T) where T: BlockStorage<Block = S::Block> + ?Sized {
+        kernels::apply(self.storage.blocks_mut(), other.blocks(), kernels::Op::AndNot);
+    }
+
+    /// Makes the set the symmetric difference with `other`, or fails if the
+    /// storage can't hold its values.
+    pub fn symmetric_difference_with<T>(&mut self, other: &T) -> Result<(), LimitError>
+        where T: BlockStorage<Block = S::Block> + ?Sized
+    {
+        let b = other.blocks();
+        self.reserve_blocks(used_len(b), b)?;
+        kernels::apply(self.storage.blocks_mut(), b, kernels::Op::Xor);
+        Ok(())
+    }
+}
+
+impl<S: BlockStorage> BlockStorage for GenericBitSet<S> {
+    type Block = S::Block;
+
+    #[inline]
+    fn blocks(&self) -> &[S::Block] {
+        self.storage.blocks()
+    }
+}
+
+impl<'a, S: BlockStorage> IntoIterator for &'a GenericBitSet<S> {
+    type Item = usize;
+    type IntoIter = ViewIter<'a, S::Block>;
+
+    #[inline]
+    fn into_iter(self) -> ViewIter<'a, S::Block> {
+        self.iter()
+    }
+}
+
+impl<S: BlockStorage> fmt::Debug for GenericBitSet<S> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_set().entries(self.iter()).finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use BitSet;
+    use super::{BitSetMut, GenericBitSet};
+
+    #[test]
+    fn test_generic_bit_set_fixed() {
+        let mut s = GenericBitSet::new([0u8; 2]);
+        assert_eq!(s.capacity(), 16);
+        assert_eq!(s.insert(3), Ok(true));
+        assert_eq!(s.insert(3), Ok(false));
+        let err = s.insert(16).unwrap_err();
+        assert_eq!((err.value(), err.limit()), (16, 16));
+
+        let other: BitSet<u8> = [4, 15, 20].iter().cloned().collect();
+        let err = s.union_with(&other).unwrap_err();
+        assert_eq!(err.value(), 20);
+        assert_eq!(s.iter().collect::<Vec<_>>(), [3]);
+
+        let small: BitSet<u8> = [4, 15].iter().cloned().collect();
+        s.union_with(&small).unwrap();
+        s.symmetric_difference_with(&[0b0001_1000u8][..]).unwrap();
+        assert_eq!(s.iter().collect::<Vec<_>>(), [15]);
+        assert!(s.remove(15) && !s.remove(15) && !s.remove(100));
+        assert!(s.is_empty());
+    }
+
+    #[test]
+    fn test_generic_bit_set_ops() {
+        let mut a = GenericBitSet::new(Vec::<u8>::new());
+        let mut blocks = [0u8; 4];
+        let mut b = GenericBitSet::new(&mut blocks[..]);
+        for &value in &[1, 9, 30] {
+            a.insert(value).unwrap();
+        }
+        for &value in &[9, 10] {
+            b.insert(value).unwrap();
+        }
+        assert_eq!(a.len(), 3);
+        assert!(!a.is_disjoint(&b) && !a.is_subset(&b));
+
+        a.union_with(&b).unwrap();
+        assert_eq!(a.iter().collect::<Vec<_>>(), [1, 9, 10, 30]);
+        assert!(a.is_superset(&b));
+        b.intersect_with(&a);
+        b.difference_with(&[0u8, 0b10][..]);
+        assert_eq!(format!("{:?}", b), "{10}");
+        a.intersect_with(&[0b10u8][..]);
+        assert_eq!(a.iter().collect::<Vec<_>>(), [1]);
+        assert_eq!(a.storage().len(), 4);
+        b.clear();
+        assert!(b.is_empty() && b.is_disjoint(&a));
+    }
+
+    #[test]
+    fn test_bit_set_mut() {
+        let mut buffer = [0xffu8, 0];
+        {
+            let mut s = BitSetMut::new(&mut buffer[..]);
+            assert_eq!(s.len(), 8);
+            assert!(s.remove(0) && s.insert(9).unwrap());
+            let err = s.symmetric_difference_with(&[0u8, 0, 1][..]).unwrap_err();
+            assert_eq!((err.value(), err.limit()), (16, 16));
+            s.difference_with(&[0xf0u8][..]);
+            assert_eq!(s.iter().collect::<Vec<_>>(), [1, 2, 3, 9]);
+        }
+        assert_eq!(buffer, [0x0e, 0x02]);
+    }
+}
@@ -0,0 +1,234 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A set recording the values inserted and removed since the last check.
+
+use alloc::vec::Vec;
+use core::iter::FromIterator;
+use core::ops::Deref;
+
+use bit_vec::BitBlock;
+
+use BitSet;
+
+/// The values inserted into and removed from a `TrackedBitSet`, as returned
+/// by [`take_changes`](struct.TrackedBitSet.html#method.take_changes).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Changes<B: BitBlock = u32> {
+    /// The values present now that weren't before.
+    pub inserted: BitSet<B>,
+    /// The values present before that aren't now.
+    pub removed: BitSet<B>,
+}
+
+impl<B: BitBlock> Changes<B> {
+    /// Returns `true` if no value was inserted or removed.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.inserted.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// A `BitSet` recording which values were inserted and removed since the
+/// last call to [`take_changes`](#method.take_changes).
+///
+/// The first change to a block marks it dirty and saves its previous
+/// content, so taking the changes only looks at the dirty blocks, and a
+/// value inserted then removed is no change at all. All the read-only
+/// methods of `BitSet` are available through `Deref`.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::TrackedBitSet;
+///
+/// let mut s = TrackedBitSet::new();
+/// s.insert(1);
+/// s.insert(2);
+/// s.take_changes();
+///
+/// s.insert(100);
+/// s.remove(1);
+/// s.insert(5);
+/// s.remove(5);
+///
+/// let changes = s.take_changes();
+/// assert_eq!(changes.inserted.iter().collect::<Vec<_>>(), [100]);
+/// assert_eq!(changes.removed.iter().collect::<Vec<_>>(), [1]);
+/// assert!(s.take_changes().is_empty());
+/// ```
+#[derive(Debug)]
+pub struct TrackedBitSet<B: BitBlock = u32> {
+    set: BitSet<B>,
+    /// The indices of the blocks changed since the last `take_changes`.
+    dirty: BitSet,
+    /// The previous content of each dirty block, with its index.
+    saved: Vec<(usize, B)>,
+}
+
+impl<B: BitBlock> Clone for TrackedBitSet<B> {
+    #[inline]
+    fn clone(&self) -> Self {
+        TrackedBitSet { set: self.set.clone(), dirty: self.dirty.clone(), saved: self.saved.clone() }
+    }
+
+    #[inline]
+    fn clone_from(&mut self, other: &Self) {
+        self.set.clone_from(&other.set);
+        self.dirty.clone_from(&other.dirty);
+        self.saved.clone_from(&other.saved);
+    }
+}
+
+impl<B: BitBlock> Default for TrackedBitSet<B> {
+    #[inline]
+    fn default() -> Self {
+        TrackedBitSet::from_bit_set(BitSet::default())
+    }
+}
+
+impl TrackedBitSet<u32> {
+    /// Creates a new empty `TrackedBitSet` with no changes.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B: BitBlock> TrackedBitSet<B> {
+    /// Wraps `set`, with no changes recorded yet.
+    #[inline]
+    pub fn from_bit_set(set: BitSet<B>) -> Self {
+        TrackedBitSet { set, dirty: BitSet::new(), saved: Vec::new() }
+    }
+
+    /// Saves the content of the block at `index` if it isn't dirty yet.
+    fn touch(&mut self, index: usize) {
+        if self.dirty.insert(index) {
+            let block = self.set.as_block_slice().get(index).cloned().unwrap_or(B::zero());
+            self.saved.push((index, block));
+        }
+    }
+
+    /// Adds a value to the set. Returns `true` if the value was not already
+    /// present in the set.
+    pub fn insert(&mut self, value: usize) -> bool {
+        if self.set.contains(value) {
+            return false;
+        }
+        self.touch(value / B::bits());
+        self.set.insert(value)
+    }
+
+    /// Removes a value from the set. Returns `true` if the value was
+    /// present in the set.
+    pub fn remove(&mut self, value: usize) -> bool {
+        if !self.set.contains(value) {
+            return false;
+        }
+        self.touch(value / B::bits());
+        self.set.remove(value)
+    }
+
+    /// Removes all values from the set.
+    pub fn clear(&mut self) {
+        for index in 0..self.set.as_block_slice().len() {
+            if self.set.as_block_slice()[index] != B::zero() {
+                self.touch(index);
+            }
+        }
+        self.set.clear();
+    }
+
+    /// Returns the indices of the blocks changed since the last call to
+    /// [`take_changes`](#method.take_changes).
+    #[inline]
+    pub fn dirty_blocks(&self) -> &BitSet {
+        &self.dirty
+    }
+
+    /// Returns the values inserted and removed since the last call, or since
+    /// the set was created, and starts recording anew.
+    pub fn take_changes(&mut self) -> Changes<B> {
+        let nblocks = self.dirty.iter().last().map_or(0, |index| index + 1);
+        let mut inserted = alloc::vec![B::zero(); nblocks];
+        let mut removed = alloc::vec![B::zero(); nblocks];
+        for (index, old) in self.saved.drain(..) {
+            let new = self.set.as_block_slice().get(index).cloned().unwrap_or(B::zero());
+            inserted[index] = new & !old;
+            removed[index] = old & !new;
+        }
+        self.dirty.clear();
+        Changes { inserted: BitSet::from_blocks(inserted), removed: BitSet::from_blocks(removed) }
+    }
+
+    /// Returns the underlying `BitSet`, dropping the changes.
+    #[inline]
+    pub fn into_inner(self) -> BitSet<B> {
+        self.set
+    }
+}
+
+impl<B: BitBlock> Deref for TrackedBitSet<B> {
+    type Target = BitSet<B>;
+
+    #[inline]
+    fn deref(&self) -> &BitSet<B> {
+        &self.set
+    }
+}
+
+impl<B: BitBlock> Extend<usize> for TrackedBitSet<B> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = usize>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<B: BitBlock> FromIterator<usize> for TrackedBitSet<B> {
+    /// Collects the values into a set with no changes recorded.
+    fn from_iter<I: IntoIterator<Item = usize>>(iter: I) -> Self {
+        TrackedBitSet::from_bit_set(iter.into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::TrackedBitSet;
+
+    #[test]
+    fn test_tracked_bit_set() {
+        let mut s: TrackedBitSet<u8> = [1, 9, 17].iter().cloned().collect();
+        assert!(s.take_changes().is_empty());
+
+        assert!(s.insert(2));
+        assert!(!s.insert(2));
+        assert!(s.remove(9));
+        assert!(!s.remove(9));
+        assert!(s.insert(100));
+        assert!(s.insert(30) && s.remove(30));
+        assert_eq!(s.dirty_blocks().iter().collect::<Vec<_>>(), [0, 1, 3, 12]);
+
+        let changes = s.take_changes();
+        assert_eq!(changes.inserted.iter().collect::<Vec<_>>(), [2, 100]);
+        assert_eq!(changes.removed.iter().collect::<Vec<_>>(), [9]);
+        assert!(s.dirty_blocks().is_empty());
+        assert_eq!(s.iter().collect::<Vec<_>>(), [1, 2, 17, 100]);
+
+        s.clear();
+        s.extend(vec![17, 50]);
+        let changes = s.take_changes();
+        assert_eq!(changes.inserted.iter().collect::<Vec<_>>(), [50]);
+        assert_eq!(changes.removed.iter().collect::<Vec<_>>(), [1, 2, 100]);
+
+        let t = s.clone();
+        assert_eq!(t.into_inner().iter().collect::<Vec<_>>(), [17, 50]);
+    }
+}
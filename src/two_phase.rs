@@ -0,0 +1,186 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A replicated set converging by merging states.
+
+use bit_vec::BitBlock;
+
+use {BitSet, Difference};
+
+/// A two-phase set, the CRDT made of a set of added values and a set of
+/// removed ones, so that replicas converge by exchanging and merging their
+/// states in any order.
+///
+/// A value is present if it was added and not removed. Only a value
+/// observed as present can be removed, and the removal wins over any
+/// concurrent or later addition: a removed value can't be added back.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::TwoPhaseBitSet;
+///
+/// let mut a = TwoPhaseBitSet::new();
+/// a.insert(1);
+/// a.insert(2);
+///
+/// let mut b = a.clone();
+/// b.remove(1);
+/// a.insert(3);
+///
+/// a.merge(&b);
+/// b.merge(&a);
+/// assert_eq!(a, b);
+/// assert_eq!(a.iter().collect::<Vec<_>>(), [2, 3]);
+/// ```
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TwoPhaseBitSet<B: BitBlock = u32> {
+    added: BitSet<B>,
+    removed: BitSet<B>,
+}
+
+impl<B: BitBlock> Default for TwoPhaseBitSet<B> {
+    #[inline]
+    fn default() -> Self {
+        TwoPhaseBitSet { added: BitSet::default(), removed: BitSet::default() }
+    }
+}
+
+impl TwoPhaseBitSet<u32> {
+    /// Creates a new empty `TwoPhaseBitSet`.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+impl<B: BitBlock> TwoPhaseBitSet<B> {
+    /// Creates a set from the states of its added and removed values, as
+    /// returned by [`added`](#method.added) and [`removed`](#method.removed).
+    ///
+    /// Removed values missing from `added` are added to it.
+    pub fn from_parts(mut added: BitSet<B>, removed: BitSet<B>) -> Self {
+        added.union_with(&removed);
+        TwoPhaseBitSet { added, removed }
+    }
+
+    /// Returns the values ever added, present or removed since.
+    #[inline]
+    pub fn added(&self) -> &BitSet<B> {
+        &self.added
+    }
+
+    /// Returns the values removed, which can't be added back.
+    #[inline]
+    pub fn removed(&self) -> &BitSet<B> {
+        &self.removed
+    }
+
+    /// Returns `true` if the value was added and not removed.
+    #[inline]
+    pub fn contains(&self, value: usize) -> bool {
+        self.added.contains(value) && !self.removed.contains(value)
+    }
+
+    /// Adds a value to the set. Returns `true` if the value was neither
+    /// present nor removed before.
+    #[inline]
+    pub fn insert(&mut self, value: usize) -> bool {
+        !self.removed.contains(value) && self.added.insert(value)
+    }
+
+    /// Removes a present value from the set, for good. Returns `true` if the
+    /// value was present.
+    #[inline]
+    pub fn remove(&mut self, value: usize) -> bool {
+        self.added.contains(value) && self.removed.insert(value)
+    }
+
+    /// Merges the state of another replica into this one.
+    ///
+    /// Merging is commutative, associative and idempotent, so replicas
+    /// having merged the same states hold the same values.
+    pub fn merge(&mut self, other: &Self) {
+        self.added.union_with(&other.added);
+        self.removed.union_with(&other.removed);
+    }
+
+    /// Returns the number of present values.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.iter().count()
+    }
+
+    /// Returns `true` if no value is present.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.iter().next().is_none()
+    }
+
+    /// Iterator over each present value, in ascending order.
+    #[inline]
+    pub fn iter(&self) -> Difference<'_, B> {
+        self.added.difference(&self.removed)
+    }
+
+    /// Returns the present values as a `BitSet`.
+    pub fn to_bit_set(&self) -> BitSet<B> {
+        let mut set = self.added.clone();
+        set.difference_with(&self.removed);
+        set
+    }
+}
+
+impl<'a, B: BitBlock> IntoIterator for &'a TwoPhaseBitSet<B> {
+    type Item = usize;
+    type IntoIter = Difference<'a, B>;
+
+    #[inline]
+    fn into_iter(self) -> Difference<'a, B> {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use BitSet;
+    use super::TwoPhaseBitSet;
+
+    #[test]
+    fn test_two_phase_bit_set() {
+        let mut a: TwoPhaseBitSet<u8> = TwoPhaseBitSet::default();
+        assert!(a.insert(1) && a.insert(20));
+        assert!(!a.insert(1));
+        assert!(!a.remove(5));
+        assert!(a.remove(1));
+        assert!(!a.remove(1));
+        assert!(!a.insert(1));
+        assert!(!a.contains(1) && a.contains(20));
+        assert_eq!((a.len(), a.is_empty()), (1, false));
+
+        let mut b = a.clone();
+        b.insert(7);
+        b.remove(20);
+        let mut c: TwoPhaseBitSet<u8> = TwoPhaseBitSet::default();
+        c.insert(100);
+
+        let (mut abc, mut cba) = (a.clone(), c.clone());
+        abc.merge(&b);
+        abc.merge(&c);
+        cba.merge(&b);
+        cba.merge(&a);
+        cba.merge(&a);
+        assert_eq!(abc, cba);
+        assert_eq!(abc.iter().collect::<Vec<_>>(), [7, 100]);
+        assert_eq!(abc.to_bit_set().iter().collect::<Vec<_>>(), [7, 100]);
+
+        let parts = TwoPhaseBitSet::from_parts(BitSet::new(), abc.removed().iter().collect());
+        assert!(parts.is_empty());
+        assert_eq!(parts.added().iter().collect::<Vec<_>>(), [1, 20]);
+    }
+}
@@ -0,0 +1,398 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A set of typed indices, such as newtypes around `usize`.
+
+use core::convert::TryFrom;
+use core::fmt;
+use core::hash;
+use core::iter::FromIterator;
+use core::marker::PhantomData;
+
+use bit_vec::BitBlock;
+
+use {BitSet, Difference, Intersection, Iter, SymmetricDifference, Union};
+
+/// A type convertible to and from the position of a bit, used as the
+/// element type of a `TypedBitSet`.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BitIndex;
+///
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// struct NodeId(u32);
+///
+/// impl BitIndex for NodeId {
+///     fn index(self) -> usize { self.0 as usize }
+///     fn from_index(index: usize) -> Self { NodeId(index as u32) }
+/// }
+/// ```
+pub trait BitIndex: Copy {
+    /// Returns the position of the bit representing this value.
+    fn index(self) -> usize;
+
+    /// Returns the value represented by the bit at `index`.
+    fn from_index(index: usize) -> Self;
+}
+
+// The conversions panic rather than truncate, so values out of range of
+// either type never alias others.
+macro_rules! bit_index_impl {
+    ($($t:ty),*) => ($(
+        impl BitIndex for $t {
+            #[inline]
+            fn index(self) -> usize { usize::try_from(self).expect("index out of range") }
+            #[inline]
+            fn from_index(index: usize) -> Self { <$t>::try_from(index).expect("index out of range") }
+        }
+    )*)
+}
+
+bit_index_impl!(u8, u16, u32, u64, usize);
+
+/// A `BitSet` whose elements are of type `K` instead of `usize`.
+///
+/// Sets of different index types can't be mixed up, and iteration yields
+/// `K` values. The conversions compile down to nothing for newtypes around
+/// an integer.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::{BitIndex, TypedBitSet};
+///
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// struct NodeId(u32);
+///
+/// impl BitIndex for NodeId {
+///     fn index(self) -> usize { self.0 as usize }
+///     fn from_index(index: usize) -> Self { NodeId(index as u32) }
+/// }
+///
+/// let mut visited = TypedBitSet::new();
+/// visited.insert(NodeId(4));
+/// visited.insert(NodeId(1));
+///
+/// assert!(visited.contains(NodeId(4)));
+/// assert_eq!(visited.iter().collect::<Vec<_>>(), [NodeId(1), NodeId(4)]);
+/// ```
+pub struct TypedBitSet<K, B = u32> {
+    set: BitSet<B>,
+    marker: PhantomData<fn(K) -> K>,
+}
+
+impl<K: BitIndex> TypedBitSet<K, u32> {
+    /// Creates a new empty `TypedBitSet`.
+    #[inline]
+    pub fn new() -> Self {
+        Default::default()
+    }
+}
+
+impl<K: BitIndex, B: BitBlock> TypedBitSet<K, B> {
+    /// Wraps a `BitSet`, reading its elements as indices of `K`.
+    #[inline]
+    pub fn from_bit_set(set: BitSet<B>) -> Self {
+        TypedBitSet { set, marker: PhantomData }
+    }
+
+    /// Returns a reference to the underlying `BitSet`.
+    #[inline]
+    pub fn as_bit_set(&self) -> &BitSet<B> {
+        &self.set
+    }
+
+    /// Returns the underlying `BitSet`.
+    #[inline]
+    pub fn into_bit_set(self) -> BitSet<B> {
+        self.set
+    }
+
+    /// Returns `true` if this set contains the specified value.
+    #[inline]
+    pub fn contains(&self, value: K) -> bool {
+        self.set.contains(value.index())
+    }
+
+    /// Adds a value to the set. Returns `true` if the value was not already
+    /// present in the set.
+    #[inline]
+    pub fn insert(&mut self, value: K) -> bool {
+        self.set.insert(value.index())
+    }
+
+    /// Removes a value from the set. Returns `true` if the value was
+    /// present in the set.
+    #[inline]
+    pub fn remove(&mut self, value: K) -> bool {
+        self.set.remove(value.index())
+    }
+
+    /// Returns the number of values in the set.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.set.len()
+    }
+
+    /// Returns `true` if the set holds no value.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.set.is_empty()
+    }
+
+    /// Removes all values from the set.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.set.clear();
+    }
+
+    /// Iterator over each value in the set, in ascending order of index.
+    #[inline]
+    pub fn iter(&self) -> TypedIter<Iter<'_, B>, K> {
+        TypedIter::new(self.set.iter())
+    }
+
+    /// Iterator over each value in `self` or `other`, in ascending order of
+    /// index.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::TypedBitSet;
+    ///
+    /// let a: TypedBitSet<u16> = vec![1, 2].into_iter().collect();
+    /// let b: TypedBitSet<u16> = vec![2, 3].into_iter().collect();
+    /// assert_eq!(a.union(&b).collect::<Vec<u16>>(), [1, 2, 3]);
+    /// assert_eq!(a.intersection(&b).collect::<Vec<u16>>(), [2]);
+    /// assert_eq!(a.difference(&b).collect::<Vec<u16>>(), [1]);
+    /// assert_eq!(a.symmetric_difference(&b).collect::<Vec<u16>>(), [1, 3]);
+    /// ```
+    #[inline]
+    pub fn union<'a>(&'a self, other: &'a Self) -> TypedIter<Union<'a, B>, K> {
+        TypedIter::new(self.set.union(&other.set))
+    }
+
+    /// Iterator over each value in both `self` and `other`, in ascending
+    /// order of index.
+    #[inline]
+    pub fn intersection<'a>(&'a self, other: &'a Self) -> TypedIter<Intersection<'a, B>, K> {
+        TypedIter::new(self.set.intersection(&other.set))
+    }
+
+    /// Iterator over each value in `self` but not in `other`, in ascending
+    /// order of index.
+    #[inline]
+    pub fn difference<'a>(&'a self, other: &'a Self) -> TypedIter<Difference<'a, B>, K> {
+        TypedIter::new(self.set.difference(&other.set))
+    }
+
+    /// Iterator over each value in exactly one of `self` and `other`, in
+    /// ascending order of index.
+    #[inline]
+    pub fn symmetric_difference<'a>(&'a self, other: &'a Self)
+                                    -> TypedIter<SymmetricDifference<'a, B>, K> {
+        TypedIter::new(self.set.symmetric_difference(&other.set))
+    }
+
+    /// Unions in-place with the specified other set.
+    #[inline]
+    pub fn union_with(&mut self, other: &Self) {
+        self.set.union_with(&other.set);
+    }
+
+    /// Intersects in-place with the specified other set.
+    #[inline]
+    pub fn intersect_with(&mut self, other: &Self) {
+        self.set.intersect_with(&other.set);
+    }
+
+    /// Makes this set the difference with the specified other set in-place.
+    #[inline]
+    pub fn difference_with(&mut self, other: &Self) {
+        self.set.difference_with(&other.set);
+    }
+
+    /// Makes this set the symmetric difference with the specified other set
+    /// in-place.
+    #[inline]
+    pub fn symmetric_difference_with(&mut self, other: &Self) {
+        self.set.symmetric_difference_with(&other.set);
+    }
+
+    /// Returns `true` if the set has no values in common with `other`.
+    #[inline]
+    pub fn is_disjoint(&self, other: &Self) -> bool {
+        self.set.is_disjoint(&other.set)
+    }
+
+    /// Returns `true` if the set is a subset of another.
+    #[inline]
+    pub fn is_subset(&self, other: &Self) -> bool {
+        self.set.is_subset(&other.set)
+    }
+
+    /// Returns `true` if the set is a superset of another.
+    #[inline]
+    pub fn is_superset(&self, other: &Self) -> bool {
+        self.set.is_superset(&other.set)
+    }
+}
+
+impl<K, B: BitBlock> Clone for TypedBitSet<K, B> {
+    #[inline]
+    fn clone(&self) -> Self {
+        TypedBitSet { set: self.set.clone(), marker: PhantomData }
+    }
+
+    #[inline]
+    fn clone_from(&mut self, other: &Self) {
+        self.set.clone_from(&other.set);
+    }
+}
+
+impl<K, B: BitBlock> Default for TypedBitSet<K, B> {
+    #[inline]
+    fn default() -> Self {
+        TypedBitSet { set: BitSet::default(), marker: PhantomData }
+    }
+}
+
+impl<K, B: BitBlock> PartialEq for TypedBitSet<K, B> {
+    #[inline]
+    fn eq(&self, other: &Self) -> bool {
+        self.set == other.set
+    }
+}
+
+impl<K, B: BitBlock> Eq for TypedBitSet<K, B> {}
+
+impl<K, B: BitBlock> hash::Hash for TypedBitSet<K, B> {
+    fn hash<H: hash::Hasher>(&self, state: &mut H) {
+        self.set.hash(state);
+    }
+}
+
+impl<K: BitIndex, B: BitBlock> From<BitSet<B>> for TypedBitSet<K, B> {
+    #[inline]
+    fn from(set: BitSet<B>) -> Self {
+        TypedBitSet::from_bit_set(set)
+    }
+}
+
+impl<K: BitIndex, B: BitBlock> FromIterator<K> for TypedBitSet<K, B> {
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut set = TypedBitSet::default();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<K: BitIndex, B: BitBlock> Extend<K> for TypedBitSet<K, B> {
+    #[inline]
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        self.set.extend(iter.into_iter().map(K::index));
+    }
+}
+
+impl<'a, K: BitIndex, B: BitBlock> IntoIterator for &'a TypedBitSet<K, B> {
+    type Item = K;
+    type IntoIter = TypedIter<Iter<'a, B>, K>;
+
+    fn into_iter(self) -> TypedIter<Iter<'a, B>, K> {
+        self.iter()
+    }
+}
+
+impl<K: BitIndex + fmt::Debug, B: BitBlock> fmt::Debug for TypedBitSet<K, B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_set().entries(self).finish()
+    }
+}
+
+/// An iterator for `TypedBitSet`, turning the indices yielded by the
+/// `BitSet` iterator `I` into values of `K`.
+pub struct TypedIter<I, K> {
+    iter: I,
+    marker: PhantomData<fn() -> K>,
+}
+
+impl<I, K> TypedIter<I, K> {
+    #[inline]
+    fn new(iter: I) -> Self {
+        TypedIter { iter, marker: PhantomData }
+    }
+}
+
+impl<I: Clone, K> Clone for TypedIter<I, K> {
+    #[inline]
+    fn clone(&self) -> Self {
+        TypedIter::new(self.iter.clone())
+    }
+}
+
+impl<I: Iterator<Item = usize>, K: BitIndex> Iterator for TypedIter<I, K> {
+    type Item = K;
+
+    #[inline] fn next(&mut self) -> Option<K> { self.iter.next().map(K::from_index) }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.iter.size_hint() }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use super::{BitIndex, TypedBitSet};
+    use BitSet;
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    struct RegId(u16);
+
+    impl BitIndex for RegId {
+        fn index(self) -> usize { self.0 as usize }
+        fn from_index(index: usize) -> Self { RegId(index as u16) }
+    }
+
+    #[test]
+    fn test_typed_bit_set() {
+        let mut a: TypedBitSet<RegId> = [RegId(3), RegId(70)].iter().cloned().collect();
+        let b: TypedBitSet<RegId> = BitSet::from_range(2..5).into();
+        assert!(a.insert(RegId(5)));
+        assert!(!a.insert(RegId(5)));
+        assert!(a.remove(RegId(70)));
+        assert_eq!(a.len(), 2);
+        assert_eq!(format!("{:?}", a), "{RegId(3), RegId(5)}");
+
+        assert_eq!(b.as_bit_set().len(), 3);
+        let mut c = a.clone();
+        c.intersect_with(&b);
+        assert_eq!(c.iter().collect::<Vec<_>>(), [RegId(3)]);
+        assert!(c.is_subset(&a) && c.is_subset(&b));
+        c.union_with(&b);
+        assert_eq!(c, b);
+        c.difference_with(&a);
+        assert!(c.is_disjoint(&a));
+        c.symmetric_difference_with(&b);
+        assert_eq!(c.into_bit_set().iter().collect::<Vec<_>>(), [3]);
+
+        let ids: TypedBitSet<u32> = vec![7u32, 1].into_iter().collect();
+        assert_eq!((&ids).into_iter().collect::<Vec<u32>>(), [1, 7]);
+    }
+
+    #[test]
+    fn test_bit_index_in_range() {
+        assert_eq!(u8::from_index(255), 255);
+        assert_eq!(255u8.index(), 255);
+        assert_eq!(u64::from_index(usize::MAX).index(), usize::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "index out of range")]
+    fn test_bit_index_out_of_range() {
+        u8::from_index(300);
+    }
+}
@@ -0,0 +1,175 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! A compact encoding of the gaps between the values of a set, as LEB128
+//! varints.
+
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use bit_vec::BitBlock;
+
+use BitSet;
+
+/// The error returned by
+/// [`BitSet::decode_varint_deltas`](struct.BitSet.html#method.decode_varint_deltas).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DecodeVarintError {
+    kind: DecodeErrorKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum DecodeErrorKind {
+    Truncated,
+    Overflow,
+    TooLarge,
+}
+
+impl DecodeVarintError {
+    fn new(kind: DecodeErrorKind) -> Self {
+        DecodeVarintError { kind }
+    }
+}
+
+impl fmt::Display for DecodeVarintError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            DecodeErrorKind::Truncated => fmt.write_str("truncated varint"),
+            DecodeErrorKind::Overflow => fmt.write_str("value overflows usize"),
+            DecodeErrorKind::TooLarge => fmt.write_str("value too large to allocate"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for DecodeVarintError {}
+
+fn write_varint(bytes: &mut Vec<u8>, mut value: usize) {
+    while value >= 0x80 {
+        bytes.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    bytes.push(value as u8);
+}
+
+/// Reads the varint at the start of `bytes`, advancing past it.
+fn read_varint(bytes: &mut &[u8]) -> Result<usize, DecodeVarintError> {
+    let mut value: usize = 0;
+    let mut shift = 0;
+    loop {
+        let (&byte, rest) = bytes.split_first()
+            .ok_or(DecodeVarintError::new(DecodeErrorKind::Truncated))?;
+        *bytes = rest;
+        let low = (byte & 0x7f) as usize;
+        if shift >= usize::BITS || (low << shift) >> shift != low {
+            return Err(DecodeVarintError::new(DecodeErrorKind::Overflow));
+        }
+        value |= low << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+        shift += 7;
+    }
+}
+
+impl<B: BitBlock> BitSet<B> {
+    /// Encodes the values of the set as a stream of LEB128 varints: the
+    /// first value, then the gap minus one between each value and the
+    /// previous one.
+    ///
+    /// Values closer than 128 to the previous one take one byte each, so
+    /// sparse sets encode much smaller than their blocks. The length of the
+    /// set isn't encoded.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use bit_set::BitSet;
+    ///
+    /// let s: BitSet = [3, 4, 1000, 1001].iter().cloned().collect();
+    /// let bytes = s.encode_varint_deltas();
+    /// assert_eq!(bytes, [3, 0, 0xe3, 0x07, 0]);
+    /// assert_eq!(BitSet::decode_varint_deltas(&bytes), Ok(s));
+    /// ```
+    pub fn encode_varint_deltas(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut next = 0;
+        for value in self {
+            write_varint(&mut bytes, value - next);
+            next = value + 1;
+        }
+        bytes
+    }
+
+    /// Decodes the output of
+    /// [`encode_varint_deltas`](#method.encode_varint_deltas).
+    ///
+    /// The length of the set is one past its largest value.
+    ///
+    /// # Errors
+    ///
+    /// Fails if the stream ends within a varint, if a value doesn't fit in
+    /// a `usize`, or if the blocks for a value can't be allocated, so a
+    /// forged stream can't abort the process.
+    pub fn decode_varint_deltas(mut bytes: &[u8]) -> Result<Self, DecodeVarintError> {
+        let mut set = BitSet::default();
+        let mut next: usize = 0;
+        while !bytes.is_empty() {
+            let value = next.checked_add(read_varint(&mut bytes)?)
+                .ok_or(DecodeVarintError::new(DecodeErrorKind::Overflow))?;
+            set.try_insert(value)
+                .map_err(|_| DecodeVarintError::new(DecodeErrorKind::TooLarge))?;
+            // `try_insert` can't allocate for `usize::MAX`, so this can't
+            // overflow
+            next = value + 1;
+        }
+        Ok(set)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use BitSet;
+    use super::{read_varint, write_varint, DecodeErrorKind, DecodeVarintError};
+
+    #[test]
+    fn test_varint() {
+        for &value in &[0, 1, 127, 128, 300, 1 << 20, usize::MAX] {
+            let mut bytes = Vec::new();
+            write_varint(&mut bytes, value);
+            let mut slice = &bytes[..];
+            assert_eq!(read_varint(&mut slice), Ok(value));
+            assert!(slice.is_empty());
+        }
+        let overflow = DecodeVarintError::new(DecodeErrorKind::Overflow);
+        assert_eq!(read_varint(&mut &[0xff; 11][..]), Err(overflow));
+    }
+
+    #[test]
+    fn test_varint_deltas() {
+        let s: BitSet<u8> = [0, 1, 2, 200, 201, 5000].iter().cloned().collect();
+        let bytes = s.encode_varint_deltas();
+        assert_eq!(bytes.len(), 8);
+        let back = BitSet::<u8>::decode_varint_deltas(&bytes).unwrap();
+        assert_eq!(back, s);
+        assert_eq!(back.get_ref().len(), 5001);
+
+        let empty: BitSet = BitSet::new();
+        assert!(empty.encode_varint_deltas().is_empty());
+        assert_eq!(BitSet::decode_varint_deltas(&[]), Ok(empty));
+
+        let truncated = DecodeVarintError::new(DecodeErrorKind::Truncated);
+        assert_eq!(BitSet::<u32>::decode_varint_deltas(&[1, 0x80]), Err(truncated));
+        let mut huge = Vec::new();
+        write_varint(&mut huge, usize::MAX);
+        let too_large = DecodeVarintError::new(DecodeErrorKind::TooLarge);
+        assert_eq!(BitSet::<u32>::decode_varint_deltas(&huge), Err(too_large));
+    }
+}
@@ -0,0 +1,257 @@
+// Licensed under the Apache License, Version 2.0 <LICENSE-APACHE or
+// http://www.apache.org/licenses/LICENSE-2.0> or the MIT license
+// <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your
+// option. This file may not be copied, modified, or distributed
+// except according to those terms.
+
+//! Read-only sets over borrowed blocks, such as memory-mapped files.
+
+use core::fmt;
+use core::iter::Cloned;
+use core::mem;
+use core::slice;
+#[cfg(feature = "std")]
+use std::error::Error;
+
+use bit_vec::BitBlock;
+
+use {block_contains, blocks_for_bits, kernels, BitSet, BlockIter};
+
+/// The error returned when blocks can't be viewed as a set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ViewError {
+    kind: ViewErrorKind,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ViewErrorKind {
+    Length,
+    Alignment,
+    PastLength,
+}
+
+impl ViewError {
+    fn new(kind: ViewErrorKind) -> Self {
+        ViewError { kind }
+    }
+}
+
+impl fmt::Display for ViewError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match self.kind {
+            ViewErrorKind::Length => fmt.write_str("the blocks don't match the length in bits"),
+            ViewErrorKind::Alignment => fmt.write_str("the bytes are not aligned for the block type"),
+            ViewErrorKind::PastLength => fmt.write_str("bits are set past the length"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Error for ViewError {}
+
+/// A read-only set over borrowed blocks, laid out as in a `BitSet`.
+///
+/// The blocks can come from anywhere, such as a memory-mapped file through
+/// [`from_bytes`](#method.from_bytes), and are queried in place without
+/// being copied to the heap.
+///
+/// # Examples
+///
+/// ```
+/// use bit_set::BitSetView;
+///
+/// let blocks: [u32; 2] = [0b1010, 1];
+/// let view = BitSetView::new(&blocks, 33).unwrap();
+/// assert!(view.contains(3) && view.contains(32));
+/// assert_eq!(view.iter().collect::<Vec<_>>(), [1, 3, 32]);
+///
+/// assert!(BitSetView::new(&blocks, 20).is_err());
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BitSetView<'a, B: 'a> {
+    blocks: &'a [B],
+    nbits: usize,
+}
+
+impl<'a, B: BitBlock> BitSetView<'a, B> {
+    /// Views `blocks` as a set of length the bits of all the blocks.
+    pub(crate) fn from_blocks(blocks: &'a [B]) -> Self {
+        BitSetView { blocks, nbits: blocks.len() * B::bits() }
+    }
+
+    /// Views `blocks` as a set of length `nbits`.
+    ///
+    /// # Errors
+    ///
+    /// Fails if there aren't exactly as many blocks as `nbits` needs, or if
+    /// bits at or past `nbits` are set.
+    pub fn new(blocks: &'a [B], nbits: usize) -> Result<Self, ViewError> {
+        if blocks.len() != blocks_for_bits::<B>(nbits) {
+            return Err(ViewError::new(ViewErrorKind::Length));
+        }
+        let used = nbits % B::bits();
+        if used != 0 && blocks[blocks.len() - 1] >> used != B::zero() {
+            return Err(ViewError::new(ViewErrorKind::PastLength));
+        }
+        Ok(BitSetView { blocks, nbits })
+    }
+
+    /// Returns the length in bits of the view, one past the largest value it
+    /// can hold.
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.nbits
+    }
+
+    /// Returns the viewed blocks.
+    #[inline]
+    pub fn as_block_slice(&self) -> &'a [B] {
+        self.blocks
+    }
+
+    /// Returns `true` if the view contains the specified value.
+    #[inline]
+    pub fn contains(&self, value: usize) -> bool {
+        value < self.nbits && block_contains(self.blocks, value)
+    }
+
+    /// Returns the number of values in the view.
+    #[inline]
+    pub fn len(&self) -> usize {
+        kernels::count_ones(self.blocks)
+    }
+
+    /// Returns `true` if the view holds no value.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        kernels::is_empty(self.blocks)
+    }
+
+    /// Iterator over each value of the view, in ascending order.
+    #[inline]
+    pub fn iter(&self) -> ViewIter<'a, B> {
+        ViewIter(BlockIter::from_blocks(self.blocks.iter().cloned()))
+    }
+
+    /// Copies the view into a `BitSet`.
+    pub fn to_bit_set(&self) -> BitSet<B> {
+        let mut set = BitSet::from_blocks(self.blocks.to_vec());
+        set.truncate(self.nbits);
+        set
+    }
+}
+
+macro_rules! from_bytes {
+    ($($block:ty),*) => {$(
+        impl<'a> BitSetView<'a, $block> {
+            /// Views `bytes` as the blocks of a set of length `nbits`, in the
+            /// native byte order, without copying them.
+            ///
+            /// # Errors
+            ///
+            /// Fails if `bytes` isn't aligned for the block type, if it
+            /// doesn't hold exactly as many blocks as `nbits` needs, or if
+            /// bits at or past `nbits` are set.
+            pub fn from_bytes(bytes: &'a [u8], nbits: usize) -> Result<Self, ViewError> {
+                if bytes.as_ptr() as usize % mem::align_of::<$block>() != 0 {
+                    return Err(ViewError::new(ViewErrorKind::Alignment));
+                }
+                if bytes.len() % mem::size_of::<$block>() != 0 {
+                    return Err(ViewError::new(ViewErrorKind::Length));
+                }
+                // Every bit pattern is a valid integer, and the pointer was
+                // checked to be aligned
+                let blocks = unsafe {
+                    slice::from_raw_parts(bytes.as_ptr() as *const $block,
+                                          bytes.len() / mem::size_of::<$block>())
+                };
+                BitSetView::new(blocks, nbits)
+            }
+        }
+    )*}
+}
+
+from_bytes!(u8, u16, u32, u64);
+
+impl<'a, B: BitBlock> IntoIterator for BitSetView<'a, B> {
+    type Item = usize;
+    type IntoIter = ViewIter<'a, B>;
+
+    #[inline]
+    fn into_iter(self) -> ViewIter<'a, B> {
+        self.iter()
+    }
+}
+
+impl<'a, B: BitBlock> fmt::Debug for BitSetView<'a, B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<B: BitBlock> BitSet<B> {
+    /// Returns a read-only view of this set.
+    #[inline]
+    pub fn view(&self) -> BitSetView<'_, B> {
+        BitSetView { blocks: self.as_block_slice(), nbits: self.bit_vec.len() }
+    }
+}
+
+/// An iterator over the values of a `BitSetView`.
+#[derive(Clone)]
+pub struct ViewIter<'a, B: 'a>(BlockIter<Cloned<slice::Iter<'a, B>>, B>);
+
+impl<'a, B: BitBlock> Iterator for ViewIter<'a, B> {
+    type Item = usize;
+
+    #[inline] fn next(&mut self) -> Option<usize> { self.0.next() }
+    #[inline] fn size_hint(&self) -> (usize, Option<usize>) { self.0.size_hint() }
+    #[inline] fn fold<Acc, F>(self, init: Acc, f: F) -> Acc where F: FnMut(Acc, usize) -> Acc { self.0.fold(init, f) }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::vec::Vec;
+
+    use BitSet;
+    use super::{BitSetView, ViewError, ViewErrorKind};
+
+    #[test]
+    fn test_bit_set_view() {
+        let set: BitSet<u8> = [0, 9, 17, 20].iter().cloned().collect();
+        let view = set.view();
+        assert_eq!(view.capacity(), 21);
+        assert_eq!(view.len(), 4);
+        assert!(!view.is_empty());
+        assert!(view.contains(17) && !view.contains(18) && !view.contains(1000));
+        assert_eq!(view.iter().collect::<Vec<_>>(), [0, 9, 17, 20]);
+        assert_eq!(view.to_bit_set(), set);
+        assert_eq!(view.to_bit_set().get_ref().len(), 21);
+        assert_eq!(format!("{:?}", view), "{0, 9, 17, 20}");
+
+        let blocks = set.as_block_slice();
+        assert_eq!(BitSetView::new(blocks, 21), Ok(view));
+        assert_eq!(BitSetView::new(blocks, 24).unwrap().capacity(), 24);
+        assert_eq!(BitSetView::new(blocks, 16), Err(ViewError::new(ViewErrorKind::Length)));
+        assert_eq!(BitSetView::new(blocks, 18), Err(ViewError::new(ViewErrorKind::PastLength)));
+    }
+
+    #[test]
+    fn test_bit_set_view_from_bytes() {
+        let words: [u64; 3] = [1 << 5, 0, 1 << 2];
+        let bytes = unsafe {
+            ::core::slice::from_raw_parts(words.as_ptr() as *const u8, 24)
+        };
+        let view = BitSetView::<u64>::from_bytes(bytes, 131).unwrap();
+        assert_eq!(view.iter().collect::<Vec<_>>(), [5, 130]);
+
+        assert_eq!(BitSetView::<u64>::from_bytes(&bytes[1..9], 64),
+                   Err(ViewError::new(ViewErrorKind::Alignment)));
+        assert_eq!(BitSetView::<u64>::from_bytes(&bytes[..12], 64),
+                   Err(ViewError::new(ViewErrorKind::Length)));
+        assert_eq!(BitSetView::<u64>::from_bytes(&bytes[..16], 130),
+                   Err(ViewError::new(ViewErrorKind::Length)));
+        let u8_view = BitSetView::<u8>::from_bytes(&bytes[..8], 64).unwrap();
+        assert_eq!(u8_view.iter().collect::<Vec<_>>(), [5]);
+    }
+}